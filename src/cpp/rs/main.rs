@@ -10,7 +10,9 @@ use std::process::exit;
 use benchmark::benchmark;
 use search::main_search;
 use serve::serve;
-use twsearch::_internal::cli::args::{get_options_cpp_wrapper, CliCommand};
+use twsearch::_internal::cli::args::{
+    get_options_cpp_wrapper, print_completions_cpp_wrapper, CliCommand,
+};
 
 // TODO: Figure out how to move this out of the main entry file.
 #[cxx::bridge]
@@ -32,8 +34,9 @@ fn main() {
     let args = get_options_cpp_wrapper();
 
     let result = match args.command {
-        CliCommand::Completions(_completions_args) => {
-            panic!("Completions should have been printed during options parsing, followed by program exit.");
+        CliCommand::Completions(completions_args) => {
+            print_completions_cpp_wrapper(&completions_args);
+            Ok(())
         }
         CliCommand::Search(search_command_args) => main_search(
             &search_command_args,
@@ -41,7 +44,8 @@ fn main() {
             &search_command_args
                 .optional
                 .scramble_and_target_pattern_optional_args
-                .scramble_file,
+                .scramble_file_path()
+                .cloned(),
             &search_command_args
                 .optional
                 .scramble_and_target_pattern_optional_args
@@ -77,6 +81,10 @@ fn main() {
             &None,
             &None, // TODO: allow custom target pattern?
         ),
+        CliCommand::Coset(_args) => {
+            println!("This command is not supported for the wrapper CLI");
+            exit(1);
+        }
         CliCommand::Scramble(_args) => {
             println!("This command is not supported for the wrapper CLI");
             exit(1);
@@ -86,6 +94,10 @@ fn main() {
             exit(1);
         }
         CliCommand::Benchmark(benchmark_args) => benchmark(benchmark_args),
+        CliCommand::ListPuzzles | CliCommand::AlgInfo(_) => {
+            println!("This command is not supported for the wrapper CLI");
+            exit(1);
+        }
     };
     if let Err(err) = result {
         eprintln!("{}", err);
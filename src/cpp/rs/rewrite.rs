@@ -4,10 +4,10 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use cubing::kpuzzle::{KPatternData, KPuzzleDefinition};
+use cubing::kpuzzle::{KPatternData, KPuzzle, KPuzzleDefinition};
 use serde::Deserialize;
 use tempfile::NamedTempFile;
-use twsearch::_internal::cli::args::DefOnlyArgs;
+use twsearch::{_internal::cli::args::DefOnlyArgs, experimental_lib_api::KPuzzleSource};
 
 use crate::serialize::{
     serialize_kpuzzle_definition, serialize_scramble_kpattern_data, serialize_scramble_list,
@@ -52,7 +52,7 @@ fn write_rewritten_input_file(
 
 fn rewrite_input_file<T: for<'a> Deserialize<'a>>(
     input_file: &Path,
-    rewrite_fn: fn(T) -> Result<String, String>,
+    rewrite_fn: impl Fn(T) -> Result<String, String>,
     debug_print_serialized_json: bool,
 ) -> Result<(String, TempFileScopeHandle), String> {
     let json = rewrite_fn(read_to_json(input_file)?)?;
@@ -61,7 +61,7 @@ fn rewrite_input_file<T: for<'a> Deserialize<'a>>(
 
 fn must_rewrite_input_file<T: for<'a> Deserialize<'a>>(
     input_file: &Path,
-    rewrite_fn: fn(T) -> Result<String, String>,
+    rewrite_fn: impl Fn(T) -> Result<String, String>,
     debug_print_serialized_json: bool,
 ) -> (String, TempFileScopeHandle) {
     let json = rewrite_fn(read_to_json(input_file).unwrap()).unwrap();
@@ -101,7 +101,13 @@ pub fn rewrite_def_file(
     input_args: &DefOnlyArgs,
     target_pattern_file: &Option<PathBuf>,
 ) -> Result<(String, TempFileScopeHandle), String> {
-    match input_args.def_file.extension().and_then(|ext| ext.to_str()) {
+    if input_args.puzzle.is_some() {
+        return Err("Unsupported flag for twsearch-cpp-wrapper: --puzzle".to_owned());
+    }
+    let Some(def_file) = &input_args.def_file else {
+        return Err("A definition file must be specified.".to_owned());
+    };
+    match def_file.extension().and_then(|ext| ext.to_str()) {
         Some("tws") => {
             if target_pattern_file.is_some() {
                 return Err(
@@ -109,22 +115,19 @@ pub fn rewrite_def_file(
                 );
             };
             Ok((
-                input_args
-                    .def_file
-                    .to_str()
-                    .expect("Invalid def file path")
-                    .to_owned(),
+                def_file.to_str().expect("Invalid def file path").to_owned(),
                 TempFileScopeHandle(None),
             ))
         }
         _ => {
             // TODO: allow failure?
             Ok(must_rewrite_input_file_with_optional_second_file(
-                &input_args.def_file,
+                def_file,
                 target_pattern_file,
                 |def: KPuzzleDefinition, custom_default_pattern: Option<KPatternData>| {
+                    let kpuzzle = KPuzzle::try_from(def).map_err(|e| e.to_string())?;
                     let def = serialize_kpuzzle_definition(
-                        def,
+                        &kpuzzle,
                         Some(&KPuzzleSerializationOptions {
                             move_subset: None,
                             // move_subset: move_subset.clone(), // TODO
@@ -140,39 +143,48 @@ pub fn rewrite_def_file(
 }
 
 pub fn rewrite_scramble_file(
+    input_args: &DefOnlyArgs,
     scramble_file: &Option<PathBuf>,
     debug_print_serialized_json: bool,
-) -> (String, TempFileScopeHandle) {
+) -> Result<(String, TempFileScopeHandle), String> {
     match scramble_file {
         Some(scramble_file) => match scramble_file.extension().and_then(|ext| ext.to_str()) {
-            Some("scr") => (
+            Some("scr") => Ok((
                 scramble_file
                     .to_str()
                     .expect("Invalid scramble file path")
                     .to_owned(),
                 TempFileScopeHandle(None),
-            ),
+            )),
             _ => {
-                match rewrite_input_file(
-                    scramble_file,
-                    |scramble_list: ScrambleList| serialize_scramble_list(None, &scramble_list),
-                    debug_print_serialized_json,
-                ) {
-                    Ok(v) => v,
-                    Err(_) => must_rewrite_input_file(
+                // Scrambles are written with the orbits of the definition.
+                let kpuzzle = KPuzzleSource::from_clap_args(input_args)
+                    .and_then(|kpuzzle_source| kpuzzle_source.kpuzzle())
+                    .map_err(|e| e.description)?;
+                Ok(
+                    match rewrite_input_file(
                         scramble_file,
-                        |kpattern_data: KPatternData| {
-                            serialize_scramble_kpattern_data(
-                                None,
-                                "Anonymous_Scramble",
-                                &kpattern_data,
-                            )
+                        |scramble_list: ScrambleList| {
+                            serialize_scramble_list(&kpuzzle, &scramble_list)
                         },
                         debug_print_serialized_json,
-                    ),
-                }
+                    ) {
+                        Ok(v) => v,
+                        Err(_) => must_rewrite_input_file(
+                            scramble_file,
+                            |kpattern_data: KPatternData| {
+                                serialize_scramble_kpattern_data(
+                                    &kpuzzle,
+                                    "Anonymous_Scramble",
+                                    &kpattern_data,
+                                )
+                            },
+                            debug_print_serialized_json,
+                        ),
+                    },
+                )
             }
         },
-        None => ("".to_owned(), TempFileScopeHandle(None)),
+        None => Ok(("".to_owned(), TempFileScopeHandle(None))),
     }
 }
@@ -17,7 +17,7 @@ pub fn main_search(
     reset_args_from(vec![args_for_reset]);
 
     let (def_file, _temp1) = rewrite_def_file(input_args, target_pattern_file)?;
-    let (scramble_file, _temp2) = rewrite_scramble_file(scramble_file, false);
+    let (scramble_file, _temp2) = rewrite_scramble_file(input_args, scramble_file, false)?;
 
     rust_api::rust_api_main_search(&def_file, &scramble_file);
 
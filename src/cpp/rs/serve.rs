@@ -1,5 +1,6 @@
 use cubing::alg::Move;
 use cubing::kpuzzle::KPatternData;
+use cubing::kpuzzle::KPuzzle;
 use cubing::kpuzzle::KPuzzleDefinition;
 
 use rouille::router;
@@ -22,10 +23,10 @@ use crate::serialize::serialize_scramble_kpattern_data;
 use crate::serialize::KPuzzleSerializationOptions;
 
 fn set_definition(
-    def: KPuzzleDefinition,
+    kpuzzle: &KPuzzle,
     options: &KPuzzleSerializationOptions,
 ) -> Result<(), Response> {
-    let s = match serialize_kpuzzle_definition(def, Some(options)) {
+    let s = match serialize_kpuzzle_definition(kpuzzle, Some(options)) {
         Ok(s) => s,
         Err(e) => {
             return Err(Response::text(format!("Invalid definition: {}", e)).with_status_code(400));
@@ -65,8 +66,14 @@ fn solve_pattern(request: &Request, serve_command_args: &ServeCommandArgs) -> Re
         client_args: &kpattern_solve.search_args,
     };
     reset_args_from(vec![&args_for_individual_search]);
+    let kpuzzle = match KPuzzle::try_from(kpattern_solve.definition) {
+        Ok(kpuzzle) => kpuzzle,
+        Err(e) => {
+            return Response::text(format!("Invalid definition: {}", e)).with_status_code(400);
+        }
+    };
     match set_definition(
-        kpattern_solve.definition,
+        &kpuzzle,
         &KPuzzleSerializationOptions {
             move_subset: kpattern_solve.move_subset,
             custom_start_pattern: kpattern_solve.start_pattern,
@@ -76,7 +83,7 @@ fn solve_pattern(request: &Request, serve_command_args: &ServeCommandArgs) -> Re
         Err(response) => return response,
     };
     let result = match serialize_scramble_kpattern_data(
-        None,
+        &kpuzzle,
         "AnonymousScramble",
         &kpattern_solve.pattern,
     ) {
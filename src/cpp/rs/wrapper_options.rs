@@ -1,9 +1,10 @@
 use twsearch::_internal::cli::args::{
-    BenchmarkArgs, CanonicalAlgsArgs, CommonSearchArgs, EnableAutoAlwaysNeverValueEnum,
-    GeneratorArgs, Generators, GodsAlgorithmArgs, MemoryArgs, MetricArgs, MetricEnum,
-    PerformanceArgs, RequiredDefArgs, SchreierSimsArgs, ScrambleAndTargetPatternOptionalArgs,
-    SearchCommandArgs, SearchCommandOptionalArgs, SearchPersistenceArgs,
-    ServeArgsForIndividualSearch, ServeClientArgs, ServeCommandArgs, TimingTestArgs,
+    BenchmarkArgs, CanonicalAlgsArgs, CanonicalFSMArgs, CommonSearchArgs,
+    EnableAutoAlwaysNeverValueEnum, GeneratorArgs, Generators, GodsAlgorithmArgs, MemoryArgs,
+    MetricArgs, MetricEnum, PerformanceArgs, RequiredDefArgs, SchreierSimsArgs,
+    ScrambleAndTargetPatternOptionalArgs, SearchCommandArgs, SearchCommandOptionalArgs,
+    SearchPersistenceArgs, ServeArgsForIndividualSearch, ServeClientArgs, ServeCommandArgs,
+    TimingTestArgs,
 };
 
 use std::{fmt::Display, process::exit};
@@ -70,14 +71,37 @@ impl SetCppArgs for CommonSearchArgs {
         set_optional_arg("--mindepth", &self.min_depth.map(|o| o.0));
         set_optional_arg("-m", &self.max_depth.map(|o| o.0));
         set_optional_arg("--startprunedepth", &self.start_prune_depth.map(|o| o.0));
+        self.canonical_fsm_args.set_cpp_args();
         self.performance_args.set_cpp_args();
     }
 }
 
+impl SetCppArgs for CanonicalFSMArgs {
+    fn set_cpp_args(&self) {
+        set_optional_arg("--newcanon", &self.canonical_relation_length);
+        if self.forbid_adjacent_moves.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --forbid-adjacent-moves");
+            exit(1);
+        }
+        if self.forbid_transitions.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --forbid-transitions");
+            exit(1);
+        }
+        if self.canonical_fsm_constraints.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --canonical-fsm-constraints");
+            exit(1);
+        }
+    }
+}
+
 impl SetCppArgs for SearchCommandArgs {
     fn set_cpp_args(&self) {
         self.optional.set_cpp_args();
         self.def_args.set_cpp_args();
+        if self.output.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --output");
+            exit(1);
+        }
     }
 }
 
@@ -94,7 +118,13 @@ impl SetCppArgs for SearchCommandOptionalArgs {
 
 impl SetCppArgs for GeneratorArgs {
     fn set_cpp_args(&self) {
-        let parsed = self.parse();
+        let parsed = match self.parse() {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        };
         match parsed {
             Generators::Default => {}
             Generators::Custom(parsed) => {
@@ -151,6 +181,26 @@ impl SetCppArgs for GodsAlgorithmArgs {
             eprintln!("Unsupported flag for twsearch-cpp-wrapper: --start-pattern");
             exit(1);
         }
+        if self.save_table.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --save-table");
+            exit(1);
+        }
+        if self.optional.checkpoint_dir.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --checkpoint-dir");
+            exit(1);
+        }
+        if self.output.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --output");
+            exit(1);
+        }
+        if self.optional.symmetry_reduced {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --symmetry-reduced");
+            exit(1);
+        }
+        if self.optional.disk_backed {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --disk-backed");
+            exit(1);
+        }
         self.optional.generator_args.set_cpp_args();
         set_boolean_arg("-g", true);
         set_boolean_arg("-F", self.optional.force_arrays);
@@ -163,6 +213,10 @@ impl SetCppArgs for GodsAlgorithmArgs {
 
 impl SetCppArgs for TimingTestArgs {
     fn set_cpp_args(&self) {
+        if self.output.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --output");
+            exit(1);
+        }
         set_boolean_arg("-T", true);
         self.performance_args.set_cpp_args();
         self.metric_args.set_cpp_args();
@@ -183,7 +237,16 @@ impl SetCppArgs for CanonicalAlgsArgs {
             );
             exit(1);
         }
+        if self.max_depth.is_some() {
+            eprintln!("Unsupported flag for `twsearch-cpp-wrapper canonical-algs`: --max-depth");
+            exit(1);
+        }
+        if self.list_depth.is_some() {
+            eprintln!("Unsupported flag for `twsearch-cpp-wrapper canonical-algs`: --list-depth");
+            exit(1);
+        }
         set_boolean_arg("-C", true);
+        self.canonical_fsm_args.set_cpp_args();
         self.performance_args.set_cpp_args();
         self.metric_args.set_cpp_args();
     }
@@ -214,6 +277,14 @@ impl SetCppArgs for ScrambleAndTargetPatternOptionalArgs {
             // TODO: Use `cubing::kpuzzle` to handle nested input syntax
             set_arg("--scramblealg", &parsed_alg.to_string())
         };
+        if self.scramble_facelets.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --scramble-facelets");
+            exit(1);
+        }
+        if self.scramble_cycles.is_some() {
+            eprintln!("Unsupported flag for twsearch-cpp-wrapper: --scramble-cycles");
+            exit(1);
+        }
         set_boolean_arg("-s", self.stdin_scrambles)
     }
 }
@@ -250,6 +321,11 @@ impl SetCppArgs for ServeClientArgs {
         if let Some(move_subset) = &self.generator_moves {
             set_moves_arg(move_subset);
         }
+        if self.canonical_fsm_constraints.is_some() {
+            eprintln!(
+                "Ignoring unsupported client arg for twsearch-cpp-wrapper: canonicalFsmConstraints"
+            );
+        }
     }
 }
 
@@ -1,10 +1,7 @@
 use cubing::alg::Move;
 use cubing::kpuzzle::{KPattern, KPatternData, KPuzzle};
 use serde::{Deserialize, Serialize};
-use twsearch::_internal::cli::args::{CustomGenerators, Generators};
-use twsearch::_internal::search::iterative_deepening::iterative_deepening_search::{
-    IndividualSearchOptions, IterativeDeepeningSearch,
-};
+use twsearch::experimental_lib_api::{PatternSearchOptions, SearchOptions, SearchSession};
 use wasm_bindgen::prelude::*;
 
 use twsearch::scramble::{free_memory_for_all_scramble_finders, random_scramble_for_event, Event};
@@ -20,7 +17,7 @@ pub struct WasmTwsearchOptions {
     generator_moves: Option<Vec<Move>>,
 
     #[serde(flatten)]
-    inidividual_search_options: IndividualSearchOptions,
+    pattern_search_options: PatternSearchOptions,
 }
 
 #[wasm_bindgen]
@@ -43,38 +40,30 @@ pub fn wasmTwsearch(
         Err(e) => return Err(e.to_string()),
     };
     if options
-        .inidividual_search_options
-        .min_num_solutions
+        .pattern_search_options
+        .get_min_num_solutions()
         .is_some()
     {
         return Err("`minNumSolutions` is not implemented yet".to_owned());
     }
 
-    let target_pattern = match options.target_pattern {
-        Some(target_pattern_data) => {
-            let target_pattern = KPattern::try_from_data(&kpuzzle, &target_pattern_data);
-            target_pattern.map_err(|e| e.to_string())?
-        }
-        None => kpuzzle.default_pattern(),
-    };
-    let generators = match options.generator_moves {
-        Some(generator_moves) => Generators::Custom(CustomGenerators {
-            moves: generator_moves,
-            algs: vec![],
-        }),
-        None => Generators::Default,
-    };
+    let mut search_options = SearchOptions::new();
+    if let Some(target_pattern_data) = options.target_pattern {
+        let target_pattern = KPattern::try_from_data(&kpuzzle, &target_pattern_data);
+        search_options = search_options.target_pattern(target_pattern.map_err(|e| e.to_string())?);
+    }
+    if let Some(generator_moves) = options.generator_moves {
+        search_options = search_options.generator_moves(generator_moves);
+    }
 
-    let iterative_deepening_search = <IterativeDeepeningSearch<KPuzzle>>::try_new(
-        kpuzzle.clone(),
-        generators.enumerate_moves_for_kpuzzle(&kpuzzle),
-        vec![target_pattern], // TODO: support multiple target patterns.
-        Default::default(),
-    );
-    let mut iterative_deepening_search = iterative_deepening_search.map_err(|e| e.description)?;
+    let search_session = match SearchSession::try_new(&kpuzzle, search_options) {
+        Ok(search_session) => search_session,
+        Err(e) => return Err(e.to_string()),
+    };
 
-    match iterative_deepening_search
-        .search(&search_pattern, options.inidividual_search_options)
+    match search_session
+        .search(&search_pattern, options.pattern_search_options)
+        .map_err(|e| e.description)?
         .next()
     {
         Some(alg) => Ok(alg.to_string().to_owned()),
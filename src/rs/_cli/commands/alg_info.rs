@@ -0,0 +1,59 @@
+use cubing::alg::Alg;
+use twsearch::{
+    _internal::{
+        cli::args::AlgInfoArgs,
+        cycle_notation::{lcm, orbit_cycles, transformation_to_cycle_notation},
+        errors::{ArgumentError, CommandError},
+    },
+    experimental_lib_api::KPuzzleSource,
+};
+
+pub fn cli_alg_info(alg_info_args: &AlgInfoArgs) -> Result<(), CommandError> {
+    let kpuzzle = KPuzzleSource::from_clap_args(&alg_info_args.def_args)?.kpuzzle()?;
+    let alg = alg_info_args
+        .alg
+        .parse::<Alg>()
+        .map_err(|e| ArgumentError {
+            description: format!("Could not parse alg: {}", e),
+        })?;
+    let transformation = kpuzzle
+        .transformation_from_alg(&alg)
+        .map_err(|e| ArgumentError {
+            description: e.to_string(),
+        })?;
+
+    let mut order: u64 = 1;
+    for orbit_info in &kpuzzle.data.ordered_orbit_info {
+        let cycles = orbit_cycles(&transformation, orbit_info);
+        let mut descriptions = vec![];
+        for cycle in &cycles {
+            order = lcm(order, cycle.order(orbit_info));
+            let net_twist = cycle.net_twist(orbit_info);
+            descriptions.push(match (cycle.positions.len(), net_twist) {
+                (1, _) => format!("twist {}", net_twist),
+                (length, 0) => format!("{}-cycle", length),
+                (length, _) => format!("{}-cycle with net twist {}", length, net_twist),
+            });
+        }
+        println!(
+            "{}: {}",
+            orbit_info.name,
+            if descriptions.is_empty() {
+                "unchanged".to_owned()
+            } else {
+                descriptions.join(", ")
+            }
+        );
+    }
+    let cycle_notation = transformation_to_cycle_notation(&kpuzzle, &transformation);
+    println!(
+        "Cycles: {}",
+        if cycle_notation.is_empty() {
+            "(identity)"
+        } else {
+            &cycle_notation
+        }
+    );
+    println!("Order: {}", order);
+    Ok(())
+}
@@ -1,10 +1,12 @@
-use cubing::kpuzzle::{KPatternBuffer, KPuzzle, KPuzzleDefinition, KTransformation};
+use cubing::kpuzzle::{KPatternBuffer, KTransformation};
 use instant::Instant;
 use rand::seq::SliceRandom;
-use twsearch::_internal::{
-    canonical_fsm::search_generators::SearchGenerators,
-    cli::{args::BenchmarkArgs, io::read_to_json},
-    errors::CommandError,
+use twsearch::{
+    _internal::{
+        canonical_fsm::search_generators::SearchGenerators, cli::args::BenchmarkArgs,
+        errors::CommandError,
+    },
+    experimental_lib_api::KPuzzleSource,
 };
 
 const NUM_RANDOM_MOVES: usize = 65536;
@@ -12,20 +14,17 @@ const NUM_TEST_TRANSFORMATIONS: usize = 100_000_000;
 const ONE_MILLION: u32 = 1_000_000;
 
 pub fn benchmark(benchmark_args: &BenchmarkArgs) -> Result<(), CommandError> {
-    let def: KPuzzleDefinition =
-        read_to_json(&benchmark_args.def_args.def_file).expect("Invalid definition"); // TODO: automatic error conversion.
-    let kpuzzle = KPuzzle::try_new(def).expect("Invalid definition"); // TODO: automatic error conversion.
+    let kpuzzle = KPuzzleSource::from_clap_args(&benchmark_args.def_args)?.kpuzzle()?;
 
     let search_generators = SearchGenerators::try_new(
         &kpuzzle,
         benchmark_args
             .generator_args
-            .parse()
+            .parse()?
             .enumerate_moves_for_kpuzzle(&kpuzzle),
         &benchmark_args.metric_args.metric,
         false,
-    )
-    .expect("Could not get search move cache"); // TODO: automatic error conversion.
+    )?;
 
     let mut rng = rand::thread_rng();
     let random_move_list: Vec<&KTransformation> = (0..NUM_RANDOM_MOVES)
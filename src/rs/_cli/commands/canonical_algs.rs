@@ -1,26 +1,84 @@
-use cubing::kpuzzle::{KPuzzle, KPuzzleDefinition};
-use twsearch::_internal::{
-    canonical_fsm::{canonical_fsm::CanonicalFSM, search_generators::SearchGenerators},
-    cli::{args::CanonicalAlgsArgs, io::read_to_json},
-    errors::CommandError,
+use cubing::alg::{Alg, AlgNode};
+use thousands::Separable;
+use twsearch::{
+    _internal::{
+        canonical_fsm::{
+            canonical_algs::{count_canonical_algs, enumerate_canonical_algs},
+            canonical_fsm::CanonicalFSM,
+            search_generators::SearchGenerators,
+        },
+        cli::args::CanonicalAlgsArgs,
+        errors::CommandError,
+    },
+    experimental_lib_api::KPuzzleSource,
 };
 
+const DEFAULT_MAX_DEPTH: usize = 10;
+
 pub fn canonical_algs(args: &CanonicalAlgsArgs) -> Result<(), CommandError> {
-    let def: KPuzzleDefinition = read_to_json(&args.def_args.def_file)?;
-    let kpuzzle = KPuzzle::try_new(def).unwrap();
+    let kpuzzle = KPuzzleSource::from_clap_args(&args.def_args)?.kpuzzle()?;
 
     let search_generators = SearchGenerators::try_new(
         &kpuzzle,
         args.generator_args
-            .parse()
+            .parse()?
             .enumerate_moves_for_kpuzzle(&kpuzzle),
         &args.metric_args.metric,
         false,
     )?;
 
-    let canonical_fsm = CanonicalFSM::try_new(kpuzzle, search_generators, Default::default())
-        .expect("Expected to work!");
-    dbg!(canonical_fsm);
+    let canonical_fsm = CanonicalFSM::try_new(
+        kpuzzle,
+        search_generators.clone(),
+        args.canonical_fsm_args.construction_options()?,
+    )?;
+
+    if let Some(list_depth) = args.list_depth {
+        for depth in 1..=list_depth {
+            enumerate_canonical_algs(&canonical_fsm, &search_generators, depth, &mut |moves| {
+                let alg = Alg {
+                    nodes: moves
+                        .iter()
+                        .map(|r#move| AlgNode::MoveNode(r#move.clone()))
+                        .collect(),
+                };
+                println!("{}", alg);
+            });
+        }
+        println!();
+    }
+
+    let max_depth = args.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+    let depth_counts = count_canonical_algs(&canonical_fsm, &search_generators, max_depth);
+    println!(
+        "{} moves in {} move classes.",
+        search_generators.flat.len(),
+        search_generators.by_move_class.len()
+    );
+    println!("depth\tcanonical algs\tmoves^depth\tcanonical fraction\tbranching factor");
+    for (i, depth_count) in depth_counts.iter().enumerate() {
+        let branching_factor = match i {
+            0 => "".to_owned(),
+            _ => match depth_count.branching_factor(&depth_counts[i - 1]) {
+                Some(branching_factor) => format!("{:.3}", branching_factor),
+                None => "-".to_owned(),
+            },
+        };
+        println!(
+            "{}\t{}\t{}\t{:.6}\t{}",
+            depth_count.depth,
+            depth_count.num_canonical_algs.separate_with_underscores(),
+            depth_count.num_move_sequences.separate_with_underscores(),
+            depth_count.canonical_fraction(),
+            branching_factor
+        );
+    }
+    if depth_counts.len() <= max_depth {
+        eprintln!(
+            "Stopped at depth {} because the counts no longer fit in 128 bits.",
+            depth_counts.len() - 1
+        );
+    }
 
     Ok(())
 }
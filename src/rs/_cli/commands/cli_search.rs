@@ -1,32 +1,92 @@
 use twsearch::{
-    _internal::{cli::args::SearchCommandArgs, errors::CommandError},
-    experimental_lib_api::{search, KPuzzleSource, PatternSource},
+    _internal::{
+        cli::args::{SearchCommandArgs, SearchOutputFormat},
+        errors::CommandError,
+        search::search_report::SearchReport,
+    },
+    experimental_lib_api::{
+        search_batch, KPuzzleSource, NamedPattern, PatternSource, SearchOptions,
+    },
 };
 
 pub fn cli_search(search_command_args: SearchCommandArgs) -> Result<(), CommandError> {
     let search_start_time: std::time::Instant = instant::Instant::now();
     let kpuzzle =
-        KPuzzleSource::from_clap_args(&search_command_args.def_args.def_args).kpuzzle()?;
-    let search_pattern = PatternSource::search_pattern_from_clap_args(
-        &search_command_args
-            .optional
-            .scramble_and_target_pattern_optional_args,
-    )?
-    .pattern(&kpuzzle)?;
-    let solutions = search(&kpuzzle, &search_pattern, search_command_args.optional)?;
-    let mut solution_index = 0;
-    for solution in solutions {
-        solution_index += 1;
+        KPuzzleSource::from_clap_args(&search_command_args.def_args.def_args)?.kpuzzle()?;
+    let (names, search_patterns): (Vec<String>, Vec<_>) =
+        PatternSource::search_pattern_from_clap_args(
+            &search_command_args
+                .optional
+                .scramble_and_target_pattern_optional_args,
+        )?
+        .patterns(&kpuzzle)?
+        .into_iter()
+        .map(|NamedPattern { name, pattern }| (name, pattern))
+        .unzip();
+    let is_batch = search_patterns.len() > 1;
+    let metric = search_command_args.optional.metric_args.metric.clone();
+    // For a single scramble, an error is returned instead of printed.
+    let mut first_error = None;
+    search_batch(
+        &kpuzzle,
+        &search_patterns,
+        SearchOptions::try_from_clap_args(&kpuzzle, &search_command_args.optional)?,
+        |index, solutions| {
+            let mut solutions = match solutions {
+                Ok(solutions) => solutions,
+                Err(e) => {
+                    if let Some(SearchOutputFormat::Json) = search_command_args.output {
+                        let report = SearchReport::from_error(
+                            names[index].clone(),
+                            e.description,
+                            &metric,
+                            instant::Instant::now() - search_start_time,
+                        );
+                        println!("{}", report.to_json_line());
+                    } else if is_batch {
+                        println!("// Scramble #{}: {}", index + 1, names[index]);
+                        println!("// Could not solve: {}", e.description);
+                    } else {
+                        first_error.get_or_insert(e);
+                    }
+                    return;
+                }
+            };
+            if let Some(SearchOutputFormat::Json) = search_command_args.output {
+                let solution_algs: Vec<_> = solutions.by_ref().collect();
+                let report = SearchReport::new(
+                    names[index].clone(),
+                    &solution_algs,
+                    &metric,
+                    instant::Instant::now() - search_start_time,
+                    solutions.stats(),
+                );
+                println!("{}", report.to_json_line());
+                return;
+            }
+            if is_batch {
+                println!("// Scramble #{}: {}", index + 1, names[index]);
+            }
+            let mut solution_index = 0;
+            for solution in solutions {
+                solution_index += 1;
+                println!(
+                    "{} // solution #{} ({} nodes)",
+                    solution,
+                    solution_index,
+                    solution.nodes.len()
+                )
+            }
+        },
+    )?;
+    if let Some(e) = first_error {
+        return Err(e.into());
+    }
+    if search_command_args.output.is_none() {
         println!(
-            "{} // solution #{} ({} nodes)",
-            solution,
-            solution_index,
-            solution.nodes.len()
-        )
+            "// Entire search duration: {:?}",
+            instant::Instant::now() - search_start_time
+        );
     }
-    println!(
-        "// Entire search duration: {:?}",
-        instant::Instant::now() - search_start_time
-    );
     Ok(())
 }
@@ -0,0 +1,19 @@
+use twsearch::{
+    _internal::{cli::args::CosetArgs, errors::CommandError},
+    experimental_lib_api::{coset_with_observer, KPuzzleSource},
+};
+
+use super::gods_algorithm::ProgressBarObserver;
+
+pub fn cli_coset(coset_args: CosetArgs) -> Result<(), CommandError> {
+    let kpuzzle = KPuzzleSource::from_clap_args(&coset_args.def_args)?.kpuzzle()?;
+    let table = coset_with_observer(
+        &kpuzzle,
+        coset_args.optional,
+        &mut ProgressBarObserver::default(),
+    )?;
+    println!();
+    println!();
+    println!("{}", table.summary());
+    Ok(())
+}
@@ -1,12 +1,120 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressFinish, ProgressStyle};
+use thousands::Separable;
 use twsearch::{
-    _internal::{cli::args::GodsAlgorithmArgs, errors::CommandError},
-    experimental_lib_api::{gods_algorithm, KPuzzleSource},
+    _internal::{
+        cli::args::{GodsAlgorithmArgs, GodsAlgorithmOutputFormat},
+        errors::CommandError,
+        gods_algorithm::{progress::GodsAlgorithmProgressObserver, report::GodsAlgorithmReport},
+    },
+    experimental_lib_api::{gods_algorithm_with_observer, KPuzzleSource},
 };
 
+#[derive(Default)]
+pub(crate) struct ProgressBarObserver {
+    multi_progress_bar: MultiProgress,
+    progress_bar: Option<ProgressBar>,
+    num_candidates: usize,
+}
+
+impl ProgressBarObserver {
+    fn progress_bar_for_depth(&mut self, depth: usize) -> &ProgressBar {
+        self.progress_bar.get_or_insert_with(|| {
+            let progress_bar = ProgressBar::new(self.num_candidates.try_into().unwrap());
+            let progress_bar = self.multi_progress_bar.insert_from_back(0, progress_bar);
+            let progress_bar = progress_bar.with_finish(ProgressFinish::AndLeave);
+            let progress_bar_style = ProgressStyle::with_template(
+                "{prefix:3} {bar:12.cyan/blue} {elapsed:.2} {wide_msg}",
+            )
+            .expect("Could not construct progress bar.");
+            progress_bar.set_style(progress_bar_style);
+            progress_bar.set_prefix(depth.to_string());
+            progress_bar
+        })
+    }
+}
+
+impl GodsAlgorithmProgressObserver for ProgressBarObserver {
+    fn resumed_from_checkpoint(&mut self, last_completed_depth: usize, num_patterns_total: usize) {
+        eprintln!(
+            "Resuming from checkpoint at depth {} ({} patterns so far).",
+            last_completed_depth,
+            num_patterns_total.separate_with_underscores()
+        );
+    }
+
+    fn depth_started(&mut self, depth: usize, num_candidates: usize) {
+        self.num_candidates = num_candidates;
+        self.progress_bar = None;
+        self.progress_bar_for_depth(depth);
+    }
+
+    fn depth_progress(
+        &mut self,
+        depth: usize,
+        num_candidates_tested: usize,
+        num_patterns_at_depth: usize,
+        num_patterns_total: usize,
+    ) {
+        let num_candidates = self.num_candidates;
+        let progress_bar = self.progress_bar_for_depth(depth);
+        progress_bar.set_length(num_candidates.try_into().unwrap());
+        progress_bar.set_position(num_candidates_tested as u64);
+        progress_bar.set_message(format!(
+            "{} patterns ({} cumulative) — {} remaining candidates",
+            num_patterns_at_depth.separate_with_underscores(),
+            num_patterns_total.separate_with_underscores(),
+            (num_candidates - num_candidates_tested).separate_with_underscores()
+        ));
+    }
+
+    fn depth_completed(
+        &mut self,
+        depth: usize,
+        num_patterns_at_depth: usize,
+        num_patterns_total: usize,
+    ) {
+        let progress_bar = self.progress_bar_for_depth(depth);
+        progress_bar.set_length(num_patterns_at_depth.try_into().unwrap());
+        progress_bar.set_position(num_patterns_at_depth.try_into().unwrap());
+        progress_bar.set_message(format!(
+            "{} patterns ({} cumulative)",
+            num_patterns_at_depth.separate_with_underscores(),
+            num_patterns_total.separate_with_underscores()
+        ));
+        if num_patterns_at_depth == 0 {
+            progress_bar.finish_and_clear();
+        } else {
+            progress_bar.finish();
+        }
+        self.progress_bar = None;
+    }
+}
+
 pub fn cli_gods_algorithm(gods_algorithm_args: GodsAlgorithmArgs) -> Result<(), CommandError> {
-    gods_algorithm(
-        &KPuzzleSource::from_clap_args(&gods_algorithm_args.def_args).kpuzzle()?,
+    let kpuzzle = KPuzzleSource::from_clap_args(&gods_algorithm_args.def_args)?.kpuzzle()?;
+    let metric = gods_algorithm_args.optional.metric_args.metric.clone();
+    let table = gods_algorithm_with_observer(
+        &kpuzzle,
         gods_algorithm_args.optional,
+        &mut ProgressBarObserver::default(),
     )?;
+    match gods_algorithm_args.output {
+        None => {
+            println!();
+            println!();
+            println!("{}", table.summary());
+        }
+        Some(output_format) => {
+            let report = GodsAlgorithmReport::new(&table, &kpuzzle.definition().name, &metric);
+            match output_format {
+                GodsAlgorithmOutputFormat::Json => println!("{}", report.to_json()),
+                GodsAlgorithmOutputFormat::Csv => print!("{}", report.to_csv()),
+            }
+        }
+    }
+    if let Some(save_table) = &gods_algorithm_args.save_table {
+        table.save(save_table)?;
+        eprintln!("Saved table to: {}", save_table.display());
+    }
     Ok(())
 }
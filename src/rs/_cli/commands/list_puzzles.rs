@@ -0,0 +1,16 @@
+use twsearch::{_internal::errors::CommandError, scramble::embedded_puzzles};
+
+pub fn cli_list_puzzles() -> Result<(), CommandError> {
+    let id_width = embedded_puzzles()
+        .iter()
+        .map(|embedded_puzzle| embedded_puzzle.id.len())
+        .max()
+        .unwrap_or(0);
+    for embedded_puzzle in embedded_puzzles() {
+        println!(
+            "{:id_width$}  {}",
+            embedded_puzzle.id, embedded_puzzle.description
+        );
+    }
+    Ok(())
+}
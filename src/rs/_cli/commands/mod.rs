@@ -1,5 +1,10 @@
+pub mod alg_info;
 pub mod benchmark;
 pub mod canonical_algs;
 pub mod cli_scramble;
 pub mod cli_search;
+pub mod coset;
 pub mod gods_algorithm;
+pub mod list_puzzles;
+pub mod schreier_sims;
+pub mod timing_test;
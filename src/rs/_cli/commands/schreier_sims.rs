@@ -0,0 +1,10 @@
+use twsearch::{
+    _internal::{cli::args::SchreierSimsArgs, errors::CommandError},
+    experimental_lib_api::{schreier_sims, KPuzzleSource},
+};
+
+pub fn cli_schreier_sims(schreier_sims_args: &SchreierSimsArgs) -> Result<(), CommandError> {
+    let kpuzzle = KPuzzleSource::from_clap_args(&schreier_sims_args.def_args)?.kpuzzle()?;
+    println!("{}", schreier_sims(&kpuzzle).summary());
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use twsearch::{
+    _internal::{
+        cli::args::{TimingTestArgs, TimingTestOutputFormat},
+        errors::CommandError,
+        timing_test::timing_test,
+    },
+    experimental_lib_api::KPuzzleSource,
+};
+
+pub fn cli_timing_test(timing_test_args: &TimingTestArgs) -> Result<(), CommandError> {
+    let kpuzzle = KPuzzleSource::from_clap_args(&timing_test_args.def_args)?.kpuzzle()?;
+    let report = timing_test(
+        &kpuzzle,
+        &timing_test_args.metric_args.metric,
+        timing_test_args
+            .performance_args
+            .memory_args
+            .memory_mebibytes,
+    )?;
+    match timing_test_args.output {
+        None => println!("{}", report.summary()),
+        Some(TimingTestOutputFormat::Json) => println!("{}", report.to_json()),
+    }
+    Ok(())
+}
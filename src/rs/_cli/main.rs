@@ -2,35 +2,49 @@ mod commands;
 mod serve;
 
 use commands::{
+    alg_info::cli_alg_info,
     benchmark::benchmark,
     canonical_algs::canonical_algs,
     cli_scramble::{cli_scramble, cli_scramble_finder_solve},
     cli_search::cli_search,
+    coset::cli_coset,
     gods_algorithm::cli_gods_algorithm,
+    list_puzzles::cli_list_puzzles,
+    schreier_sims::cli_schreier_sims,
+    timing_test::cli_timing_test,
 };
-use twsearch::_internal::{
-    cli::args::{get_options, CliCommand},
-    errors::CommandError,
-};
+use std::process::exit;
+
+use twsearch::_internal::cli::args::{get_options, print_completions, CliCommand};
 
-fn main() -> Result<(), CommandError> {
+fn main() {
     let args = get_options();
 
-    match args.command {
-        CliCommand::Completions(_completions_args) => {
-            panic!("Completions should have been printed during options parsing, followed by program exit.");
+    let result = match args.command {
+        CliCommand::Completions(completions_args) => {
+            print_completions(&completions_args);
+            Ok(())
         }
         CliCommand::Search(search_command_args) => cli_search(search_command_args),
         CliCommand::Serve(serve_command_args) => serve::serve::serve(serve_command_args),
         // TODO: consolidate def-only arg implementations.
-        CliCommand::SchreierSims(_schreier_sims_command_args) => todo!(),
+        CliCommand::SchreierSims(schreier_sims_command_args) => {
+            cli_schreier_sims(&schreier_sims_command_args)
+        }
         CliCommand::GodsAlgorithm(gods_algorithm_args) => cli_gods_algorithm(gods_algorithm_args),
-        CliCommand::TimingTest(_args) => todo!(),
+        CliCommand::Coset(coset_args) => cli_coset(coset_args),
+        CliCommand::TimingTest(timing_test_args) => cli_timing_test(&timing_test_args),
         CliCommand::CanonicalAlgs(args) => canonical_algs(&args),
         CliCommand::Scramble(scramble_args) => cli_scramble(&scramble_args),
         CliCommand::ScrambleFinder(scramble_finder_solve_args) => {
             cli_scramble_finder_solve(&scramble_finder_solve_args)
         }
         CliCommand::Benchmark(benchmark_args) => benchmark(&benchmark_args),
+        CliCommand::ListPuzzles => cli_list_puzzles(),
+        CliCommand::AlgInfo(alg_info_args) => cli_alg_info(&alg_info_args),
+    };
+    if let Err(err) = result {
+        eprintln!("Error: {}", err);
+        exit(1);
     }
 }
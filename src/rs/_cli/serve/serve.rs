@@ -2,17 +2,13 @@ use cubing::kpuzzle::{KPattern, KPatternData, KPuzzle, KPuzzleDefinition};
 
 use rouille::{router, try_or_400, Request, Response};
 use serde::{Deserialize, Serialize};
-use twsearch::_internal::{
-    cli::args::{
-        CustomGenerators, Generators, ServeArgsForIndividualSearch, ServeClientArgs,
-        ServeCommandArgs,
+use twsearch::{
+    _internal::{
+        canonical_fsm::canonical_fsm::CanonicalFSMConstructionOptions,
+        cli::args::{ServeArgsForIndividualSearch, ServeClientArgs, ServeCommandArgs},
+        errors::CommandError,
     },
-    errors::CommandError,
-    search::iterative_deepening::iterative_deepening_search::{
-        IndividualSearchOptions, IterativeDeepeningSearch,
-        IterativeDeepeningSearchConstructionOptions,
-    },
-    search::search_logger::SearchLogger,
+    experimental_lib_api::{SearchOptions, SearchSession},
 };
 
 use std::sync::Arc;
@@ -63,13 +59,6 @@ fn solve_pattern(
         },
         None => kpuzzle.default_pattern(),
     };
-    let search_logger = Arc::new(SearchLogger {
-        verbosity: args_for_individual_search
-            .commandline_args
-            .verbosity_args
-            .verbosity
-            .unwrap_or_default(),
-    });
     let move_subset = match args_for_individual_search.client_args {
         Some(client_args) => client_args.generator_moves.as_ref().cloned(),
         None => None,
@@ -80,45 +69,45 @@ fn solve_pattern(
         Ok(search_pattern) => search_pattern,
         Err(e) => return Response::text(e.to_string()).with_status_code(400),
     };
-    let mut search = match <IterativeDeepeningSearch<KPuzzle>>::try_new(
-        kpuzzle.clone(),
-        Generators::Custom(CustomGenerators {
-            moves: move_list.clone(),
-            algs: vec![],
-        })
-        .enumerate_moves_for_kpuzzle(&kpuzzle),
-        vec![target_pattern], // TODO: modify api to support multiple target patterns
-        IterativeDeepeningSearchConstructionOptions {
-            search_logger,
-            random_start: match args_for_individual_search.client_args {
-                Some(client_args) => client_args.random_start == Some(true),
-                None => false,
-            },
-            ..Default::default()
-        },
-    ) {
-        Ok(search) => search,
+    let mut search_options = SearchOptions::new()
+        .generator_moves(move_list)
+        .target_pattern(target_pattern)
+        .verbosity(
+            args_for_individual_search
+                .commandline_args
+                .verbosity_args
+                .verbosity
+                .unwrap_or_default()
+                .into(),
+        );
+    if let Some(client_args) = args_for_individual_search.client_args {
+        search_options = search_options.random_start(client_args.random_start == Some(true));
+        if let Some(canonical_fsm_constraints) = &client_args.canonical_fsm_constraints {
+            search_options = search_options.canonical_fsm_options(
+                CanonicalFSMConstructionOptions::from(canonical_fsm_constraints).into(),
+            );
+        }
+        if let Some(check_before_solve) = &client_args.check_before_solve {
+            search_options = search_options.check_before_solve(check_before_solve.into());
+        }
+        if let Some(min_depth) = client_args.min_depth {
+            search_options = search_options.min_depth(min_depth.0);
+        }
+        if let Some(max_depth) = client_args.max_depth {
+            search_options = search_options.max_depth(max_depth.0);
+        }
+    }
+    // TODO: support canonical FSM pre-moves and post-moves.
+    let pattern_search_options = search_options.pattern_search_options();
+    let search_session = match SearchSession::try_new(&kpuzzle, search_options) {
+        Ok(search_session) => search_session,
+        Err(e) => return Response::text(e.to_string()).with_status_code(400),
+    };
+    let mut solutions = match search_session.search(&search_pattern, pattern_search_options) {
+        Ok(solutions) => solutions,
         Err(e) => return Response::text(e.description).with_status_code(400),
     };
-    if let Some(solution) = search
-        .search(
-            &search_pattern,
-            IndividualSearchOptions {
-                min_num_solutions: None,
-                min_depth: args_for_individual_search
-                    .client_args
-                    .as_ref()
-                    .and_then(|client_args| client_args.min_depth),
-                max_depth: args_for_individual_search
-                    .client_args
-                    .as_ref()
-                    .and_then(|client_args| client_args.max_depth),
-                // TODO: support canonical FSM pre-moves and post-moves.
-                ..Default::default()
-            },
-        )
-        .next()
-    {
+    if let Some(solution) = solutions.next() {
         println!(
             "[Search request #{}] Solution found (in {:?}): {}",
             request_counter,
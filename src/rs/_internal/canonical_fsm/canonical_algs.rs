@@ -0,0 +1,193 @@
+use cubing::alg::Move;
+
+use crate::_internal::puzzle_traits::puzzle_traits::SemiGroupActionPuzzle;
+
+use super::{
+    canonical_fsm::{CanonicalFSM, CanonicalFSMState, CANONICAL_FSM_START_STATE},
+    search_generators::SearchGenerators,
+};
+
+#[derive(Debug)]
+pub struct CanonicalAlgsDepthCount {
+    pub depth: usize,
+    pub num_canonical_algs: u128,
+    /// The number of move sequences without any pruning (`num_moves^depth`).
+    pub num_move_sequences: u128,
+}
+
+impl CanonicalAlgsDepthCount {
+    /// The fraction of move sequences that are canonical.
+    pub fn canonical_fraction(&self) -> f64 {
+        self.num_canonical_algs as f64 / self.num_move_sequences as f64
+    }
+
+    /// The ratio of canonical algs at this depth to those at the previous depth.
+    /// Returns `None` if there were no canonical algs at the previous depth.
+    pub fn branching_factor(&self, previous: &CanonicalAlgsDepthCount) -> Option<f64> {
+        if previous.num_canonical_algs == 0 {
+            return None;
+        }
+        Some(self.num_canonical_algs as f64 / previous.num_canonical_algs as f64)
+    }
+}
+
+/// Counts the canonical algs of each depth from `0` to `max_depth` (inclusive), by counting the number of ways to reach each FSM state.
+///
+/// Stops early if the counts no longer fit in a `u128`.
+pub fn count_canonical_algs<TPuzzle: SemiGroupActionPuzzle>(
+    canonical_fsm: &CanonicalFSM<TPuzzle>,
+    search_generators: &SearchGenerators<TPuzzle>,
+    max_depth: usize,
+) -> Vec<CanonicalAlgsDepthCount> {
+    let num_states = canonical_fsm.next_state_lookup.len();
+    let num_moves = search_generators.flat.len() as u128;
+
+    let mut state_counts = vec![0u128; num_states];
+    state_counts[usize::from(CANONICAL_FSM_START_STATE)] = 1;
+    let mut depth_counts = vec![CanonicalAlgsDepthCount {
+        depth: 0,
+        num_canonical_algs: 1,
+        num_move_sequences: 1,
+    }];
+    'depths: for depth in 1..=max_depth {
+        let Some(num_move_sequences) = depth_counts
+            .last()
+            .unwrap()
+            .num_move_sequences
+            .checked_mul(num_moves)
+        else {
+            break;
+        };
+        let mut next_state_counts = vec![0u128; num_states];
+        for (state, count) in state_counts.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            for (move_class_index, move_transformation_multiples) in
+                search_generators.by_move_class.iter()
+            {
+                let Some(next_state) =
+                    canonical_fsm.next_state(CanonicalFSMState(state), move_class_index)
+                else {
+                    continue;
+                };
+                let next_state_count = &mut next_state_counts[usize::from(next_state)];
+                let Some(new_count) = count
+                    .checked_mul(move_transformation_multiples.len() as u128)
+                    .and_then(|increment| next_state_count.checked_add(increment))
+                else {
+                    break 'depths;
+                };
+                *next_state_count = new_count;
+            }
+        }
+        state_counts = next_state_counts;
+        depth_counts.push(CanonicalAlgsDepthCount {
+            depth,
+            num_canonical_algs: state_counts.iter().sum(),
+            num_move_sequences,
+        });
+    }
+    depth_counts
+}
+
+/// Calls `f` with each canonical alg of exactly the given depth, in move class order.
+pub fn enumerate_canonical_algs<TPuzzle: SemiGroupActionPuzzle>(
+    canonical_fsm: &CanonicalFSM<TPuzzle>,
+    search_generators: &SearchGenerators<TPuzzle>,
+    depth: usize,
+    f: &mut impl FnMut(&[Move]),
+) {
+    enumerate_canonical_algs_recursive(
+        canonical_fsm,
+        search_generators,
+        CANONICAL_FSM_START_STATE,
+        depth,
+        &mut vec![],
+        f,
+    );
+}
+
+fn enumerate_canonical_algs_recursive<TPuzzle: SemiGroupActionPuzzle>(
+    canonical_fsm: &CanonicalFSM<TPuzzle>,
+    search_generators: &SearchGenerators<TPuzzle>,
+    current_state: CanonicalFSMState,
+    remaining_depth: usize,
+    moves: &mut Vec<Move>,
+    f: &mut impl FnMut(&[Move]),
+) {
+    if remaining_depth == 0 {
+        f(moves);
+        return;
+    }
+    for (move_class_index, move_transformation_multiples) in search_generators.by_move_class.iter()
+    {
+        let Some(next_state) = canonical_fsm.next_state(current_state, move_class_index) else {
+            continue;
+        };
+        for move_transformation_info in move_transformation_multiples {
+            moves.push(move_transformation_info.r#move.clone());
+            enumerate_canonical_algs_recursive(
+                canonical_fsm,
+                search_generators,
+                next_state,
+                remaining_depth - 1,
+                moves,
+                f,
+            );
+            moves.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::puzzles::cube3x3x3_kpuzzle;
+
+    use crate::_internal::{
+        canonical_fsm::{canonical_fsm::CanonicalFSM, search_generators::SearchGenerators},
+        cli::args::MetricEnum,
+    };
+
+    use super::{count_canonical_algs, CanonicalAlgsDepthCount};
+
+    #[test]
+    fn count_canonical_algs_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let search_generators = SearchGenerators::try_new(
+            kpuzzle,
+            ["U", "L", "F", "R", "B", "D"]
+                .iter()
+                .map(|move_str| move_str.parse().unwrap())
+                .collect(),
+            &MetricEnum::Hand,
+            false,
+        )
+        .unwrap();
+        let canonical_fsm = CanonicalFSM::try_new(
+            kpuzzle.clone(),
+            search_generators.clone(),
+            Default::default(),
+        )
+        .unwrap();
+        let depth_counts = count_canonical_algs(&canonical_fsm, &search_generators, 4);
+        assert_eq!(
+            depth_counts
+                .iter()
+                .map(|depth_count| depth_count.num_canonical_algs)
+                .collect::<Vec<_>>(),
+            vec![1, 18, 243, 3240, 43254]
+        );
+        assert_eq!(
+            depth_counts[2].branching_factor(&depth_counts[1]),
+            Some(13.5)
+        );
+
+        let depth_count = |num_canonical_algs| CanonicalAlgsDepthCount {
+            depth: 0,
+            num_canonical_algs,
+            num_move_sequences: 1,
+        };
+        assert_eq!(depth_count(0).branching_factor(&depth_count(0)), None);
+    }
+}
@@ -5,6 +5,7 @@ use std::{
 };
 
 use cubing::alg::QuantumMove;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     _internal::{
@@ -14,24 +15,75 @@ use crate::{
     whole_number_newtype,
 };
 
-use super::search_generators::{MoveTransformationInfo, SearchGenerators};
-
-const MAX_NUM_MOVE_CLASSES: usize = usize::BITS as usize;
+use super::{
+    move_class_relations::{
+        find_redundant_move_class_sequences, next_state_lookup_forbidding_sequences,
+    },
+    search_generators::{MoveTransformationInfo, SearchGenerators},
+};
 
 whole_number_newtype!(MoveClassIndex, usize);
 
+const MASK_WORD_BITS: usize = u64::BITS as usize;
+
 // Bit N is indexed by a `MoveClassIndex` value of N.
-whole_number_newtype!(MoveClassMask, u64);
+// Puzzles with at most 64 move classes use a single word.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+struct MoveClassMask(Vec<u64>);
+
+impl MoveClassMask {
+    fn empty(num_move_classes: usize) -> Self {
+        Self(vec![0; num_move_classes.div_ceil(MASK_WORD_BITS)])
+    }
+
+    fn full(num_move_classes: usize) -> Self {
+        let mut mask = Self::empty(num_move_classes);
+        for move_class_index in 0..num_move_classes {
+            mask.insert(move_class_index);
+        }
+        mask
+    }
+
+    fn contains(&self, move_class_index: usize) -> bool {
+        (self.0[move_class_index / MASK_WORD_BITS] >> (move_class_index % MASK_WORD_BITS)) & 1 != 0
+    }
+
+    fn insert(&mut self, move_class_index: usize) {
+        self.0[move_class_index / MASK_WORD_BITS] |= 1 << (move_class_index % MASK_WORD_BITS);
+    }
+
+    fn remove(&mut self, move_class_index: usize) {
+        self.0[move_class_index / MASK_WORD_BITS] &= !(1 << (move_class_index % MASK_WORD_BITS));
+    }
 
-impl BitAndAssign for MoveClassMask {
-    fn bitand_assign(&mut self, rhs: Self) {
-        self.0 = self.0 & rhs.0
+    // Whether any move class with an index strictly greater than `move_class_index` is in both masks.
+    fn intersects_above(&self, other: &Self, move_class_index: usize) -> bool {
+        let word_index = move_class_index / MASK_WORD_BITS;
+        let intersection_above = match move_class_index % MASK_WORD_BITS + 1 {
+            MASK_WORD_BITS => 0,
+            shift => (self.0[word_index] & other.0[word_index]) >> shift,
+        };
+        intersection_above != 0
+            || self.0[word_index + 1..]
+                .iter()
+                .zip(other.0[word_index + 1..].iter())
+                .any(|(word, other_word)| word & other_word != 0)
     }
 }
 
-impl BitOrAssign for MoveClassMask {
-    fn bitor_assign(&mut self, rhs: Self) {
-        self.0 = self.0 | rhs.0
+impl BitAndAssign<&MoveClassMask> for MoveClassMask {
+    fn bitand_assign(&mut self, rhs: &Self) {
+        for (word, rhs_word) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *word &= rhs_word;
+        }
+    }
+}
+
+impl BitOrAssign<&MoveClassMask> for MoveClassMask {
+    fn bitor_assign(&mut self, rhs: &Self) {
+        for (word, rhs_word) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *word |= rhs_word;
+        }
     }
 }
 
@@ -40,38 +92,43 @@ whole_number_newtype!(CanonicalFSMState, usize);
 pub(crate) const CANONICAL_FSM_START_STATE: CanonicalFSMState = CanonicalFSMState(0);
 pub(crate) const ILLEGAL_FSM_STATE: CanonicalFSMState = CanonicalFSMState(0xFFFFFFFF);
 
+// Identifies a state during construction.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct StateKey {
+    mask: MoveClassMask,
+    // The move class of the previous move. This is only tracked if there are
+    // forbidden transitions (to avoid multiplying the number of states otherwise).
+    last_move_class: Option<usize>,
+}
+
 #[derive(Default, Debug)]
-struct MaskToState(HashMap<MoveClassMask, CanonicalFSMState>);
+struct MaskToState(HashMap<StateKey, CanonicalFSMState>);
 
 impl MaskToState {
-    pub fn insert(&mut self, mask: MoveClassMask, state: CanonicalFSMState) {
-        self.0.insert(mask, state);
+    pub fn insert(&mut self, key: StateKey, state: CanonicalFSMState) {
+        self.0.insert(key, state);
     }
 
-    pub fn get(&self, mask: MoveClassMask) -> Option<CanonicalFSMState> {
-        self.0.get(&mask).copied() // TODO: figure out how to do this safely and most performantly
+    pub fn get(&self, key: &StateKey) -> Option<CanonicalFSMState> {
+        self.0.get(key).copied() // TODO: figure out how to do this safely and most performantly
     }
 }
 
 #[derive(Default, Debug)]
-struct StateToMask(IndexedVec<CanonicalFSMState, MoveClassMask>);
+struct StateToMask(IndexedVec<CanonicalFSMState, StateKey>);
 
 impl StateToMask {
-    pub fn new(initial_value: MoveClassMask) -> StateToMask {
+    pub fn new(initial_value: StateKey) -> StateToMask {
         Self(IndexedVec::new(vec![initial_value]))
     }
 
     // Push the next value (useful while constructing in order.)
-    pub fn push(&mut self, mask: MoveClassMask) {
-        self.0.push(mask);
+    pub fn push(&mut self, key: StateKey) {
+        self.0.push(key);
     }
 
-    pub fn set(&mut self, state: CanonicalFSMState, value: MoveClassMask) {
-        self.0.set(state, value);
-    }
-
-    pub fn get(&self, state: CanonicalFSMState) -> MoveClassMask {
-        *self.0.at(state)
+    pub fn get(&self, state: CanonicalFSMState) -> &StateKey {
+        self.0.at(state)
     }
 }
 
@@ -89,25 +146,72 @@ pub struct CanonicalFSM<TPuzzle: SemiGroupActionPuzzle> {
 #[derive(Debug, Default)]
 pub struct CanonicalFSMConstructionOptions {
     pub forbid_transitions_by_quantums_either_direction: HashSet<(QuantumMove, QuantumMove)>,
+    /// Each entry `(first, second)` forbids a move with quantum `first` from being followed by a move with quantum `second`.
+    pub forbid_transitions_by_quantums_in_order: HashSet<(QuantumMove, QuantumMove)>,
+    /// If set, also prune move sequences up to this length that have the same
+    /// effect as a shorter (or otherwise earlier) move sequence. This requires
+    /// puzzle support for composing and hashing transformations.
+    pub max_relation_length: Option<usize>,
 }
 
 impl CanonicalFSMConstructionOptions {
+    // Whether `move1_info` may not be followed by `move2_info`.
     fn is_transition_forbidden<TPuzzle: SemiGroupActionPuzzle>(
         &self,
         move1_info: &MoveTransformationInfo<TPuzzle>,
         move2_info: &MoveTransformationInfo<TPuzzle>,
     ) -> bool {
-        self.forbid_transitions_by_quantums_either_direction
-            .contains(&(
-                move1_info.r#move.quantum.as_ref().clone(),
-                move2_info.r#move.quantum.as_ref().clone(),
-            ))
+        let in_order = (
+            move1_info.r#move.quantum.as_ref().clone(),
+            move2_info.r#move.quantum.as_ref().clone(),
+        );
+        let reversed = (in_order.1.clone(), in_order.0.clone());
+        self.forbid_transitions_by_quantums_in_order
+            .contains(&in_order)
+            || self
+                .forbid_transitions_by_quantums_either_direction
+                .contains(&in_order)
             || self
                 .forbid_transitions_by_quantums_either_direction
-                .contains(&(
-                    move2_info.r#move.quantum.as_ref().clone(),
-                    move1_info.r#move.quantum.as_ref().clone(),
-                ))
+                .contains(&reversed)
+    }
+}
+
+/// Forbidden transitions that can be specified by users (e.g. in a JSON file
+/// passed using `--canonical-fsm-constraints`, or by a `serve` client).
+///
+/// ```json
+/// {
+///   "forbiddenAdjacentMoves": [["L", "BR"]],
+///   "forbiddenTransitions": [["x", "y"]]
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanonicalFSMConstraints {
+    /// Pairs of move quantums that may not be adjacent (in either order).
+    #[serde(default)]
+    pub forbidden_adjacent_moves: Vec<(QuantumMove, QuantumMove)>,
+    /// Pairs `(first, second)` where a move with quantum `first` may not be followed by a move with quantum `second`.
+    #[serde(default)]
+    pub forbidden_transitions: Vec<(QuantumMove, QuantumMove)>,
+}
+
+impl From<&CanonicalFSMConstraints> for CanonicalFSMConstructionOptions {
+    fn from(constraints: &CanonicalFSMConstraints) -> Self {
+        Self {
+            forbid_transitions_by_quantums_either_direction: constraints
+                .forbidden_adjacent_moves
+                .iter()
+                .cloned()
+                .collect(),
+            forbid_transitions_by_quantums_in_order: constraints
+                .forbidden_transitions
+                .iter()
+                .cloned()
+                .collect(),
+            ..Default::default()
+        }
     }
 }
 
@@ -120,16 +224,17 @@ impl<TPuzzle: SemiGroupActionPuzzle> CanonicalFSM<TPuzzle> {
         options: CanonicalFSMConstructionOptions,
     ) -> Result<CanonicalFSM<TPuzzle>, SearchError> {
         let num_move_classes = generators.by_move_class.len();
-        if num_move_classes > MAX_NUM_MOVE_CLASSES {
-            return Err(SearchError {
-                description: "Too many move classes!".to_owned(),
-            });
-        }
 
         let mut commutes: Vec<MoveClassMask> =
-            vec![MoveClassMask((1 << num_move_classes) - 1); num_move_classes];
+            vec![MoveClassMask::full(num_move_classes); num_move_classes];
         let mut forbidden_transitions: Vec<MoveClassMask> =
-            vec![MoveClassMask(0); num_move_classes];
+            vec![MoveClassMask::empty(num_move_classes); num_move_classes];
+        // For commuting move classes, only one order (lower index first) is
+        // canonical. If a user forbids that order, the other order must be
+        // allowed instead. `commuting_order_exceptions[i]` holds the move
+        // classes `j > i` that may be followed by `i` for this reason.
+        let mut commuting_order_exceptions: Vec<MoveClassMask> =
+            vec![MoveClassMask::empty(num_move_classes); num_move_classes];
 
         // Written this way so if we later iterate over all moves instead of
         // all move classes. This is because multiples can commute differently than their quantum values.
@@ -144,36 +249,92 @@ impl<TPuzzle: SemiGroupActionPuzzle> CanonicalFSM<TPuzzle> {
                 let move1_info = &generators.by_move_class.at(i)[0];
                 let move2_info = &generators.by_move_class.at(j)[0];
                 if !tpuzzle.do_moves_commute(move1_info, move2_info) {
-                    commutes[*i] &= MoveClassMask(!(1 << *j));
-                    commutes[*j] &= MoveClassMask(!(1 << *i));
+                    commutes[*i].remove(*j);
+                    commutes[*j].remove(*i);
                 }
+                // `forbidden_transitions[j]` holds the move classes that may not be followed by `j`.
                 if options.is_transition_forbidden(move1_info, move2_info) {
-                    forbidden_transitions[*i] |= MoveClassMask(1 << *j);
-                    forbidden_transitions[*j] |= MoveClassMask(1 << *i);
+                    forbidden_transitions[*j].insert(*i);
+                    if *i < *j && tpuzzle.do_moves_commute(move1_info, move2_info) {
+                        commuting_order_exceptions[*i].insert(*j);
+                    }
+                }
+            }
+        }
+        let has_forbidden_transitions = forbidden_transitions
+            .iter()
+            .any(|mask| *mask != MoveClassMask::empty(num_move_classes));
+        let canonical_order_commutes: Vec<MoveClassMask> = commutes
+            .iter()
+            .zip(&commuting_order_exceptions)
+            .map(|(commutes_mask, exceptions)| {
+                let mut mask = commutes_mask.clone();
+                for move_class_index in 0..num_move_classes {
+                    if exceptions.contains(move_class_index) {
+                        mask.remove(move_class_index);
+                    }
+                }
+                mask
+            })
+            .collect();
+        // Move classes involved in an exception can't be merged with other
+        // move classes of their commute group below.
+        let mut has_exception = vec![false; num_move_classes];
+        for (i, exceptions) in commuting_order_exceptions.iter().enumerate() {
+            for j in 0..num_move_classes {
+                if exceptions.contains(j) {
+                    has_exception[i] = true;
+                    has_exception[j] = true;
                 }
             }
         }
 
+        // Move classes with the same `commutes` mask share a commute group.
+        let mut commute_group_by_mask =
+            HashMap::<(&MoveClassMask, Option<usize>), usize>::default();
+        let commute_groups: Vec<usize> = commutes
+            .iter()
+            .enumerate()
+            .map(|(move_class_index, mask)| {
+                let num_commute_groups = commute_group_by_mask.len();
+                *commute_group_by_mask
+                    .entry((
+                        mask,
+                        has_exception[move_class_index].then_some(move_class_index),
+                    ))
+                    .or_insert(num_commute_groups)
+            })
+            .collect();
+        let mut seen_commute_groups = vec![false; commute_group_by_mask.len()];
+
         let mut next_state_lookup: IndexedVec<
             CanonicalFSMState,
             IndexedVec<MoveClassIndex, CanonicalFSMState>,
         > = IndexedVec::default();
 
+        let start_state_key = StateKey {
+            mask: MoveClassMask::empty(num_move_classes),
+            last_move_class: None,
+        };
         let mut mask_to_state = MaskToState::default();
-        mask_to_state.insert(MoveClassMask(0), CANONICAL_FSM_START_STATE);
-        let mut state_to_mask = StateToMask::new(MoveClassMask(0));
+        mask_to_state.insert(start_state_key.clone(), CANONICAL_FSM_START_STATE);
+        let mut state_to_mask = StateToMask::new(start_state_key);
         // state_to_mask, indexed by state ordinal,  holds the set of move classes in the
         // move sequence so far for which there has not been a subsequent move that does not
         // commute with that move.
-        let mut disallowed_move_classes = StateToMask::new(MoveClassMask(0));
+        let mut disallowed_move_classes: IndexedVec<CanonicalFSMState, MoveClassMask> =
+            IndexedVec::new(vec![MoveClassMask::empty(num_move_classes)]);
 
         let mut queue_index: CanonicalFSMState = CANONICAL_FSM_START_STATE;
         while Into::<usize>::into(queue_index) < state_to_mask.0.len() {
             let mut next_state: IndexedVec<MoveClassIndex, CanonicalFSMState> =
                 IndexedVec::new(vec![ILLEGAL_FSM_STATE; num_move_classes]);
 
-            let dequeue_move_class_mask: MoveClassMask = state_to_mask.get(queue_index);
-            disallowed_move_classes.push(MoveClassMask(0));
+            let StateKey {
+                mask: dequeue_move_class_mask,
+                last_move_class,
+            } = state_to_mask.get(queue_index).clone();
+            disallowed_move_classes.push(MoveClassMask::empty(num_move_classes));
 
             queue_index += CanonicalFSMState(1);
             let from_state = queue_index;
@@ -183,43 +344,49 @@ impl<TPuzzle: SemiGroupActionPuzzle> CanonicalFSM<TPuzzle> {
                 // If there's a greater move (multiple) in the state that
                 // commutes with this move's `move_class`, we can't move
                 // `move_class`.
-                skip |= (*dequeue_move_class_mask & *commutes[*move_class_index])
-                    >> (*move_class_index + 1)
-                    != 0;
-                skip |= (*dequeue_move_class_mask & *forbidden_transitions[*move_class_index]) != 0;
-                skip |= ((*dequeue_move_class_mask >> *move_class_index) & 1) != 0;
+                skip |= dequeue_move_class_mask.intersects_above(
+                    &canonical_order_commutes[*move_class_index],
+                    *move_class_index,
+                );
+                // Forbidden transitions only apply to the immediately preceding move.
+                skip |= last_move_class.is_some_and(|last_move_class| {
+                    forbidden_transitions[*move_class_index].contains(last_move_class)
+                });
+                skip |= dequeue_move_class_mask.contains(*move_class_index);
                 if skip {
-                    let new_value = MoveClassMask(
-                        *disallowed_move_classes.get(from_state) | (1 << *move_class_index),
-                    );
+                    let mut new_value = disallowed_move_classes.at(from_state).clone();
+                    new_value.insert(*move_class_index);
                     disallowed_move_classes.set(from_state, new_value);
                     continue;
                 }
-                // TODO implement bit arithmetic for whole number newtypes.
-                let mut next_state_bits = (*dequeue_move_class_mask & *commutes[*move_class_index])
-                    | (1 << *move_class_index);
+                let mut next_move_mask_class = dequeue_move_class_mask.clone();
+                next_move_mask_class &= &commutes[*move_class_index];
+                next_move_mask_class.insert(*move_class_index);
                 // If a pair of bits are set with the same commutating moves, we
-                // can clear out the higher ones. This optimization keeps the
+                // can clear out the lower ones. This optimization keeps the
                 // state count from going exponential for very big cubes.
-                for i in 0..num_move_classes {
-                    if (next_state_bits >> i) & 1 != 0 {
-                        for j in (i + 1)..num_move_classes {
-                            if ((next_state_bits >> j) & 1) != 0 && commutes[i] == commutes[j] {
-                                next_state_bits &= !(1 << i);
-                            }
+                seen_commute_groups.fill(false);
+                for i in (0..num_move_classes).rev() {
+                    if next_move_mask_class.contains(i) {
+                        if seen_commute_groups[commute_groups[i]] {
+                            next_move_mask_class.remove(i);
+                        } else {
+                            seen_commute_groups[commute_groups[i]] = true;
                         }
                     }
                 }
 
-                let next_move_mask_class = MoveClassMask(next_state_bits);
-
+                let next_state_key = StateKey {
+                    mask: next_move_mask_class,
+                    last_move_class: has_forbidden_transitions.then_some(*move_class_index),
+                };
                 next_state.set(
                     move_class_index,
-                    match mask_to_state.get(next_move_mask_class) {
+                    match mask_to_state.get(&next_state_key) {
                         None => {
                             let next_state = CanonicalFSMState(state_to_mask.0.len());
-                            mask_to_state.insert(next_move_mask_class, next_state);
-                            state_to_mask.push(next_move_mask_class);
+                            mask_to_state.insert(next_state_key.clone(), next_state);
+                            state_to_mask.push(next_state_key);
                             next_state
                         }
                         Some(state) => state,
@@ -229,10 +396,21 @@ impl<TPuzzle: SemiGroupActionPuzzle> CanonicalFSM<TPuzzle> {
             next_state_lookup.push(next_state);
         }
 
-        Ok(Self {
+        let mut canonical_fsm = Self {
             next_state_lookup,
             phantom_data: PhantomData,
-        })
+        };
+        if let Some(max_relation_length) = options.max_relation_length {
+            let redundant_sequences = find_redundant_move_class_sequences(
+                &tpuzzle,
+                &generators,
+                &canonical_fsm,
+                max_relation_length,
+            )?;
+            canonical_fsm.next_state_lookup =
+                next_state_lookup_forbidding_sequences(&canonical_fsm, &redundant_sequences);
+        }
+        Ok(canonical_fsm)
     }
 
     pub(crate) fn next_state(
@@ -250,3 +428,201 @@ impl<TPuzzle: SemiGroupActionPuzzle> CanonicalFSM<TPuzzle> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use cubing::{
+        alg::{Alg, AlgNode, Move, QuantumMove},
+        kpuzzle::KPuzzle,
+        puzzles::{cube2x2x2_kpuzzle, cube3x3x3_kpuzzle},
+    };
+
+    use crate::{
+        _internal::{
+            canonical_fsm::{
+                canonical_algs::{count_canonical_algs, enumerate_canonical_algs},
+                search_generators::SearchGenerators,
+            },
+            cli::args::MetricEnum,
+            ksolve::parse_ksolve_definition,
+        },
+        scramble::embedded_kpuzzle,
+    };
+
+    use super::{CanonicalFSM, CanonicalFSMConstructionOptions};
+
+    fn canonical_algs_at_depth_2(forbidden_transition: (&str, &str)) -> Vec<String> {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let search_generators = SearchGenerators::try_new(
+            kpuzzle,
+            vec!["R".parse().unwrap(), "L".parse().unwrap()],
+            &MetricEnum::Hand,
+            false,
+        )
+        .unwrap();
+        let canonical_fsm = CanonicalFSM::try_new(
+            kpuzzle.clone(),
+            search_generators.clone(),
+            CanonicalFSMConstructionOptions {
+                forbid_transitions_by_quantums_in_order: HashSet::from([(
+                    QuantumMove::new(forbidden_transition.0, None),
+                    QuantumMove::new(forbidden_transition.1, None),
+                )]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            count_canonical_algs(&canonical_fsm, &search_generators, 2)[2].num_canonical_algs,
+            9
+        );
+        let mut algs = vec![];
+        enumerate_canonical_algs(&canonical_fsm, &search_generators, 2, &mut |moves| {
+            algs.push(
+                Alg {
+                    nodes: moves
+                        .iter()
+                        .map(|r#move| AlgNode::MoveNode(r#move.clone()))
+                        .collect(),
+                }
+                .to_string(),
+            )
+        });
+        algs
+    }
+
+    #[test]
+    fn forbidden_transitions_between_commuting_moves_test() {
+        // `R` and `L` commute, so exactly one order of each pair must remain.
+        let algs = canonical_algs_at_depth_2(("R", "L"));
+        assert_eq!(algs.len(), 9);
+        assert!(algs.iter().all(|alg| alg.starts_with('L')));
+
+        let algs = canonical_algs_at_depth_2(("L", "R"));
+        assert_eq!(algs.len(), 9);
+        assert!(algs.iter().all(|alg| alg.starts_with('R')));
+    }
+
+    #[test]
+    fn more_than_64_move_classes_test() {
+        const NUM_MOVE_CLASSES: usize = 71;
+        let move_name = |i: usize| {
+            format!(
+                "M{}{}",
+                (b'a' + (i / 26) as u8) as char,
+                (b'a' + (i % 26) as u8) as char
+            )
+        };
+        // Moves 0/1 and 2/3 are non-commuting transpositions on a shared set.
+        // Every other move flips a piece in its own set, so it commutes with everything.
+        let mut definition = "Name Synthetic\nSet SHARED0 3 1\nSet SHARED1 3 1\n".to_owned();
+        for i in 4..NUM_MOVE_CLASSES {
+            definition += &format!("Set FLIP{} 1 2\n", i);
+        }
+        definition += "Solved\nEnd\n";
+        for i in 0..NUM_MOVE_CLASSES {
+            let orbit_lines = match i {
+                0 | 1 => format!("SHARED0\n{}\n", ["2 1 3", "1 3 2"][i]),
+                2 | 3 => format!("SHARED1\n{}\n", ["2 1 3", "1 3 2"][i - 2]),
+                _ => format!("FLIP{}\n1\n1\n", i),
+            };
+            definition += &format!("Move {}\n{}End\n", move_name(i), orbit_lines);
+        }
+        let kpuzzle = KPuzzle::try_from(parse_ksolve_definition(&definition).unwrap()).unwrap();
+        let search_generators = SearchGenerators::try_new(
+            &kpuzzle,
+            (0..NUM_MOVE_CLASSES)
+                .map(|i| move_name(i).parse().unwrap())
+                .collect(),
+            &MetricEnum::Hand,
+            false,
+        )
+        .unwrap();
+        assert_eq!(search_generators.by_move_class.len(), NUM_MOVE_CLASSES);
+        let canonical_fsm =
+            CanonicalFSM::try_new(kpuzzle, search_generators.clone(), Default::default()).unwrap();
+        let depth_counts = count_canonical_algs(&canonical_fsm, &search_generators, 2);
+        // Each of the 71 * 70 / 2 pairs of move classes has one canonical order, and the 2 non-commuting pairs have a second.
+        assert_eq!(
+            depth_counts
+                .iter()
+                .map(|depth_count| depth_count.num_canonical_algs)
+                .collect::<Vec<_>>(),
+            vec![1, 71, 2487]
+        );
+    }
+
+    // Counts the canonical algs at each depth for the generators and
+    // adjacent-move constraint used by a scramble finder, and checks that the
+    // constrained moves are never adjacent.
+    fn scramble_finder_depth_counts(
+        kpuzzle: &KPuzzle,
+        generator_moves: &[&str],
+        forbidden_adjacent_moves: (&str, &str),
+        max_depth: usize,
+    ) -> Vec<u128> {
+        let search_generators = SearchGenerators::try_new(
+            kpuzzle,
+            generator_moves
+                .iter()
+                .map(|move_str| move_str.parse().unwrap())
+                .collect(),
+            &MetricEnum::Hand,
+            false,
+        )
+        .unwrap();
+        let canonical_fsm = CanonicalFSM::try_new(
+            kpuzzle.clone(),
+            search_generators.clone(),
+            CanonicalFSMConstructionOptions {
+                forbid_transitions_by_quantums_either_direction: HashSet::from([(
+                    QuantumMove::new(forbidden_adjacent_moves.0, None),
+                    QuantumMove::new(forbidden_adjacent_moves.1, None),
+                )]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let is_forbidden = |r#move: &Move| {
+            let family = &r#move.quantum.family;
+            family == forbidden_adjacent_moves.0 || family == forbidden_adjacent_moves.1
+        };
+        enumerate_canonical_algs(
+            &canonical_fsm,
+            &search_generators,
+            max_depth,
+            &mut |moves| {
+                assert!(moves
+                    .windows(2)
+                    .all(|pair| !(is_forbidden(&pair[0]) && is_forbidden(&pair[1]))));
+            },
+        );
+        count_canonical_algs(&canonical_fsm, &search_generators, max_depth)
+            .iter()
+            .map(|depth_count| depth_count.num_canonical_algs)
+            .collect()
+    }
+
+    // Forbidden transitions used to be checked against every move class in the
+    // commuting mask rather than just the previous move. The expected counts
+    // were recorded with that implementation, to show that the constraints
+    // used by the scramble finders still prune exactly the same move sequences.
+    #[test]
+    fn scramble_finder_forbidden_transitions_test() {
+        assert_eq!(
+            scramble_finder_depth_counts(
+                embedded_kpuzzle("baby_fto").unwrap(),
+                &["U", "L", "F", "R", "BR"],
+                ("L", "BR"),
+                6
+            ),
+            vec![1, 10, 72, 528, 3840, 28032, 204288]
+        );
+        assert_eq!(
+            scramble_finder_depth_counts(cube2x2x2_kpuzzle(), &["U", "L", "F", "R"], ("L", "R"), 6),
+            vec![1, 12, 90, 702, 5346, 41310, 316386]
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub mod canonical_algs;
 #[allow(clippy::module_inception)] // TODO
 pub mod canonical_fsm;
+mod move_class_relations;
 pub mod search_generators;
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use thousands::Separable;
+
+use crate::_internal::{
+    errors::SearchError, puzzle_traits::puzzle_traits::SemiGroupActionPuzzle,
+    search::indexed_vec::IndexedVec,
+};
+
+use super::{
+    canonical_fsm::{
+        CanonicalFSM, CanonicalFSMState, MoveClassIndex, CANONICAL_FSM_START_STATE,
+        ILLEGAL_FSM_STATE,
+    },
+    search_generators::SearchGenerators,
+};
+
+type NextStateLookup = IndexedVec<CanonicalFSMState, IndexedVec<MoveClassIndex, CanonicalFSMState>>;
+
+// Every enumerated transformation is kept in memory, so this also bounds memory usage.
+const MAX_RELATION_SEARCH_NODES: usize = 1 << 22;
+
+fn unsupported_puzzle_error() -> SearchError {
+    SearchError {
+        description: "Finding relations between move sequences is not supported for this puzzle."
+            .to_owned(),
+    }
+}
+
+/// Finds move class sequences (of length up to `max_relation_length`) for
+/// which every move sequence has the same effect as a move sequence that
+/// comes earlier in the order that sequences are enumerated (shorter
+/// sequences first). Such sequences never need to be searched.
+///
+/// Only sequences that are allowed by `canonical_fsm` and do not contain a
+/// shorter returned sequence are considered. Returns an error if this would
+/// enumerate more than `MAX_RELATION_SEARCH_NODES` move sequences.
+pub(crate) fn find_redundant_move_class_sequences<TPuzzle: SemiGroupActionPuzzle>(
+    tpuzzle: &TPuzzle,
+    generators: &SearchGenerators<TPuzzle>,
+    canonical_fsm: &CanonicalFSM<TPuzzle>,
+    max_relation_length: usize,
+) -> Result<Vec<Vec<MoveClassIndex>>, SearchError> {
+    let Some(first_move_info) = generators.flat.0.first() else {
+        return Ok(vec![]);
+    };
+    let mut relation_search = RelationSearch {
+        tpuzzle,
+        generators,
+        canonical_fsm,
+        seen_transformations: HashMap::default(),
+        num_nodes: 0,
+        max_relation_length,
+        all_redundant: HashMap::default(),
+        move_class_sequence: vec![],
+        redundant_sequences: vec![],
+    };
+    // The identity is the power of a move just before its powers cycle back to the move itself.
+    let mut power = first_move_info.transformation.clone();
+    loop {
+        let next_power = tpuzzle
+            .transformation_apply_transformation(&power, &first_move_info.transformation)
+            .ok_or_else(unsupported_puzzle_error)?;
+        if next_power == first_move_info.transformation {
+            break;
+        }
+        power = next_power;
+    }
+    relation_search.insert_if_new(power)?;
+
+    for length in 1..=max_relation_length {
+        relation_search.all_redundant.clear();
+        relation_search.recurse(None, CANONICAL_FSM_START_STATE, length)?;
+        let mut newly_redundant: Vec<Vec<MoveClassIndex>> = relation_search
+            .all_redundant
+            .drain()
+            .filter_map(|(sequence, all_redundant)| all_redundant.then_some(sequence))
+            .collect();
+        newly_redundant.sort();
+        relation_search.redundant_sequences.extend(newly_redundant);
+    }
+    Ok(relation_search.redundant_sequences)
+}
+
+struct RelationSearch<'a, TPuzzle: SemiGroupActionPuzzle> {
+    tpuzzle: &'a TPuzzle,
+    generators: &'a SearchGenerators<TPuzzle>,
+    canonical_fsm: &'a CanonicalFSM<TPuzzle>,
+    // The distinct transformations of all move sequences enumerated so far, by hash.
+    seen_transformations: HashMap<u64, Vec<TPuzzle::Transformation>>,
+    num_nodes: usize,
+    max_relation_length: usize,
+    // For each move class sequence of the current length: whether all of its move sequences so far were redundant.
+    all_redundant: HashMap<Vec<MoveClassIndex>, bool>,
+    move_class_sequence: Vec<MoveClassIndex>,
+    redundant_sequences: Vec<Vec<MoveClassIndex>>,
+}
+
+impl<TPuzzle: SemiGroupActionPuzzle> RelationSearch<'_, TPuzzle> {
+    /// Returns whether `transformation` had not been seen before. Transformations
+    /// with the same hash are compared directly, so hash collisions are harmless.
+    fn insert_if_new(
+        &mut self,
+        transformation: TPuzzle::Transformation,
+    ) -> Result<bool, SearchError> {
+        let hash = self
+            .tpuzzle
+            .transformation_hash_u64(&transformation)
+            .ok_or_else(unsupported_puzzle_error)?;
+        let transformations_with_hash = self.seen_transformations.entry(hash).or_default();
+        if transformations_with_hash.contains(&transformation) {
+            return Ok(false);
+        }
+        transformations_with_hash.push(transformation);
+        Ok(true)
+    }
+
+    fn recurse(
+        &mut self,
+        transformation: Option<&TPuzzle::Transformation>,
+        fsm_state: CanonicalFSMState,
+        remaining_depth: usize,
+    ) -> Result<(), SearchError> {
+        if remaining_depth == 0 {
+            let transformation =
+                transformation.expect("Internal error: relations must have at least one move.");
+            self.num_nodes += 1;
+            if self.num_nodes > MAX_RELATION_SEARCH_NODES {
+                return Err(SearchError {
+                    description: format!(
+                        "Finding relations up to length {} would enumerate more than {} move sequences. Use a smaller canonical relation length.",
+                        self.max_relation_length,
+                        MAX_RELATION_SEARCH_NODES.separate_with_underscores()
+                    ),
+                });
+            }
+            let redundant = !self.insert_if_new(transformation.clone())?;
+            *self
+                .all_redundant
+                .entry(self.move_class_sequence.clone())
+                .or_insert(true) &= redundant;
+            return Ok(());
+        }
+        for (move_class_index, move_transformation_multiples) in
+            self.generators.by_move_class.iter()
+        {
+            let Some(next_fsm_state) = self.canonical_fsm.next_state(fsm_state, move_class_index)
+            else {
+                continue;
+            };
+            self.move_class_sequence.push(move_class_index);
+            if !ends_with_any(&self.move_class_sequence, &self.redundant_sequences) {
+                for move_transformation_info in move_transformation_multiples {
+                    let next_transformation = match transformation {
+                        None => move_transformation_info.transformation.clone(),
+                        Some(transformation) => self
+                            .tpuzzle
+                            .transformation_apply_transformation(
+                                transformation,
+                                &move_transformation_info.transformation,
+                            )
+                            .ok_or_else(unsupported_puzzle_error)?,
+                    };
+                    self.recurse(
+                        Some(&next_transformation),
+                        next_fsm_state,
+                        remaining_depth - 1,
+                    )?;
+                }
+            }
+            self.move_class_sequence.pop();
+        }
+        Ok(())
+    }
+}
+
+fn ends_with_any(sequence: &[MoveClassIndex], suffixes: &[Vec<MoveClassIndex>]) -> bool {
+    suffixes.iter().any(|suffix| sequence.ends_with(suffix))
+}
+
+/// Combines the transitions of `canonical_fsm` with a matcher (in the style of
+/// Aho–Corasick) that makes a transition illegal if it completes any of the
+/// `forbidden_sequences`.
+///
+/// Each state of the result corresponds to a state of `canonical_fsm` together
+/// with the longest suffix of the move classes so far that is a proper prefix
+/// of a forbidden sequence.
+pub(crate) fn next_state_lookup_forbidding_sequences<TPuzzle: SemiGroupActionPuzzle>(
+    canonical_fsm: &CanonicalFSM<TPuzzle>,
+    forbidden_sequences: &[Vec<MoveClassIndex>],
+) -> NextStateLookup {
+    let num_move_classes = canonical_fsm
+        .next_state_lookup
+        .at(CANONICAL_FSM_START_STATE)
+        .len();
+    let mut next_state_lookup = NextStateLookup::default();
+    let mut queue: Vec<(CanonicalFSMState, Vec<MoveClassIndex>)> =
+        vec![(CANONICAL_FSM_START_STATE, vec![])];
+    let mut state_by_key = HashMap::from([(queue[0].clone(), CANONICAL_FSM_START_STATE)]);
+    let mut queue_index = 0;
+    while queue_index < queue.len() {
+        let (base_state, suffix) = queue[queue_index].clone();
+        queue_index += 1;
+        let mut next_state: IndexedVec<MoveClassIndex, CanonicalFSMState> =
+            IndexedVec::new(vec![ILLEGAL_FSM_STATE; num_move_classes]);
+        for move_class_index in (0..num_move_classes).map(MoveClassIndex) {
+            let Some(next_base_state) = canonical_fsm.next_state(base_state, move_class_index)
+            else {
+                continue;
+            };
+            let mut next_suffix = suffix.clone();
+            next_suffix.push(move_class_index);
+            if ends_with_any(&next_suffix, forbidden_sequences) {
+                continue;
+            }
+            let keep = (1..=next_suffix.len())
+                .rev()
+                .find(|&length| {
+                    let tail = &next_suffix[next_suffix.len() - length..];
+                    forbidden_sequences.iter().any(|forbidden_sequence| {
+                        forbidden_sequence.len() > length && forbidden_sequence.starts_with(tail)
+                    })
+                })
+                .unwrap_or(0);
+            let key = (
+                next_base_state,
+                next_suffix.split_off(next_suffix.len() - keep),
+            );
+            let num_states = queue.len();
+            let state = *state_by_key.entry(key.clone()).or_insert_with(|| {
+                queue.push(key);
+                CanonicalFSMState(num_states)
+            });
+            next_state.set(move_class_index, state);
+        }
+        next_state_lookup.push(next_state);
+    }
+    next_state_lookup
+}
@@ -1,15 +1,19 @@
 use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::generator::generate;
 use clap_complete::{Generator, Shell};
-use cubing::alg::{Alg, Move};
+use cubing::alg::{Alg, Move, QuantumMove};
 use cubing::kpuzzle::KPuzzle;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::io::stdout;
 use std::path::PathBuf;
-use std::process::exit;
 use std::str::FromStr;
 
+use crate::_internal::canonical_fsm::canonical_fsm::{
+    CanonicalFSMConstraints, CanonicalFSMConstructionOptions,
+};
+use crate::_internal::cli::io::read_to_json;
+use crate::_internal::errors::ArgumentError;
 use crate::_internal::puzzle_traits::puzzle_traits::GroupActionPuzzle;
 use crate::_internal::search::prune_table_trait::Depth;
 
@@ -32,6 +36,7 @@ pub struct TwsearchArgs {
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)] // Only constructed once, when parsing the command line.
 pub enum CliCommand {
     /// Run a single search.
     Search(SearchCommandArgs),
@@ -41,13 +46,14 @@ pub enum CliCommand {
     /// Use with: https://experiments.cubing.net/cubing.js/twsearch/text-ui.html
     Serve(ServeCommandArgs),
 
-    // TOOD: Detect identical pieces and warn/error (and give advice on how to run the "same" search with fully-distinguishable pieces).
     /// Run the Schreier-Sims algorithm to calculate the number of reachable patterns.
     ///
-    /// Warning: Does NOT account for identical pieces.
+    /// Identical pieces and orientation wildcards in the default pattern are accounted for.
     SchreierSims(SchreierSimsArgs),
     /// Enumerate the entire pattern graph and print antipodes.
     GodsAlgorithm(GodsAlgorithmArgs),
+    /// Enumerate the distances from a coset pattern to every element of a subgroup.
+    Coset(CosetArgs),
     /// Run a timing test for given definition.
     TimingTest(TimingTestArgs),
     // Enumerate canonical algs (move sequences) at iterative depths.
@@ -60,13 +66,19 @@ pub enum CliCommand {
     /// Run an internal benchmark suite.
     Benchmark(BenchmarkArgs),
 
+    /// List the built-in puzzle definitions (for use with `--puzzle`).
+    ListPuzzles,
+
+    /// Print the effect of an alg as disjoint cycles (with orientation twists) for each orbit.
+    AlgInfo(AlgInfoArgs),
+
     /// Print completions for the given shell.
     Completions(CompletionsArgs),
 }
 
 #[derive(Args, Debug, Default)]
 pub struct CommonSearchArgs {
-    /// Check that a position is valid before attempting to solve it. This may take extra time or memory for large puzzles.
+    /// Check that a position is valid before attempting to solve it. By default, this checks orbit invariants (orientation sums and permutation parities). With `always`, this also checks group membership, which may take extra time or memory for large puzzles.
     #[clap(long/*, visible_alias = "checkbeforesolve" */)]
     pub check_before_solve: Option<EnableAutoAlwaysNeverValueEnum>,
 
@@ -75,7 +87,7 @@ pub struct CommonSearchArgs {
     #[clap(long/*, visible_alias = "randomstart"`*/)]
     pub random_start: bool,
 
-    /// Print all optimal solutions.
+    /// Print all optimal solutions. (Not implemented yet: searches with this flag return an error.)
     #[clap(long)]
     pub all_optimal: bool,
 
@@ -93,10 +105,84 @@ pub struct CommonSearchArgs {
     #[clap(long/* , visible_alias = "maxdepth" */)]
     pub max_depth: Option<Depth>,
 
+    #[command(flatten)]
+    pub canonical_fsm_args: CanonicalFSMArgs,
+
     #[command(flatten)]
     pub performance_args: PerformanceArgs,
 }
 
+#[derive(Args, Debug, Default)]
+pub struct CanonicalFSMArgs {
+    /// Use search-based canonical sequences: also prune move sequences up to
+    /// this length that have the same effect as a shorter (or otherwise
+    /// earlier) move sequence.
+    #[clap(long, help_heading = "Canonical FSM"/* , visible_alias = "newcanon" */)]
+    pub canonical_relation_length: Option<usize>,
+
+    /// Forbid moves with these quantums from being adjacent (in either
+    /// order), as a comma-separated list of pairs. For example: `L BR,U D`
+    #[clap(long, help_heading = "Canonical FSM")]
+    pub forbid_adjacent_moves: Option<String>,
+
+    /// Forbid a move with the first quantum from being followed by a move
+    /// with the second quantum, as a comma-separated list of pairs. For
+    /// example: `x y,y x`. If the moves commute, the other order is still
+    /// allowed (so that search can find the same solutions).
+    #[clap(long, help_heading = "Canonical FSM")]
+    pub forbid_transitions: Option<String>,
+
+    /// A JSON file with forbidden adjacent moves and transitions, in addition to the ones passed using flags. For example:
+    /// `{ "forbiddenAdjacentMoves": [["L", "BR"]], "forbiddenTransitions": [["x", "y"]] }`
+    #[clap(long, help_heading = "Canonical FSM")]
+    pub canonical_fsm_constraints: Option<PathBuf>,
+}
+
+impl CanonicalFSMArgs {
+    pub fn construction_options(&self) -> Result<CanonicalFSMConstructionOptions, ArgumentError> {
+        let mut constraints: CanonicalFSMConstraints = match &self.canonical_fsm_constraints {
+            Some(path_buf) => read_to_json(path_buf)?,
+            None => Default::default(),
+        };
+        constraints
+            .forbidden_adjacent_moves
+            .extend(parse_quantum_move_pairs(&self.forbid_adjacent_moves)?);
+        constraints
+            .forbidden_transitions
+            .extend(parse_quantum_move_pairs(&self.forbid_transitions)?);
+        Ok(CanonicalFSMConstructionOptions {
+            max_relation_length: self.canonical_relation_length,
+            ..(&constraints).into()
+        })
+    }
+}
+
+fn parse_quantum_move_pairs(
+    input: &Option<String>,
+) -> Result<Vec<(QuantumMove, QuantumMove)>, ArgumentError> {
+    let Some(input) = input else {
+        return Ok(vec![]);
+    };
+    input
+        .split(',')
+        .map(|pair_str| {
+            let quantum_moves = pair_str
+                .split_whitespace()
+                .map(|quantum_move_str| quantum_move_str.parse::<QuantumMove>())
+                .collect::<Result<Vec<QuantumMove>, _>>();
+            match quantum_moves.as_deref() {
+                Ok([first, second]) => Ok((first.clone(), second.clone())),
+                _ => Err(ArgumentError {
+                    description: format!(
+                        "Invalid move pair (expected two move quantums separated by a space): {}",
+                        pair_str
+                    ),
+                }),
+            }
+        })
+        .collect()
+}
+
 #[derive(Args, Debug)]
 pub struct SearchCommandArgs {
     #[command(flatten)]
@@ -104,6 +190,15 @@ pub struct SearchCommandArgs {
 
     #[command(flatten)]
     pub optional: SearchCommandOptionalArgs,
+
+    /// Print the solutions (with metadata) in a machine-readable format instead of a human-readable summary.
+    #[clap(long)]
+    pub output: Option<SearchOutputFormat>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SearchOutputFormat {
+    Json,
 }
 
 #[derive(Args, Debug, Default)]
@@ -190,35 +285,38 @@ pub struct CustomGenerators {
 }
 
 impl GeneratorArgs {
-    pub fn parse(&self) -> Generators {
-        let moves = parse_comma_separated(&self.generator_moves_string);
-        let algs = parse_comma_separated(&self.generator_algs);
-        match (moves, algs) {
+    pub fn parse(&self) -> Result<Generators, ArgumentError> {
+        let moves = parse_comma_separated(&self.generator_moves_string, "--generator-moves")?;
+        let algs = parse_comma_separated(&self.generator_algs, "--generator-algs")?;
+        Ok(match (moves, algs) {
             (None, None) => Generators::Default,
             (moves, algs) => Generators::Custom(CustomGenerators {
                 moves: moves.unwrap_or_default(),
                 algs: algs.unwrap_or_default(),
             }),
-        }
+        })
     }
 }
 
 fn parse_comma_separated<T: FromStr<Err = E>, E: Display>(
     input: &Option<String>,
-) -> Option<Vec<T>> {
-    input.as_ref().map(|moves| {
-        moves
-            .split(',')
-            .by_ref()
-            .map(|move_str| match move_str.parse::<T>() {
-                Ok(r#move) => r#move,
-                Err(err) => {
-                    eprintln!("Invalid move ({}): {}", err, move_str);
-                    panic!("Exiting due to invalid move.")
-                }
+    flag_name: &str,
+) -> Result<Option<Vec<T>>, ArgumentError> {
+    let Some(input) = input else {
+        return Ok(None);
+    };
+    input
+        .split(',')
+        .map(|entry| {
+            entry.parse::<T>().map_err(|err| ArgumentError {
+                description: format!(
+                    "Invalid entry `{}` for `{}` ({}). Entries must be separated by commas without spaces, e.g. `U,F,R2`.",
+                    entry, flag_name, err
+                ),
             })
-            .collect()
-    })
+        })
+        .collect::<Result<Vec<T>, ArgumentError>>()
+        .map(Some)
 }
 
 #[derive(Args, Debug, Default)]
@@ -303,6 +401,26 @@ pub struct GodsAlgorithmArgs {
 
     #[command(flatten)]
     pub optional: GodsAlgorithmOptionalArgs,
+
+    /// Save the table to the given file after enumeration. Patterns can later be solved directly from the saved table.
+    /// Not supported with `--disk-backed` (the table is not kept in memory) or
+    /// `--symmetry-reduced` (only one pattern per symmetry class is kept).
+    #[clap(
+        long,
+        help_heading = "Persistence",
+        conflicts_with_all = ["disk_backed", "symmetry_reduced"]
+    )]
+    pub save_table: Option<PathBuf>,
+
+    /// Print the distance distribution (with metadata) in a machine-readable format instead of a human-readable summary.
+    #[clap(long)]
+    pub output: Option<GodsAlgorithmOutputFormat>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GodsAlgorithmOutputFormat {
+    Json,
+    Csv,
 }
 
 #[derive(Args, Debug, Default)]
@@ -330,6 +448,68 @@ pub struct GodsAlgorithmOptionalArgs {
 
     #[command(flatten)]
     pub performance_args: PerformanceArgs,
+
+    /// Write a checkpoint to this directory after each completed depth.
+    #[clap(long, help_heading = "Persistence")]
+    pub checkpoint_dir: Option<PathBuf>,
+
+    /// Resume from the last completed depth of the checkpoint in `--checkpoint-dir`.
+    #[clap(long, help_heading = "Persistence", requires = "checkpoint_dir")]
+    pub resume: bool,
+
+    /// Keep only the most recent depths on disk instead of the full table in memory. Uses `--memory-MiB` (default: 1024) as the approximate memory cap.
+    #[clap(long, help_heading = "Performance")]
+    pub disk_backed: bool,
+
+    /// Directory for temporary files of `--disk-backed` (default: the system temp directory).
+    #[clap(long, help_heading = "Performance", requires = "disk_backed")]
+    pub temp_dir: Option<PathBuf>,
+
+    /// Store only one pattern per symmetry class, and report the number of classes at each depth in addition to the number of patterns.
+    /// Symmetries are the relabelings of pieces that map each move of the definition to a move or its inverse (including reflections, so a cube has up to 48 symmetries).
+    #[clap(long)]
+    pub symmetry_reduced: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CosetArgs {
+    #[command(flatten)]
+    pub def_args: DefOnlyArgs,
+
+    #[command(flatten)]
+    pub optional: CosetOptionalArgs,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct CosetOptionalArgs {
+    /// A comma-separated list of moves that generate the subgroup. All multiples of these moves are included.
+    #[clap(
+        long,
+        required_unless_present = "subgroup_mask",
+        conflicts_with = "subgroup_mask"
+    )]
+    pub subgroup_moves: Option<String>,
+
+    /// A mask pattern file. The subgroup consists of all patterns that look the same as the default pattern when the mask is applied.
+    #[clap(long)]
+    pub subgroup_mask: Option<PathBuf>,
+
+    /// A pattern file for the coset. Defaults to the default pattern (i.e. the subgroup itself).
+    #[clap(long)]
+    pub coset_pattern: Option<PathBuf>,
+
+    /// Required for `--subgroup-mask`.
+    #[clap(long)]
+    pub max_depth: Option<usize>,
+
+    #[command(flatten)]
+    pub generator_args: GeneratorArgs,
+
+    #[command(flatten)]
+    pub metric_args: MetricArgs,
+
+    #[command(flatten)]
+    pub memory_args: MemoryArgs,
 }
 
 #[derive(Args, Debug)]
@@ -342,6 +522,15 @@ pub struct TimingTestArgs {
 
     #[command(flatten)]
     pub performance_args: PerformanceArgs,
+
+    /// Print the measurements in a machine-readable format instead of a human-readable summary.
+    #[clap(long)]
+    pub output: Option<TimingTestOutputFormat>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TimingTestOutputFormat {
+    Json,
 }
 
 #[derive(Args, Debug)]
@@ -357,6 +546,17 @@ pub struct CanonicalAlgsArgs {
 
     #[command(flatten)]
     pub performance_args: PerformanceArgs,
+
+    /// Count canonical algs up to this depth (inclusive). Defaults to 10.
+    #[clap(long)]
+    pub max_depth: Option<usize>,
+
+    /// Print every canonical alg up to this depth (inclusive), one per line.
+    #[clap(long)]
+    pub list_depth: Option<usize>,
+
+    #[command(flatten)]
+    pub canonical_fsm_args: CanonicalFSMArgs,
 }
 
 #[derive(Clone, Args, Debug)]
@@ -421,10 +621,25 @@ pub struct ScrambleFinderSolveArgs {
     pub scramble_setup_alg: String,
 }
 
+#[derive(Args, Debug)]
+pub struct AlgInfoArgs {
+    #[command(flatten)]
+    pub def_args: DefOnlyArgs,
+
+    /// The alg to describe.
+    #[clap(long)]
+    pub alg: String,
+}
+
 #[derive(Args, Debug)]
 pub struct DefOnlyArgs {
-    #[clap()]
-    pub def_file: PathBuf,
+    /// A puzzle definition: either a JSON `KPuzzleDefinition` or a ksolve definition (e.g. `.tws`).
+    #[clap(required_unless_present = "puzzle", conflicts_with = "puzzle")]
+    pub def_file: Option<PathBuf>,
+
+    /// Use a built-in puzzle definition instead of a definition file. Run `twsearch list-puzzles` to see the available IDs.
+    #[clap(long)]
+    pub puzzle: Option<String>,
     // TODO: remove this
     // #[clap(long)]
     // pub debug_print_serialized_json: bool,
@@ -438,12 +653,26 @@ pub struct RequiredDefArgs {
 
 #[derive(Args, Debug, Default)]
 pub struct ScrambleAndTargetPatternOptionalArgs {
-    /// Solve all the scrambles from the given file.
+    /// Solve all the scrambles from the given file. With `--puzzle`, use `--scramble-file` instead.
     #[clap(help_heading = "Scramble input", group = "scramble_input")]
     pub scramble_file: Option<PathBuf>,
+    /// Solve all the scrambles from the given file (same as the positional argument, but can be used with `--puzzle`).
+    #[clap(
+        long = "scramble-file",
+        value_name = "SCRAMBLE_FILE",
+        help_heading = "Scramble input",
+        group = "scramble_input"
+    )]
+    pub scramble_file_flag: Option<PathBuf>,
     /// Solve a single scramble specified directly as an argument.
     #[clap(long/*, visible_alias = "scramblealg" */, help_heading = "Scramble input", group = "scramble_input")]
     pub scramble_alg: Option<String>, // TODO: Make `Alg` implement `Send` (e.g. by using `Arc`, possibly through an optional feature or a separate thread-safe `Alg` struct)
+    /// Solve a single scramble specified in cycle notation relative to the default pattern, e.g. `CORNERS:(0 1 2)+ EDGES:(3 7)`.
+    #[clap(long, help_heading = "Scramble input", group = "scramble_input")]
+    pub scramble_cycles: Option<String>,
+    /// Solve a single scramble specified as a facelet string (e.g. Kociemba's `UUUUUUUUURRR…` for 3x3x3). Supported for 2x2x2, 3x3x3, and 4x4x4. Pieces that look the same (e.g. the centers of a 4x4x4 with the same color) are treated as identical.
+    #[clap(long, help_heading = "Scramble input", group = "scramble_input")]
+    pub scramble_facelets: Option<String>,
     /// Solve a list of scrambles passed to standard in (separated by newlines).
     #[clap(long, help_heading = "Scramble input", group = "scramble_input"/* , visible_short_alias = 's' */)]
     pub stdin_scrambles: bool,
@@ -452,6 +681,15 @@ pub struct ScrambleAndTargetPatternOptionalArgs {
     pub experimental_target_pattern: Option<PathBuf>,
 }
 
+impl ScrambleAndTargetPatternOptionalArgs {
+    /// The scramble file, passed either positionally or using `--scramble-file`.
+    pub fn scramble_file_path(&self) -> Option<&PathBuf> {
+        self.scramble_file
+            .as_ref()
+            .or(self.scramble_file_flag.as_ref())
+    }
+}
+
 #[derive(Args, Debug, Default)]
 pub struct StartPatternArgs {
     #[clap(long)]
@@ -477,32 +715,27 @@ fn completions_for_shell(cmd: &mut clap::Command, generator: impl Generator) {
     generate(generator, cmd, "twsearch", &mut stdout());
 }
 
-pub fn get_options() -> TwsearchArgs {
-    let mut command = TwsearchArgs::command();
-
-    let args = TwsearchArgs::parse();
-    if let CliCommand::Completions(completions_args) = args.command {
-        completions_for_shell(&mut command, completions_args.shell);
-        exit(0);
-    };
+pub fn print_completions(completions_args: &CompletionsArgs) {
+    completions_for_shell(&mut TwsearchArgs::command(), completions_args.shell);
+}
 
-    args
+pub fn get_options() -> TwsearchArgs {
+    TwsearchArgs::parse()
 }
 
 fn completions_for_shell_cpp_wrapper(cmd: &mut clap::Command, generator: impl Generator) {
     generate(generator, cmd, "twsearch-cpp-wrapper", &mut stdout());
 }
 
-pub fn get_options_cpp_wrapper() -> TwsearchCppWrapperArgs {
-    let mut command = TwsearchCppWrapperArgs::command();
-
-    let args = TwsearchCppWrapperArgs::parse();
-    if let CliCommand::Completions(completions_args) = args.command {
-        completions_for_shell_cpp_wrapper(&mut command, completions_args.shell);
-        exit(0);
-    };
+pub fn print_completions_cpp_wrapper(completions_args: &CompletionsArgs) {
+    completions_for_shell_cpp_wrapper(
+        &mut TwsearchCppWrapperArgs::command(),
+        completions_args.shell,
+    );
+}
 
-    args
+pub fn get_options_cpp_wrapper() -> TwsearchCppWrapperArgs {
+    TwsearchCppWrapperArgs::parse()
 }
 
 ////////
@@ -532,4 +765,58 @@ pub struct ServeClientArgs {
     pub start_prune_depth: Option<Depth>,
     pub quantum_metric: Option<bool>, // TODO: enum
     pub generator_moves: Option<Vec<Move>>,
+    pub canonical_fsm_constraints: Option<CanonicalFSMConstraints>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use clap::Parser;
+
+    use super::{CliCommand, TwsearchArgs};
+
+    #[test]
+    fn puzzle_and_scramble_file_args_test() {
+        let args = TwsearchArgs::try_parse_from([
+            "twsearch",
+            "search",
+            "--puzzle",
+            "3x3x3",
+            "--scramble-file",
+            "scrambles.txt",
+        ])
+        .unwrap();
+        let CliCommand::Search(search_command_args) = args.command else {
+            panic!("Expected a search command.");
+        };
+        assert_eq!(
+            search_command_args
+                .optional
+                .scramble_and_target_pattern_optional_args
+                .scramble_file_path(),
+            Some(&PathBuf::from("scrambles.txt"))
+        );
+
+        // A positional definition file is never reinterpreted as a scramble file.
+        assert!(TwsearchArgs::try_parse_from([
+            "twsearch",
+            "search",
+            "samples/main/3x3x3.tws",
+            "--puzzle",
+            "3x3x3",
+        ])
+        .is_err());
+        assert!(TwsearchArgs::try_parse_from([
+            "twsearch",
+            "search",
+            "--puzzle",
+            "3x3x3",
+            "--scramble-file",
+            "scrambles.txt",
+            "--scramble-alg",
+            "R U",
+        ])
+        .is_err());
+    }
 }
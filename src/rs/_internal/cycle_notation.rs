@@ -0,0 +1,375 @@
+use std::{iter::Peekable, str::Chars};
+
+use cubing::kpuzzle::{KPattern, KPuzzle, KPuzzleOrbitInfo, KTransformation};
+
+use super::errors::ArgumentError;
+
+// Cycle notation lists the cycles of each orbit after the orbit name, e.g.:
+//
+//     CORNERS:(0 1 2)+ (3 4-) EDGES:(3 7)
+//
+// In a cycle `(a b c)`, the piece at position `a` moves to position `b`, the
+// piece at `b` moves to `c`, and the piece at `c` moves to `a`. A twist after a
+// position (`+`, `-`, `+2`, …) is added to the orientation of the piece that
+// moves to that position. A twist after the closing parenthesis is added to
+// the first position of the cycle. Positions that are not listed are unchanged.
+
+fn parse_error(description: impl Into<String>) -> ArgumentError {
+    ArgumentError {
+        description: format!("Invalid cycle notation: {}", description.into()),
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Option<u32> {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits.parse().ok()
+}
+
+// Parses an optional twist (`+`, `-`, `+2`, `-2`, …) and returns it modulo `num_orientations`.
+fn parse_twist(
+    chars: &mut Peekable<Chars>,
+    orbit_info: &KPuzzleOrbitInfo,
+) -> Result<u8, ArgumentError> {
+    let sign = match chars.peek() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return Ok(0),
+    };
+    chars.next();
+    let amount = parse_number(chars).unwrap_or(1) as i64;
+    if orbit_info.num_orientations == 1 {
+        return Err(parse_error(format!(
+            "pieces in orbit {} cannot be twisted",
+            orbit_info.name
+        )));
+    }
+    Ok((sign * amount).rem_euclid(orbit_info.num_orientations as i64) as u8)
+}
+
+/// Parses cycle notation (e.g. `CORNERS:(0 1 2)+ EDGES:(3 7)`) into a transformation.
+pub fn parse_cycle_notation(
+    kpuzzle: &KPuzzle,
+    input: &str,
+) -> Result<KTransformation, ArgumentError> {
+    let mut transformation = kpuzzle.identity_transformation();
+    let mut chars = input.chars().peekable();
+    let mut current_orbit: Option<(&KPuzzleOrbitInfo, Vec<bool>)> = None;
+    let mut seen_orbits: Vec<&str> = vec![];
+    loop {
+        skip_whitespace(&mut chars);
+        let Some(&c) = chars.peek() else {
+            break;
+        };
+        if c == '(' {
+            chars.next();
+            let Some((orbit_info, seen_positions)) = current_orbit.as_mut() else {
+                return Err(parse_error(
+                    "expected an orbit name (e.g. `CORNERS:`) before the first cycle",
+                ));
+            };
+            let mut cycle: Vec<(u8, u8)> = vec![];
+            loop {
+                skip_whitespace(&mut chars);
+                if chars.next_if_eq(&')').is_some() {
+                    break;
+                }
+                let Some(position) = parse_number(&mut chars) else {
+                    return Err(parse_error(match chars.peek() {
+                        Some(c) => format!("unexpected character `{}` in a cycle", c),
+                        None => "missing `)`".to_owned(),
+                    }));
+                };
+                if position >= orbit_info.num_pieces as u32 {
+                    return Err(parse_error(format!(
+                        "position {} is out of range for orbit {} (which has {} pieces)",
+                        position, orbit_info.name, orbit_info.num_pieces
+                    )));
+                }
+                if std::mem::replace(&mut seen_positions[position as usize], true) {
+                    return Err(parse_error(format!(
+                        "position {} of orbit {} is listed more than once",
+                        position, orbit_info.name
+                    )));
+                }
+                let twist = parse_twist(&mut chars, orbit_info)?;
+                cycle.push((position as u8, twist));
+            }
+            let Some(first) = cycle.first_mut() else {
+                return Err(parse_error("empty cycle"));
+            };
+            first.1 =
+                (first.1 + parse_twist(&mut chars, orbit_info)?) % orbit_info.num_orientations;
+            for (i, &(position, twist)) in cycle.iter().enumerate() {
+                let from = cycle[(i + cycle.len() - 1) % cycle.len()].0;
+                transformation.set_permutation_idx(orbit_info, position, from);
+                transformation.set_orientation_delta(orbit_info, position, twist);
+            }
+            continue;
+        }
+        let mut orbit_name = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+            orbit_name.push(c);
+        }
+        if orbit_name.is_empty() {
+            return Err(parse_error(format!("unexpected character `{}`", c)));
+        }
+        if chars.next_if_eq(&':').is_none() {
+            return Err(parse_error(format!(
+                "expected `:` after orbit name {}",
+                orbit_name
+            )));
+        }
+        let Some(orbit_info) = kpuzzle
+            .data
+            .ordered_orbit_info
+            .iter()
+            .find(|orbit_info| orbit_info.name.0 == orbit_name)
+        else {
+            return Err(parse_error(format!("unknown orbit: {}", orbit_name)));
+        };
+        if seen_orbits.contains(&orbit_info.name.0.as_str()) {
+            return Err(parse_error(format!(
+                "orbit {} is listed more than once",
+                orbit_name
+            )));
+        }
+        seen_orbits.push(&orbit_info.name.0);
+        current_orbit = Some((orbit_info, vec![false; orbit_info.num_pieces as usize]));
+    }
+    Ok(transformation)
+}
+
+/// Parses cycle notation and applies it to the default pattern.
+pub fn parse_pattern_cycle_notation(
+    kpuzzle: &KPuzzle,
+    input: &str,
+) -> Result<KPattern, ArgumentError> {
+    Ok(kpuzzle
+        .default_pattern()
+        .apply_transformation(&parse_cycle_notation(kpuzzle, input)?))
+}
+
+fn twist_string(twist: u8, num_orientations: u8) -> String {
+    if twist == 0 {
+        "".to_owned()
+    } else if twist == 1 {
+        "+".to_owned()
+    } else if twist == num_orientations - 1 {
+        "-".to_owned()
+    } else if twist <= num_orientations / 2 {
+        format!("+{}", twist)
+    } else {
+        format!("-{}", num_orientations - twist)
+    }
+}
+
+/// A cycle of an orbit: the positions (each with the twist of the piece that moves there) in order.
+pub struct OrbitCycle {
+    pub positions: Vec<(u8, u8)>,
+}
+
+impl OrbitCycle {
+    pub fn net_twist(&self, orbit_info: &KPuzzleOrbitInfo) -> u8 {
+        (self
+            .positions
+            .iter()
+            .map(|(_, twist)| *twist as u32)
+            .sum::<u32>()
+            % orbit_info.num_orientations as u32) as u8
+    }
+
+    /// The number of times the cycle must be applied to return every piece to its original position and orientation.
+    pub fn order(&self, orbit_info: &KPuzzleOrbitInfo) -> u64 {
+        let net_twist = self.net_twist(orbit_info) as u64;
+        let num_orientations = orbit_info.num_orientations as u64;
+        self.positions.len() as u64 * (num_orientations / gcd(net_twist, num_orientations))
+    }
+
+    fn to_string(&self, orbit_info: &KPuzzleOrbitInfo) -> String {
+        format!(
+            "({})",
+            self.positions
+                .iter()
+                .map(|(position, twist)| format!(
+                    "{}{}",
+                    position,
+                    twist_string(*twist, orbit_info.num_orientations)
+                ))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )
+    }
+}
+
+/// The greatest common divisor (with `gcd(a, 0) = a`).
+pub fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The least common multiple, e.g. for combining the orders of cycles.
+pub fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+/// The disjoint cycles of a transformation in one orbit, starting each cycle at its lowest position.
+pub fn orbit_cycles(
+    transformation: &KTransformation,
+    orbit_info: &KPuzzleOrbitInfo,
+) -> Vec<OrbitCycle> {
+    let num_pieces = orbit_info.num_pieces;
+    let mut destination = vec![0; num_pieces as usize];
+    for position in 0..num_pieces {
+        destination[transformation.get_permutation_idx(orbit_info, position) as usize] = position;
+    }
+    let mut visited = vec![false; num_pieces as usize];
+    let mut cycles = vec![];
+    for start in 0..num_pieces {
+        if visited[start as usize]
+            || (transformation.get_permutation_idx(orbit_info, start) == start
+                && transformation.get_orientation_delta(orbit_info, start) == 0)
+        {
+            continue;
+        }
+        let mut positions = vec![];
+        let mut position = start;
+        loop {
+            visited[position as usize] = true;
+            positions.push((
+                position,
+                transformation.get_orientation_delta(orbit_info, position),
+            ));
+            position = destination[position as usize];
+            if position == start {
+                break;
+            }
+        }
+        cycles.push(OrbitCycle { positions });
+    }
+    cycles
+}
+
+/// Prints a transformation in cycle notation (the inverse of [`parse_cycle_notation`]). The identity is printed as an empty string.
+pub fn transformation_to_cycle_notation(
+    kpuzzle: &KPuzzle,
+    transformation: &KTransformation,
+) -> String {
+    kpuzzle
+        .data
+        .ordered_orbit_info
+        .iter()
+        .filter_map(|orbit_info| {
+            let cycles = orbit_cycles(transformation, orbit_info);
+            if cycles.is_empty() {
+                return None;
+            }
+            Some(format!(
+                "{}:{}",
+                orbit_info.name,
+                cycles
+                    .iter()
+                    .map(|cycle| cycle.to_string(orbit_info))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Prints a pattern in cycle notation, relative to the default pattern.
+///
+/// This requires the default pattern to have distinct pieces (in order, with
+/// orientation 0), and ignores orientation wildcards.
+pub fn pattern_to_cycle_notation(
+    kpuzzle: &KPuzzle,
+    pattern: &KPattern,
+) -> Result<String, ArgumentError> {
+    let default_pattern = kpuzzle.default_pattern();
+    let mut transformation = kpuzzle.identity_transformation();
+    for orbit_info in &kpuzzle.data.ordered_orbit_info {
+        let mut seen_pieces = vec![false; orbit_info.num_pieces as usize];
+        for position in 0..orbit_info.num_pieces {
+            if default_pattern.get_piece(orbit_info, position) != position
+                || default_pattern
+                    .get_orientation_with_mod(orbit_info, position)
+                    .orientation
+                    != 0
+            {
+                return Err(ArgumentError {
+                    description: format!(
+                        "Cannot print a pattern in cycle notation, because the default pattern of orbit {} does not have distinct pieces in order.",
+                        orbit_info.name
+                    ),
+                });
+            }
+            let piece = pattern.get_piece(orbit_info, position);
+            if std::mem::replace(&mut seen_pieces[piece as usize], true) {
+                return Err(ArgumentError {
+                    description: format!(
+                        "Cannot print a pattern in cycle notation, because piece {} of orbit {} appears more than once.",
+                        piece, orbit_info.name
+                    ),
+                });
+            }
+            transformation.set_permutation_idx(orbit_info, position, piece);
+            transformation.set_orientation_delta(
+                orbit_info,
+                position,
+                pattern
+                    .get_orientation_with_mod(orbit_info, position)
+                    .orientation,
+            );
+        }
+    }
+    Ok(transformation_to_cycle_notation(kpuzzle, &transformation))
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::{alg::parse_alg, puzzles::cube3x3x3_kpuzzle};
+
+    use super::{
+        parse_cycle_notation, parse_pattern_cycle_notation, pattern_to_cycle_notation,
+        transformation_to_cycle_notation,
+    };
+
+    #[test]
+    fn cycle_notation_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let transformation = parse_cycle_notation(kpuzzle, "CORNERS:(0 1 2)+ EDGES:(3 7)").unwrap();
+        assert_eq!(
+            transformation_to_cycle_notation(kpuzzle, &transformation),
+            "EDGES:(3 7) CORNERS:(0+ 1 2)"
+        );
+
+        let sexy_move = kpuzzle
+            .transformation_from_alg(parse_alg!("R U R' U'"))
+            .unwrap();
+        let cycles = transformation_to_cycle_notation(kpuzzle, &sexy_move);
+        assert_eq!(parse_cycle_notation(kpuzzle, &cycles).unwrap(), sexy_move);
+
+        let pattern = kpuzzle
+            .default_pattern()
+            .apply_alg(parse_alg!("R U"))
+            .unwrap();
+        let cycles = pattern_to_cycle_notation(kpuzzle, &pattern).unwrap();
+        assert_eq!(
+            parse_pattern_cycle_notation(kpuzzle, &cycles).unwrap(),
+            pattern
+        );
+
+        assert!(parse_cycle_notation(kpuzzle, "CORNERS:(0 1 0)").is_err());
+        assert!(parse_cycle_notation(kpuzzle, "WINGS:(0 1)").is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use crate::scramble::{EventError, PuzzleError};
 
 #[derive(derive_more::From, Debug)]
@@ -8,11 +10,32 @@ pub enum CommandError {
     EventError(EventError),
 }
 
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::ArgumentError(e) => e.fmt(f),
+            CommandError::SearchError(e) => e.fmt(f),
+            CommandError::PuzzleError(e) => e.fmt(f),
+            CommandError::EventError(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
 #[derive(Debug)]
 pub struct ArgumentError {
     pub description: String,
 }
 
+impl Display for ArgumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl std::error::Error for ArgumentError {}
+
 impl From<&str> for ArgumentError {
     fn from(description: &str) -> Self {
         Self {
@@ -26,6 +49,14 @@ pub struct SearchError {
     pub description: String,
 }
 
+impl Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl std::error::Error for SearchError {}
+
 impl From<&str> for SearchError {
     fn from(description: &str) -> Self {
         Self {
@@ -34,6 +65,14 @@ impl From<&str> for SearchError {
     }
 }
 
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
 impl From<&str> for PuzzleError {
     fn from(description: &str) -> Self {
         Self {
@@ -42,6 +81,14 @@ impl From<&str> for PuzzleError {
     }
 }
 
+impl Display for EventError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl std::error::Error for EventError {}
+
 impl From<&str> for EventError {
     fn from(description: &str) -> Self {
         Self {
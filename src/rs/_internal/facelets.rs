@@ -0,0 +1,466 @@
+use cubing::kpuzzle::{KPattern, KPuzzle, KPuzzleOrbitInfo, OrientationWithMod};
+use serde::{Deserialize, Serialize};
+
+use super::errors::ArgumentError;
+
+/// The location of a facelet (sticker): the orbit name, the position of the
+/// piece in the orbit, and the index of the sticker on that piece.
+///
+/// For pieces with as many stickers as orientations, a piece with orientation
+/// `o` shows its sticker `(i - o) % num_stickers` at sticker index `i`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FaceletLocation(pub String, pub u8, pub u8);
+
+/// Maps each facelet of a facelet string (e.g. Kociemba's `UUUUUUUUURRR…`
+/// for 3x3x3) to a location on the puzzle.
+///
+/// The facelets are listed face by face (in the order of `faces`), with the
+/// same number of facelets for every face. In the default pattern, each
+/// facelet shows the character for its face.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FaceletMapping {
+    pub faces: String,
+    pub facelets: Vec<FaceletLocation>,
+}
+
+struct FaceletOrbit<'a> {
+    orbit_info: &'a KPuzzleOrbitInfo,
+    // Indexed by position, then by sticker.
+    facelet_indices: Vec<Vec<usize>>,
+}
+
+impl FaceletOrbit<'_> {
+    fn num_stickers(&self) -> usize {
+        self.facelet_indices[0].len()
+    }
+
+    fn orientation_is_visible(&self) -> bool {
+        self.orbit_info.num_orientations > 1 && self.num_stickers() > 1
+    }
+
+    fn num_visible_orientations(&self) -> usize {
+        if self.orientation_is_visible() {
+            self.num_stickers()
+        } else {
+            1
+        }
+    }
+}
+
+struct ResolvedFaceletMapping<'a> {
+    faces: Vec<char>,
+    num_facelets_per_face: usize,
+    orbits: Vec<FaceletOrbit<'a>>,
+}
+
+impl<'a> ResolvedFaceletMapping<'a> {
+    fn try_new(kpuzzle: &'a KPuzzle, mapping: &FaceletMapping) -> Result<Self, ArgumentError> {
+        let faces: Vec<char> = mapping.faces.chars().collect();
+        if faces.is_empty() || mapping.facelets.len() % faces.len() != 0 {
+            return Err(
+                "Invalid facelet mapping: the facelets must be evenly divided between the faces."
+                    .into(),
+            );
+        }
+        let mut orbits = vec![];
+        for orbit_info in &kpuzzle.data.ordered_orbit_info {
+            let mut facelet_indices: Vec<Vec<Option<usize>>> =
+                vec![vec![]; orbit_info.num_pieces as usize];
+            for (facelet_index, FaceletLocation(orbit_name, position, sticker)) in
+                mapping.facelets.iter().enumerate()
+            {
+                if orbit_name != &orbit_info.name.0 {
+                    continue;
+                }
+                let Some(stickers) = facelet_indices.get_mut(*position as usize) else {
+                    return Err(ArgumentError {
+                        description: format!(
+                            "Invalid facelet mapping: position {} is out of range for orbit: {}",
+                            position, orbit_name
+                        ),
+                    });
+                };
+                let sticker = *sticker as usize;
+                if stickers.len() <= sticker {
+                    stickers.resize(sticker + 1, None);
+                }
+                if stickers[sticker].replace(facelet_index).is_some() {
+                    return Err(ArgumentError {
+                        description: format!(
+                            "Invalid facelet mapping: sticker {} at position {} of orbit {} is listed more than once.",
+                            sticker, position, orbit_name
+                        ),
+                    });
+                }
+            }
+            let num_stickers = facelet_indices[0].len();
+            let invalid_orbit_error = || {
+                ArgumentError {
+                description: format!(
+                    "Invalid facelet mapping: every position of orbit {} must have the same number of stickers (either 1 or its number of orientations).",
+                    orbit_info.name
+                ),
+            }
+            };
+            if num_stickers == 0 {
+                return Err(ArgumentError {
+                    description: format!(
+                        "Invalid facelet mapping: orbit {} has no facelets.",
+                        orbit_info.name
+                    ),
+                });
+            }
+            if orbit_info.num_orientations > 1
+                && num_stickers > 1
+                && num_stickers != orbit_info.num_orientations as usize
+            {
+                return Err(invalid_orbit_error());
+            }
+            let facelet_indices = facelet_indices
+                .into_iter()
+                .map(|stickers| {
+                    if stickers.len() != num_stickers {
+                        return None;
+                    }
+                    stickers.into_iter().collect::<Option<Vec<usize>>>()
+                })
+                .collect::<Option<Vec<Vec<usize>>>>()
+                .ok_or_else(invalid_orbit_error)?;
+            orbits.push(FaceletOrbit {
+                orbit_info,
+                facelet_indices,
+            });
+        }
+        let num_mapped_facelets: usize = orbits
+            .iter()
+            .map(|orbit| orbit.facelet_indices.len() * orbit.num_stickers())
+            .sum();
+        if num_mapped_facelets != mapping.facelets.len() {
+            return Err("Invalid facelet mapping: some facelets refer to orbits that are not in the puzzle definition.".into());
+        }
+        Ok(Self {
+            num_facelets_per_face: mapping.facelets.len() / faces.len(),
+            faces,
+            orbits,
+        })
+    }
+
+    fn solved_face(&self, facelet_index: usize) -> char {
+        self.faces[facelet_index / self.num_facelets_per_face]
+    }
+
+    /// A name like `U9` (the face character and the 1-indexed facelet on the face).
+    fn facelet_name(&self, facelet_index: usize) -> String {
+        format!(
+            "{}{}",
+            self.solved_face(facelet_index),
+            facelet_index % self.num_facelets_per_face + 1
+        )
+    }
+
+    // The faces shown by `piece` (at its default position) with the given orientation.
+    fn stickers_at(&self, orbit: &FaceletOrbit, piece: usize, orientation: usize) -> Vec<char> {
+        let num_stickers = orbit.num_stickers();
+        (0..num_stickers)
+            .map(|sticker| {
+                self.solved_face(
+                    orbit.facelet_indices[piece]
+                        [(sticker + num_stickers - orientation) % num_stickers],
+                )
+            })
+            .collect()
+    }
+
+    // The pieces (with the orientation they would need) that show the given faces.
+    fn pieces_showing(&self, orbit: &FaceletOrbit, stickers: &[char]) -> Vec<(usize, usize)> {
+        (0..orbit.facelet_indices.len())
+            .filter_map(|piece| {
+                (0..orbit.num_visible_orientations())
+                    .find(|&orientation| self.stickers_at(orbit, piece, orientation) == stickers)
+                    .map(|orientation| (piece, orientation))
+            })
+            .collect()
+    }
+
+    fn describe_position(&self, orbit: &FaceletOrbit, position: usize) -> String {
+        format!(
+            "position {} of orbit {} (facelets {})",
+            position,
+            orbit.orbit_info.name,
+            orbit.facelet_indices[position]
+                .iter()
+                .map(|&facelet_index| self.facelet_name(facelet_index))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Parses a facelet string into a pattern. Whitespace is ignored.
+///
+/// Pieces that look identical (e.g. the centers of a 4x4x4 with the same
+/// color) cannot be told apart, so they are all represented by the lowest
+/// piece that looks like them. Use [`merge_look_alike_pieces`] on the target
+/// pattern of a search (usually the default pattern) to match.
+pub fn pattern_from_facelets(
+    kpuzzle: &KPuzzle,
+    mapping: &FaceletMapping,
+    facelets: &str,
+) -> Result<KPattern, ArgumentError> {
+    let resolved = ResolvedFaceletMapping::try_new(kpuzzle, mapping)?;
+    let facelets: Vec<char> = facelets.chars().filter(|c| !c.is_whitespace()).collect();
+    if facelets.len() != mapping.facelets.len() {
+        return Err(ArgumentError {
+            description: format!(
+                "Expected {} facelets, but found {}.",
+                mapping.facelets.len(),
+                facelets.len()
+            ),
+        });
+    }
+    if let Some((facelet_index, facelet)) = facelets
+        .iter()
+        .enumerate()
+        .find(|(_, facelet)| !resolved.faces.contains(facelet))
+    {
+        return Err(ArgumentError {
+            description: format!(
+                "Invalid facelet `{}` at {} (expected one of: {})",
+                facelet,
+                resolved.facelet_name(facelet_index),
+                mapping.faces
+            ),
+        });
+    }
+
+    let mut pattern = kpuzzle.default_pattern();
+    for orbit in &resolved.orbits {
+        let num_pieces = orbit.facelet_indices.len();
+        let observed: Vec<Vec<char>> = orbit
+            .facelet_indices
+            .iter()
+            .map(|facelet_indices| {
+                facelet_indices
+                    .iter()
+                    .map(|&facelet_index| facelets[facelet_index])
+                    .collect()
+            })
+            .collect();
+        // For each position: the candidate pieces (with the orientation they would need).
+        let mut candidates: Vec<Vec<(usize, usize)>> = vec![];
+        for (position, observed_stickers) in observed.iter().enumerate() {
+            let position_candidates = resolved.pieces_showing(orbit, observed_stickers);
+            if position_candidates.is_empty() {
+                return Err(ArgumentError {
+                    description: format!(
+                        "Impossible sticker combination {} at {}",
+                        observed_stickers.iter().collect::<String>(),
+                        resolved.describe_position(orbit, position)
+                    ),
+                });
+            }
+            candidates.push(position_candidates);
+        }
+
+        // Each piece can only be used once, so a combination of stickers must not
+        // appear more often than the pieces that show it.
+        let mut used = vec![false; num_pieces];
+        for (position, position_candidates) in candidates.iter().enumerate() {
+            let Some((piece, _)) = position_candidates.iter().find(|(piece, _)| !used[*piece])
+            else {
+                return Err(ArgumentError {
+                    description: format!(
+                        "The piece with stickers {} at {} appears more often than it exists on the puzzle.",
+                        observed[position].iter().collect::<String>(),
+                        resolved.describe_position(orbit, position)
+                    ),
+                });
+            };
+            used[*piece] = true;
+        }
+
+        for (position, position_candidates) in candidates.into_iter().enumerate() {
+            // The candidates are exactly the pieces that look the same, so use the lowest one.
+            let (piece, orientation) = position_candidates[0];
+            pattern.set_piece(orbit.orbit_info, position as u8, piece as u8);
+            pattern.set_orientation_with_mod(
+                orbit.orbit_info,
+                position as u8,
+                &OrientationWithMod {
+                    orientation: orientation as u8,
+                    // The orientation cannot be seen if the piece has a single sticker.
+                    orientation_mod: if orbit.num_stickers() == 1
+                        && orbit.orbit_info.num_orientations > 1
+                    {
+                        1
+                    } else {
+                        0
+                    },
+                },
+            );
+        }
+    }
+    Ok(pattern)
+}
+
+/// Replaces each piece by the lowest piece that looks the same (see
+/// [`pattern_from_facelets`]), adjusting its orientation to match.
+pub fn merge_look_alike_pieces(
+    kpuzzle: &KPuzzle,
+    mapping: &FaceletMapping,
+    pattern: &KPattern,
+) -> Result<KPattern, ArgumentError> {
+    let resolved = ResolvedFaceletMapping::try_new(kpuzzle, mapping)?;
+    let mut merged = pattern.clone();
+    for orbit in &resolved.orbits {
+        let num_stickers = orbit.num_stickers();
+        for position in 0..orbit.orbit_info.num_pieces {
+            let piece = pattern.get_piece(orbit.orbit_info, position) as usize;
+            let (look_alike_piece, look_alike_orientation) =
+                resolved.pieces_showing(orbit, &resolved.stickers_at(orbit, piece, 0))[0];
+            if look_alike_piece == piece {
+                continue;
+            }
+            let orientation_with_mod = pattern.get_orientation_with_mod(orbit.orbit_info, position);
+            merged.set_piece(orbit.orbit_info, position, look_alike_piece as u8);
+            merged.set_orientation_with_mod(
+                orbit.orbit_info,
+                position,
+                &OrientationWithMod {
+                    orientation: if orbit.orientation_is_visible() {
+                        ((orientation_with_mod.orientation as usize + look_alike_orientation)
+                            % num_stickers) as u8
+                    } else {
+                        orientation_with_mod.orientation
+                    },
+                    orientation_mod: orientation_with_mod.orientation_mod,
+                },
+            );
+        }
+    }
+    Ok(merged)
+}
+
+/// Prints a pattern as a facelet string (the inverse of [`pattern_from_facelets`]).
+pub fn facelets_from_pattern(
+    kpuzzle: &KPuzzle,
+    mapping: &FaceletMapping,
+    pattern: &KPattern,
+) -> Result<String, ArgumentError> {
+    let resolved = ResolvedFaceletMapping::try_new(kpuzzle, mapping)?;
+    let mut facelets = vec![' '; mapping.facelets.len()];
+    for orbit in &resolved.orbits {
+        let num_stickers = orbit.num_stickers();
+        for (position, facelet_indices) in orbit.facelet_indices.iter().enumerate() {
+            let piece = pattern.get_piece(orbit.orbit_info, position as u8) as usize;
+            let orientation = if orbit.orientation_is_visible() {
+                pattern
+                    .get_orientation_with_mod(orbit.orbit_info, position as u8)
+                    .orientation as usize
+            } else {
+                0
+            };
+            for (sticker, &facelet_index) in facelet_indices.iter().enumerate() {
+                facelets[facelet_index] = resolved.solved_face(
+                    orbit.facelet_indices[piece]
+                        [(sticker + num_stickers - orientation) % num_stickers],
+                );
+            }
+        }
+    }
+    Ok(facelets.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::{
+        alg::parse_alg,
+        kpuzzle::{KPuzzle, KPuzzleDefinition},
+        puzzles::{cube2x2x2_kpuzzle, cube3x3x3_kpuzzle},
+    };
+
+    use crate::scramble::{embedded_facelet_mapping, embedded_kpuzzle};
+
+    use super::{facelets_from_pattern, pattern_from_facelets};
+
+    #[test]
+    fn facelets_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let mapping = embedded_facelet_mapping(kpuzzle).unwrap();
+        let r = kpuzzle
+            .default_pattern()
+            .apply_alg(parse_alg!("R"))
+            .unwrap();
+        let r_facelets = "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB";
+        assert_eq!(
+            facelets_from_pattern(kpuzzle, mapping, &r).unwrap(),
+            r_facelets
+        );
+        assert_eq!(
+            pattern_from_facelets(kpuzzle, mapping, r_facelets).unwrap(),
+            r
+        );
+
+        let kpuzzle_4x4x4 = embedded_kpuzzle("4x4x4").unwrap();
+        let rw = kpuzzle_4x4x4
+            .default_pattern()
+            .apply_alg(parse_alg!("Rw"))
+            .unwrap();
+        assert_eq!(
+            facelets_from_pattern(
+                kpuzzle_4x4x4,
+                embedded_facelet_mapping(kpuzzle_4x4x4).unwrap(),
+                &rw
+            )
+            .unwrap(),
+            [
+                "UUFF".repeat(4),
+                "R".repeat(16),
+                "FFDD".repeat(4),
+                "DDBB".repeat(4),
+                "L".repeat(16),
+                "UUBB".repeat(4),
+            ]
+            .concat()
+        );
+
+        for (kpuzzle, alg) in [
+            (cube2x2x2_kpuzzle(), parse_alg!("R U' F2 x y")),
+            (cube3x3x3_kpuzzle(), parse_alg!("R U' F2 M E' S2 x y")),
+            (
+                embedded_kpuzzle("4x4x4").unwrap(),
+                parse_alg!("R U' F2 Rw Uw' 2R x y"),
+            ),
+        ] {
+            let mapping = embedded_facelet_mapping(kpuzzle).unwrap();
+            let pattern = kpuzzle.default_pattern().apply_alg(alg).unwrap();
+            let facelets = facelets_from_pattern(kpuzzle, mapping, &pattern).unwrap();
+            let parsed = pattern_from_facelets(kpuzzle, mapping, &facelets).unwrap();
+            assert_eq!(
+                facelets_from_pattern(kpuzzle, mapping, &parsed).unwrap(),
+                facelets
+            );
+        }
+
+        // Swap the U and R stickers of the UFR corner.
+        let error = pattern_from_facelets(
+            kpuzzle,
+            mapping,
+            "UUUUUUUURURFRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB",
+        )
+        .unwrap_err();
+        assert!(error.description.contains("Impossible sticker combination"));
+    }
+
+    #[test]
+    fn facelets_other_definition_test() {
+        // This definition is also named `3x3x3`, but numbers its pieces differently.
+        let def: KPuzzleDefinition = serde_json::from_str(include_str!(
+            "../../../samples/json/benchmark/benchmark-3x3x3.def.json"
+        ))
+        .unwrap();
+        let kpuzzle = KPuzzle::try_from(def).unwrap();
+        assert_eq!(kpuzzle.definition().name, "3x3x3");
+        assert!(embedded_facelet_mapping(&kpuzzle).is_none());
+    }
+}
@@ -50,6 +50,13 @@ impl<T> BulkQueue<T> {
         self.size
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.finalized_sublists
+            .iter()
+            .flatten()
+            .chain(self.current_sublist.iter())
+    }
+
     fn finalize(&mut self) {
         self.finalized_sublists
             .push(std::mem::take(&mut self.current_sublist));
@@ -0,0 +1,152 @@
+use std::{
+    fs::{create_dir_all, read_to_string, rename, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use cubing::kpuzzle::{KPattern, KPuzzle};
+use serde::{Deserialize, Serialize};
+
+use crate::_internal::{
+    canonical_fsm::canonical_fsm::CanonicalFSMState, errors::ArgumentError,
+    gods_algorithm::packed_pattern::num_pattern_bytes,
+};
+
+use super::packed_pattern::{pattern_bytes, pattern_from_bytes, DefinitionIdentity};
+
+const METADATA_FILE_NAME: &str = "checkpoint.json";
+
+// A checkpoint directory contains one file per completed depth (holding every
+// pattern at that depth, along with its canonical FSM state), plus a metadata
+// file that records the last depth whose file was completely written.
+//
+// Since the patterns at each depth are written exactly once, a checkpoint only
+// needs to write the newest depth.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CheckpointMetadata {
+    pub definition: DefinitionIdentity,
+    pub num_bytes_per_pattern: usize,
+    // A hash of the packed start pattern bytes.
+    pub start_pattern_hash: u64,
+    // All moves (including multiples), in order. This also captures the metric.
+    pub flat_moves: Vec<String>,
+    pub last_completed_depth: usize,
+    pub num_patterns_total: usize,
+}
+
+fn checkpoint_error(description: String) -> ArgumentError {
+    ArgumentError { description }
+}
+
+fn depth_file_path(checkpoint_dir: &Path, depth: usize) -> PathBuf {
+    checkpoint_dir.join(format!("depth-{:03}.patterns", depth))
+}
+
+// Writes to a temporary file and renames it, so that an interruption never leaves a partially written file in place.
+fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+) -> Result<(), ArgumentError> {
+    let temp_path = path.with_extension("tmp");
+    let file = File::create(&temp_path).map_err(|e| {
+        checkpoint_error(format!(
+            "Could not create checkpoint file {}: {}",
+            temp_path.display(),
+            e
+        ))
+    })?;
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)
+        .and_then(|_| writer.flush())
+        .map_err(|e| checkpoint_error(format!("Could not write checkpoint file: {}", e)))?;
+    drop(writer);
+    rename(&temp_path, path)
+        .map_err(|e| checkpoint_error(format!("Could not finalize checkpoint file: {}", e)))
+}
+
+pub(crate) fn write_depth<'a>(
+    checkpoint_dir: &Path,
+    depth: usize,
+    items: impl Iterator<Item = (&'a KPattern, CanonicalFSMState)>,
+) -> Result<(), ArgumentError> {
+    create_dir_all(checkpoint_dir).map_err(|e| {
+        checkpoint_error(format!(
+            "Could not create checkpoint directory {}: {}",
+            checkpoint_dir.display(),
+            e
+        ))
+    })?;
+    write_atomically(&depth_file_path(checkpoint_dir, depth), |writer| {
+        for (pattern, canonical_fsm_state) in items {
+            writer.write_all(pattern_bytes(pattern))?;
+            writer.write_all(&(*canonical_fsm_state as u32).to_le_bytes())?;
+        }
+        Ok(())
+    })
+}
+
+pub(crate) fn write_metadata(
+    checkpoint_dir: &Path,
+    metadata: &CheckpointMetadata,
+) -> Result<(), ArgumentError> {
+    write_atomically(&checkpoint_dir.join(METADATA_FILE_NAME), |writer| {
+        serde_json::to_writer_pretty(&mut *writer, metadata).map_err(std::io::Error::other)
+    })
+}
+
+/// Returns `None` if there is no checkpoint in the directory.
+pub(crate) fn read_metadata(
+    checkpoint_dir: &Path,
+) -> Result<Option<CheckpointMetadata>, ArgumentError> {
+    let path = checkpoint_dir.join(METADATA_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = read_to_string(&path)
+        .map_err(|e| checkpoint_error(format!("Could not read checkpoint metadata: {}", e)))?;
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|e| checkpoint_error(format!("Invalid checkpoint metadata: {}", e)))
+}
+
+pub(crate) fn read_depth(
+    checkpoint_dir: &Path,
+    depth: usize,
+    kpuzzle: &KPuzzle,
+    mut callback: impl FnMut(KPattern, CanonicalFSMState),
+) -> Result<(), ArgumentError> {
+    let path = depth_file_path(checkpoint_dir, depth);
+    let file = File::open(&path).map_err(|e| {
+        checkpoint_error(format!(
+            "Could not open checkpoint file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let mut reader = BufReader::new(file);
+    let num_bytes_per_pattern = num_pattern_bytes(kpuzzle);
+    let mut entry = vec![0u8; num_bytes_per_pattern + 4];
+    loop {
+        match reader.read_exact(&mut entry) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => {
+                return Err(checkpoint_error(format!(
+                    "Could not read checkpoint file {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        }
+        let Some(pattern) = pattern_from_bytes(kpuzzle, &entry[..num_bytes_per_pattern]) else {
+            return Err(checkpoint_error("Invalid checkpoint entry.".to_owned()));
+        };
+        let canonical_fsm_state = u32::from_le_bytes(
+            entry[num_bytes_per_pattern..]
+                .try_into()
+                .expect("Internal error: invalid checkpoint entry size"),
+        );
+        callback(pattern, CanonicalFSMState(canonical_fsm_state as usize));
+    }
+}
@@ -0,0 +1,1158 @@
+use std::{collections::HashMap, time::Duration};
+
+use cubing::{
+    alg::Move,
+    kpuzzle::{KPattern, KPuzzle, KPuzzleOrbitInfo, KTransformation, OrientationWithMod},
+};
+use thousands::Separable;
+
+use crate::_internal::{
+    canonical_fsm::{
+        canonical_fsm::{CanonicalFSM, CanonicalFSMState, CANONICAL_FSM_START_STATE},
+        search_generators::SearchGenerators,
+    },
+    cli::args::{EnableAutoAlwaysNeverValueEnum, Generators, MetricEnum},
+    errors::{ArgumentError, CommandError, SearchError},
+    reachability::{is_odd_permutation, ReachabilityChecker},
+    schreier_sims::{group_order::GroupOrder, stabilizer_chain::StabilizerChain},
+    search::mask_pattern::apply_mask,
+};
+
+use super::{packed_pattern::num_pattern_bytes, progress::GodsAlgorithmProgressObserver};
+
+// Rough memory use of a `HashMap<KPattern, usize>` entry, not counting the pattern bytes.
+const PRUNE_TABLE_ENTRY_OVERHEAD_BYTES: usize = 64;
+const DEFAULT_COSET_PRUNE_TABLE_MEBIBYTES: usize = 1024;
+
+pub enum CosetSubgroup {
+    /// The subgroup generated by the given moves (and their multiples). The
+    /// default pattern must have distinguishable pieces wherever the moves permute them.
+    Generators(Generators),
+    /// All patterns that look the same as the start pattern when the mask is applied.
+    Mask(KPattern),
+}
+
+#[derive(Debug, Default)]
+pub struct CosetSearchConstructionOptions {
+    /// Defaults to the default pattern of the puzzle (i.e. the subgroup itself).
+    pub coset_pattern: Option<KPattern>,
+    /// Required for mask subgroups, since the number of reachable elements is not known in advance.
+    pub max_depth: Option<usize>,
+    /// The memory for the bitmap (one bit per possible subgroup element), with the
+    /// rest used for the prune table. Defaults to what the bitmap needs, plus 1 GiB
+    /// for the prune table.
+    pub memory_mebibytes: Option<usize>,
+}
+
+/// For a coset pattern `P` and a subgroup `H` (of patterns), this contains the
+/// number of elements `h` of `H` for which the shortest move sequence `s` with
+/// `P · s = h` has each length. The sequences themselves form a coset of the
+/// corresponding subgroup of move sequences (`P` itself for `P` in `H`).
+pub struct CosetTable {
+    completed: bool,
+    depth_counts: Vec<usize>,
+    /// `None` if the number of reachable subgroup elements is not known.
+    num_subgroup_elements: Option<usize>,
+    bitmap_size_in_bits: usize,
+    total_duration: Option<Duration>,
+}
+
+impl CosetTable {
+    /// Whether every element of the subgroup was reached (as opposed to stopping at
+    /// the max depth, or because the remaining elements cannot be reached).
+    pub fn completed(&self) -> bool {
+        self.completed
+    }
+
+    /// The number of newly reached subgroup elements at each depth.
+    pub fn depth_counts(&self) -> &[usize] {
+        &self.depth_counts
+    }
+
+    pub fn num_subgroup_elements(&self) -> Option<usize> {
+        self.num_subgroup_elements
+    }
+
+    /// The number of bits used to index subgroup elements (which is an upper bound for the number of elements).
+    pub fn bitmap_size_in_bits(&self) -> usize {
+        self.bitmap_size_in_bits
+    }
+
+    pub fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+
+    pub fn summary(&self) -> String {
+        let num_found: usize = self.depth_counts.iter().sum();
+        let num_elements = match self.num_subgroup_elements {
+            Some(num_subgroup_elements) => num_subgroup_elements.to_string(),
+            None => format!("unknown, at most {}", self.bitmap_size_in_bits),
+        };
+        format!(
+            "Reached {} of {} subgroup elements{}.\nMaximum depth: {} moves\nTotal time elapsed: {:?}",
+            num_found,
+            num_elements,
+            if self.completed { "" } else { " (incomplete)" },
+            self.depth_counts.len().saturating_sub(1),
+            self.total_duration.unwrap_or_default()
+        )
+    }
+}
+
+struct Bitmap {
+    words: Vec<u64>,
+}
+
+impl Bitmap {
+    fn new(size_in_bits: usize) -> Self {
+        Self {
+            words: vec![0; size_in_bits.div_ceil(64)],
+        }
+    }
+
+    /// Returns whether the bit was newly set.
+    fn set(&mut self, index: usize) -> bool {
+        let word = &mut self.words[index / 64];
+        let bit = 1 << (index % 64);
+        let newly_set = *word & bit == 0;
+        *word |= bit;
+        newly_set
+    }
+}
+
+fn lehmer_rank(permutation: &[usize]) -> usize {
+    let mut rank = 0;
+    for (i, value) in permutation.iter().enumerate() {
+        let num_smaller_after = permutation[i + 1..]
+            .iter()
+            .filter(|other| *other < value)
+            .count();
+        rank = rank * (permutation.len() - i) + num_smaller_after;
+    }
+    rank
+}
+
+fn factorial(n: usize) -> Option<u64> {
+    (1..=n as u64).try_fold(1u64, |acc, i| acc.checked_mul(i))
+}
+
+fn effective_orientation_mod(orientation_mod: u8, num_orientations: u8) -> u8 {
+    match orientation_mod {
+        0 => num_orientations,
+        orientation_mod => orientation_mod,
+    }
+}
+
+fn indistinguishable_error(description: &str, orbit_info: &KPuzzleOrbitInfo) -> ArgumentError {
+    ArgumentError {
+        description: format!("{} (orbit: {}).", description, orbit_info.name),
+    }
+}
+
+// Whether each column has a pivot when the rows (over GF(2)) are reduced to row
+// echelon form. The values of a vector in the row space at the other columns are
+// determined by its values at the pivot columns.
+fn pivot_columns(mut rows: Vec<Vec<bool>>, num_columns: usize) -> Vec<bool> {
+    let mut pivots = vec![false; num_columns];
+    let mut rank = 0;
+    for (column, pivot) in pivots.iter_mut().enumerate() {
+        let Some(pivot_row) = (rank..rows.len()).find(|&row| rows[row][column]) else {
+            continue;
+        };
+        rows.swap(rank, pivot_row);
+        let pivot_values = rows[rank].clone();
+        for (row, values) in rows.iter_mut().enumerate() {
+            if row != rank && values[column] {
+                for (value, pivot_value) in values.iter_mut().zip(&pivot_values) {
+                    *value ^= pivot_value;
+                }
+            }
+        }
+        *pivot = true;
+        rank += 1;
+    }
+    pivots
+}
+
+// The positions of an orbit that the subgroup moves pieces between.
+struct GeneratedBlock {
+    positions: Vec<u8>,
+    // If the parity of the arrangement is determined by the other blocks, only half of the arrangements can occur.
+    parity_determined: bool,
+    // Whether the start pattern has no orientation wildcards in the block.
+    orientation_tracked: bool,
+    orientation_varies: bool,
+    // If the orientation sum is fixed, the orientation at the last position is determined by the others.
+    orientation_sum_fixed: bool,
+}
+
+struct GeneratedOrbitRanker {
+    blocks: Vec<GeneratedBlock>,
+    // For each piece in a block with more than one position, its position in the start pattern.
+    start_positions: Vec<u8>,
+}
+
+// Indexes the elements of a subgroup given by generators, using the arrangement
+// of the pieces within each block and their orientations. Since the blocks can
+// have arrangements that are not in the subgroup, a stabilizer chain is used to
+// check membership before indexing.
+struct GeneratedSubgroupRanker {
+    kpuzzle: KPuzzle,
+    start_pattern: KPattern,
+    orbits: Vec<GeneratedOrbitRanker>,
+    // Leaves out the orientations that the start pattern has wildcards for.
+    stabilizer_chain: StabilizerChain,
+    // Every subgroup element looks like the start pattern with this mask applied.
+    mask: KPattern,
+    size_in_bits: u64,
+}
+
+impl GeneratedSubgroupRanker {
+    fn try_new(
+        kpuzzle: &KPuzzle,
+        start_pattern: &KPattern,
+        subgroup_generators: &[KTransformation],
+    ) -> Result<Self, ArgumentError> {
+        let mut orbits = vec![];
+        let mut reduced_generators = subgroup_generators.to_vec();
+        let mut mask = start_pattern.clone();
+        for orbit_info in kpuzzle.orbit_info_iter() {
+            let num_pieces = orbit_info.num_pieces as usize;
+            let num_orientations = orbit_info.num_orientations;
+            let indistinguishable = || {
+                indistinguishable_error(
+                    "Subgroups given by moves require a default pattern with distinguishable pieces and orientations wherever the moves change them",
+                    orbit_info,
+                )
+            };
+
+            // Each block is labeled by its smallest position.
+            let mut block_labels: Vec<u8> = (0..orbit_info.num_pieces).collect();
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for generator in subgroup_generators {
+                    for i in 0..orbit_info.num_pieces {
+                        let from = generator.get_permutation_idx(orbit_info, i);
+                        let label = block_labels[i as usize].min(block_labels[from as usize]);
+                        for position in [i, from] {
+                            if block_labels[position as usize] != label {
+                                block_labels[position as usize] = label;
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let mut piece_counts = vec![0; num_pieces];
+            for i in 0..orbit_info.num_pieces {
+                piece_counts[start_pattern.get_piece(orbit_info, i) as usize] += 1;
+            }
+            let mut blocks = vec![];
+            let mut start_positions = vec![0; num_pieces];
+            for label in 0..orbit_info.num_pieces {
+                let positions: Vec<u8> = (0..orbit_info.num_pieces)
+                    .filter(|i| block_labels[*i as usize] == label)
+                    .collect();
+                if positions.is_empty() {
+                    continue;
+                }
+                if positions.len() > 1 {
+                    for position in &positions {
+                        let piece = start_pattern.get_piece(orbit_info, *position);
+                        if piece_counts[piece as usize] > 1 {
+                            return Err(indistinguishable());
+                        }
+                        start_positions[piece as usize] = *position;
+                    }
+                }
+
+                let orientation_mods: Vec<u8> = positions
+                    .iter()
+                    .map(|position| {
+                        effective_orientation_mod(
+                            start_pattern
+                                .get_orientation_with_mod(orbit_info, *position)
+                                .orientation_mod,
+                            num_orientations,
+                        )
+                    })
+                    .collect();
+                let orientation_mod = orientation_mods[0];
+                if orientation_mods.iter().any(|m| *m != orientation_mod) {
+                    return Err(indistinguishable());
+                }
+                let orientation_varies = subgroup_generators.iter().any(|generator| {
+                    positions.iter().any(|position| {
+                        generator.get_orientation_delta(orbit_info, *position) % orientation_mod
+                            != 0
+                    })
+                });
+                let orientation_tracked = orientation_mod == num_orientations;
+                if orientation_varies && !orientation_tracked {
+                    return Err(indistinguishable());
+                }
+                if !orientation_tracked {
+                    for generator in &mut reduced_generators {
+                        for position in &positions {
+                            generator.set_orientation_delta(orbit_info, *position, 0);
+                        }
+                    }
+                }
+                let orientation_sum_fixed = orientation_varies
+                    && subgroup_generators.iter().all(|generator| {
+                        positions
+                            .iter()
+                            .map(|position| {
+                                generator.get_orientation_delta(orbit_info, *position) as u32
+                            })
+                            .sum::<u32>()
+                            % num_orientations as u32
+                            == 0
+                    });
+
+                mask.set_orientation_with_mod(
+                    orbit_info,
+                    label,
+                    &OrientationWithMod {
+                        orientation: 0,
+                        orientation_mod: if orientation_varies { 1 } else { 0 },
+                    },
+                );
+                blocks.push(GeneratedBlock {
+                    positions,
+                    parity_determined: false,
+                    orientation_tracked,
+                    orientation_varies,
+                    orientation_sum_fixed,
+                });
+            }
+            // Identical pieces only occur in blocks with a single position, so they can be mapped to any of their positions.
+            for i in (0..orbit_info.num_pieces).rev() {
+                mask.set_piece(
+                    orbit_info,
+                    start_pattern.get_piece(orbit_info, i),
+                    block_labels[i as usize],
+                );
+            }
+            orbits.push(GeneratedOrbitRanker {
+                blocks,
+                start_positions,
+            });
+        }
+
+        // Every subgroup element has a combination of block parities that the generators can produce.
+        let permuted_blocks: Vec<(&KPuzzleOrbitInfo, &GeneratedBlock)> = kpuzzle
+            .orbit_info_iter()
+            .zip(&orbits)
+            .flat_map(|(orbit_info, orbit_ranker)| {
+                orbit_ranker
+                    .blocks
+                    .iter()
+                    .filter(|block| block.positions.len() > 1)
+                    .map(move |block| (orbit_info, block))
+            })
+            .collect();
+        let parity_rows: Vec<Vec<bool>> = subgroup_generators
+            .iter()
+            .map(|generator| {
+                permuted_blocks
+                    .iter()
+                    .map(|(orbit_info, block)| {
+                        let perm: Vec<usize> = block
+                            .positions
+                            .iter()
+                            .map(|position| {
+                                let from = generator.get_permutation_idx(orbit_info, *position);
+                                block
+                                    .positions
+                                    .iter()
+                                    .position(|other| *other == from)
+                                    .expect(
+                                        "Internal error: block is not closed under the generators.",
+                                    )
+                            })
+                            .collect();
+                        is_odd_permutation(&perm)
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut pivots = pivot_columns(parity_rows, permuted_blocks.len()).into_iter();
+        for orbit_ranker in &mut orbits {
+            for block in &mut orbit_ranker.blocks {
+                if block.positions.len() > 1 {
+                    block.parity_determined = !pivots
+                        .next()
+                        .expect("Internal error: missing parity pivot.");
+                }
+            }
+        }
+
+        let too_large = || ArgumentError {
+            description: "The subgroup is too large to index with a bitmap.".to_owned(),
+        };
+        let mut size_in_bits: u64 = 1;
+        for (orbit_info, orbit_ranker) in kpuzzle.orbit_info_iter().zip(&orbits) {
+            for block in &orbit_ranker.blocks {
+                size_in_bits = size_in_bits
+                    .checked_mul(block.num_arrangements().ok_or_else(too_large)?)
+                    .ok_or_else(too_large)?;
+                for _ in 0..block.num_orientation_digits() {
+                    size_in_bits = size_in_bits
+                        .checked_mul(orbit_info.num_orientations as u64)
+                        .ok_or_else(too_large)?;
+                }
+            }
+        }
+
+        Ok(Self {
+            kpuzzle: kpuzzle.clone(),
+            start_pattern: start_pattern.clone(),
+            orbits,
+            stabilizer_chain: StabilizerChain::new(kpuzzle, &reduced_generators),
+            mask,
+            size_in_bits,
+        })
+    }
+
+    // Only valid for patterns that look like the start pattern with the mask applied.
+    // Returns `None` if the pattern is not in the subgroup.
+    fn index(&self, pattern: &KPattern) -> Option<usize> {
+        let mut transformation = self.kpuzzle.identity_transformation();
+        let mut index = 0;
+        for (orbit_info, orbit_ranker) in self.kpuzzle.orbit_info_iter().zip(&self.orbits) {
+            let num_orientations = orbit_info.num_orientations;
+            for block in &orbit_ranker.blocks {
+                let start_positions: Vec<usize> = block
+                    .positions
+                    .iter()
+                    .map(|position| match block.positions.len() {
+                        1 => *position,
+                        _ => {
+                            orbit_ranker.start_positions
+                                [pattern.get_piece(orbit_info, *position) as usize]
+                        }
+                    } as usize)
+                    .collect();
+                for (position, from) in block.positions.iter().zip(&start_positions) {
+                    transformation.set_permutation_idx(orbit_info, *position, *from as u8);
+                    if block.orientation_tracked {
+                        let orientation = |pattern: &KPattern, i| {
+                            pattern.get_orientation_with_mod(orbit_info, i).orientation
+                        };
+                        transformation.set_orientation_delta(
+                            orbit_info,
+                            *position,
+                            (orientation(pattern, *position) + num_orientations
+                                - orientation(&self.start_pattern, *from as u8))
+                                % num_orientations,
+                        );
+                    }
+                }
+                if block.positions.len() > 1 {
+                    let rank = lehmer_rank(&start_positions);
+                    index = index
+                        * block
+                            .num_arrangements()
+                            .expect("Internal error: factorial overflow")
+                            as usize
+                        + if block.parity_determined {
+                            rank / 2
+                        } else {
+                            rank
+                        };
+                }
+                for position in &block.positions[..block.num_orientation_digits()] {
+                    index = index * num_orientations as usize
+                        + pattern
+                            .get_orientation_with_mod(orbit_info, *position)
+                            .orientation as usize;
+                }
+            }
+        }
+        self.stabilizer_chain
+            .contains(&transformation)
+            .then_some(index)
+    }
+}
+
+impl GeneratedBlock {
+    fn num_arrangements(&self) -> Option<u64> {
+        let num_arrangements = factorial(self.positions.len())?;
+        Some(if self.parity_determined {
+            num_arrangements / 2
+        } else {
+            num_arrangements
+        })
+    }
+
+    fn num_orientation_digits(&self) -> usize {
+        match (self.orientation_varies, self.orientation_sum_fixed) {
+            (false, _) => 0,
+            (true, false) => self.positions.len(),
+            (true, true) => self.positions.len() - 1,
+        }
+    }
+}
+
+struct OrientationDigit {
+    position: u8,
+    // The orientation mod that the mask keeps.
+    divisor: u8,
+    // The number of orientations (for the start pattern) with the same orientation mod `divisor`.
+    radix: u8,
+}
+
+struct MaskedOrbitRanker {
+    // Positions of each class of pieces that the mask makes identical (with more than one position).
+    class_positions: Vec<Vec<u8>>,
+    // For each piece, its rank among the pieces in its class.
+    piece_rank_in_class: Vec<usize>,
+    // The positions for which the mask hides part of the orientation.
+    orientation_digits: Vec<OrientationDigit>,
+}
+
+// Indexes the elements of a mask subgroup using the information that the mask
+// hides: the arrangement of pieces within each class of identical-looking
+// pieces, and the part of each orientation that the mask ignores. Orbits that
+// the mask fully wildcards are ignored, so patterns that only differ in them
+// count as the same element.
+struct MaskedSubgroupRanker {
+    kpuzzle: KPuzzle,
+    orbits: Vec<MaskedOrbitRanker>,
+    size_in_bits: u64,
+}
+
+impl MaskedSubgroupRanker {
+    fn try_new(
+        kpuzzle: &KPuzzle,
+        start_pattern: &KPattern,
+        mask: &KPattern,
+    ) -> Result<Self, ArgumentError> {
+        let masked_start_pattern = apply_mask(start_pattern, mask).map_err(|e| ArgumentError {
+            description: format!("Invalid subgroup mask: {}", e.description),
+        })?;
+        let mut orbits = vec![];
+        let mut size_in_bits: u64 = 1;
+        let too_large = || ArgumentError {
+            description: "The subgroup is too large to index with a bitmap.".to_owned(),
+        };
+        for orbit_info in kpuzzle.orbit_info_iter() {
+            let num_orientations = orbit_info.num_orientations;
+            let masked_orientation_mod = |i| {
+                effective_orientation_mod(
+                    masked_start_pattern
+                        .get_orientation_with_mod(orbit_info, i)
+                        .orientation_mod,
+                    num_orientations,
+                )
+            };
+            let fully_wildcarded = (0..orbit_info.num_pieces).all(|i| {
+                masked_start_pattern.get_piece(orbit_info, i)
+                    == masked_start_pattern.get_piece(orbit_info, 0)
+                    && masked_orientation_mod(i) == 1
+            });
+            if fully_wildcarded {
+                orbits.push(MaskedOrbitRanker {
+                    class_positions: vec![],
+                    piece_rank_in_class: vec![],
+                    orientation_digits: vec![],
+                });
+                continue;
+            }
+            let indistinguishable = || {
+                indistinguishable_error(
+                    "Mask subgroups require a start pattern with distinguishable pieces and orientations wherever the mask hides them",
+                    orbit_info,
+                )
+            };
+
+            let mut classes = HashMap::<u8, Vec<u8>>::new();
+            for i in 0..orbit_info.num_pieces {
+                classes
+                    .entry(masked_start_pattern.get_piece(orbit_info, i))
+                    .or_default()
+                    .push(i);
+            }
+            let mut class_positions: Vec<Vec<u8>> = classes
+                .into_values()
+                .filter(|positions| positions.len() > 1)
+                .collect();
+            class_positions.sort();
+
+            let start_orientation_mod = |i| {
+                effective_orientation_mod(
+                    start_pattern
+                        .get_orientation_with_mod(orbit_info, i)
+                        .orientation_mod,
+                    num_orientations,
+                )
+            };
+            let mut piece_rank_in_class = vec![0; orbit_info.num_pieces as usize];
+            for positions in &class_positions {
+                let mut pieces: Vec<u8> = positions
+                    .iter()
+                    .map(|position| start_pattern.get_piece(orbit_info, *position))
+                    .collect();
+                pieces.sort();
+                if pieces.windows(2).any(|pair| pair[0] == pair[1])
+                    || positions.iter().any(|position| {
+                        start_orientation_mod(*position) != start_orientation_mod(positions[0])
+                    })
+                {
+                    return Err(indistinguishable());
+                }
+                for (rank, piece) in pieces.into_iter().enumerate() {
+                    piece_rank_in_class[piece as usize] = rank;
+                }
+                size_in_bits = size_in_bits
+                    .checked_mul(factorial(positions.len()).ok_or_else(too_large)?)
+                    .ok_or_else(too_large)?;
+            }
+
+            let mut orientation_digits = vec![];
+            for i in 0..orbit_info.num_pieces {
+                let divisor = masked_orientation_mod(i);
+                let radix = start_orientation_mod(i) / divisor;
+                if radix > 1 {
+                    orientation_digits.push(OrientationDigit {
+                        position: i,
+                        divisor,
+                        radix,
+                    });
+                    size_in_bits = size_in_bits
+                        .checked_mul(radix as u64)
+                        .ok_or_else(too_large)?;
+                }
+            }
+
+            orbits.push(MaskedOrbitRanker {
+                class_positions,
+                piece_rank_in_class,
+                orientation_digits,
+            });
+        }
+        Ok(Self {
+            kpuzzle: kpuzzle.clone(),
+            orbits,
+            size_in_bits,
+        })
+    }
+
+    // Only valid for patterns in the subgroup.
+    fn index(&self, pattern: &KPattern) -> usize {
+        let mut index = 0;
+        for (orbit_info, orbit_ranker) in self.kpuzzle.orbit_info_iter().zip(&self.orbits) {
+            for positions in &orbit_ranker.class_positions {
+                let permutation: Vec<usize> = positions
+                    .iter()
+                    .map(|position| {
+                        orbit_ranker.piece_rank_in_class
+                            [pattern.get_piece(orbit_info, *position) as usize]
+                    })
+                    .collect();
+                index = index
+                    * factorial(positions.len()).expect("Internal error: factorial overflow")
+                        as usize
+                    + lehmer_rank(&permutation);
+            }
+            for orientation_digit in &orbit_ranker.orientation_digits {
+                let orientation = pattern
+                    .get_orientation_with_mod(orbit_info, orientation_digit.position)
+                    .orientation;
+                index = index * orientation_digit.radix as usize
+                    + (orientation / orientation_digit.divisor) as usize;
+            }
+        }
+        index
+    }
+}
+
+enum SubgroupRanker {
+    Generated(GeneratedSubgroupRanker),
+    Masked(MaskedSubgroupRanker),
+}
+
+struct SubgroupIndex {
+    // If the subgroup elements do not all fit in the prune table, it contains masked patterns
+    // instead (every subgroup element looks like the start pattern with this mask applied).
+    prune_table_mask: Option<KPattern>,
+    // Distances to the nearest subgroup element (or from masked patterns to the masked
+    // start pattern), up to `prune_table_radius`.
+    prune_table: HashMap<KPattern, usize>,
+    prune_table_radius: usize,
+    ranker: SubgroupRanker,
+}
+
+impl SubgroupIndex {
+    fn size_in_bits(&self) -> usize {
+        match &self.ranker {
+            SubgroupRanker::Generated(ranker) => ranker.size_in_bits as usize,
+            SubgroupRanker::Masked(ranker) => ranker.size_in_bits as usize,
+        }
+    }
+
+    fn distance_lower_bound(&self, pattern: &KPattern) -> usize {
+        let distance = match &self.prune_table_mask {
+            Some(mask) => self.prune_table.get(
+                &apply_mask(pattern, mask)
+                    .expect("Internal error: mask was validated during construction."),
+            ),
+            None => self.prune_table.get(pattern),
+        };
+        match distance {
+            Some(distance) => *distance,
+            None => self.prune_table_radius + 1,
+        }
+    }
+
+    // Only valid if `distance_lower_bound(pattern)` is 0.
+    fn index(&self, pattern: &KPattern) -> Option<usize> {
+        match &self.ranker {
+            SubgroupRanker::Generated(ranker) => ranker.index(pattern),
+            SubgroupRanker::Masked(ranker) => Some(ranker.index(pattern)),
+        }
+    }
+}
+
+// The bitmap gets the memory it needs (up to the limit, if any), and the rest
+// of the memory is used for the prune table.
+fn max_prune_table_entries(
+    kpuzzle: &KPuzzle,
+    bitmap_size_in_bits: u64,
+    memory_mebibytes: Option<usize>,
+) -> Result<usize, ArgumentError> {
+    let bitmap_mebibytes = bitmap_size_in_bits.div_ceil(1 << 23) as usize;
+    let prune_table_mebibytes = match memory_mebibytes {
+        Some(memory_mebibytes) => {
+            if bitmap_mebibytes > memory_mebibytes {
+                return Err(ArgumentError {
+                    description: format!(
+                        "The subgroup needs a bitmap of {} MiB, which does not fit in {} MiB. Use a smaller subgroup or more memory.",
+                        bitmap_mebibytes.separate_with_underscores(),
+                        memory_mebibytes.separate_with_underscores()
+                    ),
+                });
+            }
+            memory_mebibytes - bitmap_mebibytes
+        }
+        None => DEFAULT_COSET_PRUNE_TABLE_MEBIBYTES,
+    };
+    Ok(prune_table_mebibytes * (1 << 20)
+        / (num_pattern_bytes(kpuzzle) + PRUNE_TABLE_ENTRY_OVERHEAD_BYTES))
+}
+
+// The number of subgroup elements that `coset_pattern` can reach using the
+// generators, if the reachability checker is exact.
+fn num_reachable_subgroup_elements(
+    kpuzzle: &KPuzzle,
+    generator_moves: &[Move],
+    coset_pattern: &KPattern,
+    start_pattern: &KPattern,
+    subgroup_generators: &[KTransformation],
+    subgroup_stabilizer_chain: &StabilizerChain,
+    num_subgroup_elements: usize,
+) -> Result<Option<usize>, CommandError> {
+    let Some(reachability_checker) = ReachabilityChecker::try_from_check_before_solve(
+        kpuzzle,
+        generator_moves,
+        Some(&EnableAutoAlwaysNeverValueEnum::Always),
+    )?
+    .filter(|reachability_checker| reachability_checker.is_exact()) else {
+        return Ok(None);
+    };
+    let reachable = |element: &KPattern| reachability_checker.check(coset_pattern, element).is_ok();
+    // If the generators generate the whole subgroup, either all of its elements are reachable or none are.
+    if subgroup_generators
+        .iter()
+        .all(|generator| reachable_from_start(&reachability_checker, start_pattern, generator))
+    {
+        return Ok(Some(if reachable(start_pattern) {
+            num_subgroup_elements
+        } else {
+            0
+        }));
+    }
+    let mut num_reachable = 0;
+    subgroup_stabilizer_chain.for_each_transformation(|transformation| {
+        if reachable(&start_pattern.apply_transformation(transformation)) {
+            num_reachable += 1;
+        }
+    });
+    Ok(Some(num_reachable))
+}
+
+fn reachable_from_start(
+    reachability_checker: &ReachabilityChecker,
+    start_pattern: &KPattern,
+    transformation: &KTransformation,
+) -> bool {
+    reachability_checker
+        .check(
+            start_pattern,
+            &start_pattern.apply_transformation(transformation),
+        )
+        .is_ok()
+}
+
+pub struct CosetSearch {
+    search_generators: SearchGenerators<KPuzzle>,
+    canonical_fsm: CanonicalFSM<KPuzzle>,
+    coset_pattern: KPattern,
+    max_depth: Option<usize>,
+    subgroup_index: SubgroupIndex,
+    num_subgroup_elements: Option<usize>,
+    // The number of subgroup elements that the coset pattern can reach, if this is known exactly.
+    num_reachable_subgroup_elements: Option<usize>,
+}
+
+impl CosetSearch {
+    pub fn try_new(
+        kpuzzle: &KPuzzle,
+        subgroup: CosetSubgroup,
+        generators: &Generators,
+        metric: &MetricEnum,
+        options: CosetSearchConstructionOptions,
+    ) -> Result<Self, CommandError> {
+        let generator_moves = generators.enumerate_moves_for_kpuzzle(kpuzzle);
+        let search_generators =
+            SearchGenerators::try_new(kpuzzle, generator_moves.clone(), metric, false)?;
+        let canonical_fsm = CanonicalFSM::try_new(
+            kpuzzle.clone(),
+            search_generators.clone(),
+            Default::default(),
+        )?;
+        let start_pattern = kpuzzle.default_pattern();
+        let coset_pattern = options
+            .coset_pattern
+            .unwrap_or_else(|| kpuzzle.default_pattern());
+
+        let (mask, ranker, num_subgroup_elements, num_reachable_subgroup_elements) = match subgroup
+        {
+            CosetSubgroup::Generators(subgroup_generators) => {
+                let subgroup_generators = subgroup_generators
+                    .enumerate_moves_for_kpuzzle(kpuzzle)
+                    .iter()
+                    .map(|r#move| kpuzzle.transformation_from_move(r#move))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| ArgumentError {
+                        description: format!("Invalid subgroup move: {}", e),
+                    })?;
+                let ranker = GeneratedSubgroupRanker::try_new(
+                    kpuzzle,
+                    &start_pattern,
+                    &subgroup_generators,
+                )?;
+                let num_subgroup_elements =
+                    GroupOrder::new(kpuzzle, &subgroup_generators, &start_pattern)
+                        .num_patterns
+                        .to_u64()
+                        .map(|num_subgroup_elements| num_subgroup_elements as usize);
+                // Some subgroup elements may be unreachable from the coset pattern
+                // using the generator moves, so we count the reachable ones to know
+                // when to stop.
+                let num_reachable_subgroup_elements = match num_subgroup_elements {
+                    Some(num_subgroup_elements) => num_reachable_subgroup_elements(
+                        kpuzzle,
+                        &generator_moves,
+                        &coset_pattern,
+                        &start_pattern,
+                        &subgroup_generators,
+                        &ranker.stabilizer_chain,
+                        num_subgroup_elements,
+                    )?,
+                    None => None,
+                };
+                (
+                    ranker.mask.clone(),
+                    SubgroupRanker::Generated(ranker),
+                    num_subgroup_elements,
+                    num_reachable_subgroup_elements,
+                )
+            }
+            CosetSubgroup::Mask(mask) => {
+                if options.max_depth.is_none() {
+                    return Err(ArgumentError {
+                        description: "A max depth is required for mask subgroups, since the number of reachable subgroup elements is not known.".to_owned(),
+                    }
+                    .into());
+                }
+                let ranker = MaskedSubgroupRanker::try_new(kpuzzle, &start_pattern, &mask)?;
+                (mask, SubgroupRanker::Masked(ranker), None, None)
+            }
+        };
+        let size_in_bits = match &ranker {
+            SubgroupRanker::Generated(ranker) => ranker.size_in_bits,
+            SubgroupRanker::Masked(ranker) => ranker.size_in_bits,
+        };
+        let max_prune_table_entries =
+            max_prune_table_entries(kpuzzle, size_in_bits, options.memory_mebibytes)?;
+        let subgroup_elements = match &ranker {
+            SubgroupRanker::Generated(ranker)
+                if num_subgroup_elements.is_some_and(|num_subgroup_elements| {
+                    num_subgroup_elements <= max_prune_table_entries
+                }) =>
+            {
+                let mut subgroup_elements = vec![];
+                ranker
+                    .stabilizer_chain
+                    .for_each_transformation(|transformation| {
+                        subgroup_elements.push(start_pattern.apply_transformation(transformation))
+                    });
+                Some(subgroup_elements)
+            }
+            _ => None,
+        };
+        let (prune_table_mask, (prune_table, prune_table_radius)) = match subgroup_elements {
+            Some(subgroup_elements) => (
+                None,
+                build_subgroup_distance_table(
+                    &search_generators,
+                    subgroup_elements,
+                    max_prune_table_entries,
+                ),
+            ),
+            None => {
+                let masked_start_pattern = apply_mask(&start_pattern, &mask)?;
+                (
+                    Some(mask),
+                    build_subgroup_distance_table(
+                        &search_generators,
+                        vec![masked_start_pattern],
+                        max_prune_table_entries,
+                    ),
+                )
+            }
+        };
+        Ok(Self {
+            search_generators,
+            canonical_fsm,
+            coset_pattern,
+            max_depth: options.max_depth,
+            subgroup_index: SubgroupIndex {
+                prune_table_mask,
+                prune_table,
+                prune_table_radius,
+                ranker,
+            },
+            num_subgroup_elements,
+            num_reachable_subgroup_elements,
+        })
+    }
+
+    pub fn fill(
+        &mut self,
+        observer: &mut dyn GodsAlgorithmProgressObserver,
+    ) -> Result<CosetTable, CommandError> {
+        if self
+            .subgroup_index
+            .distance_lower_bound(&self.coset_pattern)
+            == usize::MAX
+            || self.num_reachable_subgroup_elements == Some(0)
+        {
+            return Err(SearchError {
+                description: "The coset pattern cannot reach the subgroup.".to_owned(),
+            }
+            .into());
+        }
+        let start_time = instant::Instant::now();
+        let bitmap_size_in_bits = self.subgroup_index.size_in_bits();
+        let mut bitmap = Bitmap::new(bitmap_size_in_bits);
+        let mut depth_counts = vec![];
+        let mut num_found_total = 0;
+        let mut completed = false;
+        for depth in 0.. {
+            if let Some(max_depth) = self.max_depth {
+                if depth > max_depth {
+                    break;
+                }
+            }
+            let mut num_found_at_depth = 0;
+            self.search(
+                &self.coset_pattern,
+                CANONICAL_FSM_START_STATE,
+                depth,
+                &mut bitmap,
+                &mut num_found_at_depth,
+            );
+            num_found_total += num_found_at_depth;
+            depth_counts.push(num_found_at_depth);
+            observer.depth_completed(depth, num_found_at_depth, num_found_total);
+            if num_found_total == self.num_subgroup_elements.unwrap_or(bitmap_size_in_bits) {
+                completed = true;
+                break;
+            }
+            match self.num_reachable_subgroup_elements {
+                // The remaining subgroup elements cannot be reached.
+                Some(num_reachable_subgroup_elements) => {
+                    if num_found_total == num_reachable_subgroup_elements {
+                        break;
+                    }
+                }
+                // Without an exact count, we stop at the first depth that finds
+                // nothing new (after finding something), since otherwise the
+                // search may never end.
+                None => {
+                    if self.max_depth.is_none() && num_found_at_depth == 0 && num_found_total > 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(CosetTable {
+            completed,
+            depth_counts,
+            num_subgroup_elements: self.num_subgroup_elements,
+            bitmap_size_in_bits,
+            total_duration: Some(instant::Instant::now() - start_time),
+        })
+    }
+
+    fn search(
+        &self,
+        pattern: &KPattern,
+        canonical_fsm_state: CanonicalFSMState,
+        remaining_depth: usize,
+        bitmap: &mut Bitmap,
+        num_found: &mut usize,
+    ) {
+        if self.subgroup_index.distance_lower_bound(pattern) > remaining_depth {
+            return;
+        }
+        if remaining_depth == 0 {
+            if let Some(index) = self.subgroup_index.index(pattern) {
+                if bitmap.set(index) {
+                    *num_found += 1;
+                }
+            }
+            return;
+        }
+        for (move_class_index, moves_in_class) in self.search_generators.by_move_class.iter() {
+            let Some(next_state) = self
+                .canonical_fsm
+                .next_state(canonical_fsm_state, move_class_index)
+            else {
+                continue;
+            };
+            for move_info in moves_in_class {
+                self.search(
+                    &pattern.apply_transformation(&move_info.transformation),
+                    next_state,
+                    remaining_depth - 1,
+                    bitmap,
+                    num_found,
+                );
+            }
+        }
+    }
+}
+
+// Multi-source BFS from the given patterns, stopping before the table exceeds `max_entries`.
+// Returns the table and the largest depth that is complete in the table.
+fn build_subgroup_distance_table(
+    search_generators: &SearchGenerators<KPuzzle>,
+    frontier: Vec<KPattern>,
+    max_entries: usize,
+) -> (HashMap<KPattern, usize>, usize) {
+    let mut table: HashMap<KPattern, usize> = frontier
+        .iter()
+        .map(|pattern| (pattern.clone(), 0))
+        .collect();
+    let mut frontier = frontier;
+    let mut radius = 0;
+    loop {
+        let mut next_frontier = vec![];
+        for pattern in &frontier {
+            for (_, move_info) in search_generators.flat.iter() {
+                let next_pattern = pattern.apply_transformation(&move_info.transformation);
+                if table.contains_key(&next_pattern) {
+                    continue;
+                }
+                if table.len() >= max_entries {
+                    return (table, radius);
+                }
+                table.insert(next_pattern.clone(), radius + 1);
+                next_frontier.push(next_pattern);
+            }
+        }
+        if next_frontier.is_empty() {
+            // Every reachable pattern is in the table, so any other pattern cannot reach the subgroup.
+            return (table, usize::MAX - 1);
+        }
+        frontier = next_frontier;
+        radius += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::{alg::parse_move, kpuzzle::OrientationWithMod, puzzles::cube3x3x3_kpuzzle};
+
+    use crate::_internal::cycle_notation::parse_pattern_cycle_notation;
+
+    use super::{GeneratedSubgroupRanker, MaskedSubgroupRanker};
+
+    #[test]
+    fn generated_subgroup_ranker_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let subgroup_generators = [
+            parse_move!("U"),
+            parse_move!("D"),
+            parse_move!("R2"),
+            parse_move!("L2"),
+            parse_move!("F2"),
+            parse_move!("B2"),
+        ]
+        .map(|r#move| kpuzzle.transformation_from_move(r#move).unwrap());
+        let ranker = GeneratedSubgroupRanker::try_new(
+            kpuzzle,
+            &kpuzzle.default_pattern(),
+            &subgroup_generators,
+        )
+        .unwrap();
+        // One bit for each of the 8! · 8! · 4! / 2 subgroup elements.
+        assert_eq!(ranker.size_in_bits, 19_508_428_800);
+
+        let index =
+            |cycles: &str| ranker.index(&parse_pattern_cycle_notation(kpuzzle, cycles).unwrap());
+        assert_eq!(index(""), Some(0));
+        // The corner and edge parities must match.
+        assert!(index("CORNERS:(0 1) EDGES:(0 1)").is_some());
+        assert_ne!(
+            index("CORNERS:(0 1) EDGES:(0 1)"),
+            index("CORNERS:(0 1) EDGES:(0 2)")
+        );
+        assert_eq!(index("CORNERS:(0 1)"), None);
+    }
+
+    #[test]
+    fn masked_subgroup_ranker_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let edges = kpuzzle.orbit_info_iter().next().unwrap();
+        assert_eq!(edges.name.0, "EDGES");
+
+        // All edges look the same (but their orientations are kept).
+        let mut mask = kpuzzle.default_pattern();
+        for i in 0..edges.num_pieces {
+            mask.set_piece(edges, i, 0);
+        }
+        let ranker =
+            MaskedSubgroupRanker::try_new(kpuzzle, &kpuzzle.default_pattern(), &mask).unwrap();
+        assert_eq!(ranker.size_in_bits, 479_001_600);
+
+        // Orbits that the mask fully wildcards are ignored.
+        mask.set_orientation_with_mod(
+            edges,
+            0,
+            &OrientationWithMod {
+                orientation: 0,
+                orientation_mod: 1,
+            },
+        );
+        let ranker =
+            MaskedSubgroupRanker::try_new(kpuzzle, &kpuzzle.default_pattern(), &mask).unwrap();
+        assert_eq!(ranker.size_in_bits, 1);
+    }
+}
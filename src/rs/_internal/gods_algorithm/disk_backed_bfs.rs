@@ -0,0 +1,394 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::{create_dir_all, remove_dir_all, remove_file, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use cubing::kpuzzle::{KPattern, KPuzzle, KTransformation};
+
+use crate::_internal::{
+    canonical_fsm::{
+        canonical_fsm::{CanonicalFSM, CanonicalFSMState, CANONICAL_FSM_START_STATE},
+        search_generators::{FlatMoveIndex, SearchGenerators},
+    },
+    errors::ArgumentError,
+    search::indexed_vec::IndexedVec,
+};
+
+use super::packed_pattern::{num_pattern_bytes, pattern_bytes, pattern_from_bytes};
+
+const MEBIBYTE: usize = 1 << 20;
+pub const DEFAULT_DISK_BACKED_MEMORY_MEBIBYTES: usize = 1024;
+// Limits the number of files that are open at the same time while merging.
+const MAX_MERGE_FAN_IN: usize = 64;
+// Approximate overhead of sorting a record (a slice reference) in addition to the record itself.
+const SORT_OVERHEAD_PER_RECORD: usize = 16;
+
+#[derive(Debug)]
+pub struct DiskBackedOptions {
+    /// A fresh subdirectory is created (and removed afterwards) inside this directory.
+    pub temp_dir: PathBuf,
+    /// Approximate maximum amount of memory to use for buffering candidate patterns.
+    pub memory_mebibytes: usize,
+}
+
+impl Default for DiskBackedOptions {
+    fn default() -> Self {
+        Self {
+            temp_dir: std::env::temp_dir(),
+            memory_mebibytes: DEFAULT_DISK_BACKED_MEMORY_MEBIBYTES,
+        }
+    }
+}
+
+fn disk_error(description: String) -> ArgumentError {
+    ArgumentError { description }
+}
+
+fn io_error(e: std::io::Error) -> ArgumentError {
+    disk_error(format!("Disk-backed enumeration I/O error: {}", e))
+}
+
+// Each record is the packed pattern bytes followed by a little-endian `u32`
+// canonical FSM state. Files are always sorted by record (and therefore by
+// pattern first), with at most one record per pattern.
+struct RecordReader {
+    reader: BufReader<File>,
+    record: Vec<u8>,
+    done: bool,
+}
+
+impl RecordReader {
+    fn open(path: &Path, record_len: usize) -> Result<Self, ArgumentError> {
+        let file = File::open(path).map_err(io_error)?;
+        let mut record_reader = Self {
+            reader: BufReader::new(file),
+            record: vec![0; record_len],
+            done: false,
+        };
+        record_reader.advance()?;
+        Ok(record_reader)
+    }
+
+    fn advance(&mut self) -> Result<(), ArgumentError> {
+        match self.reader.read_exact(&mut self.record) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                self.done = true;
+                Ok(())
+            }
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    // Advances past all records whose pattern is less than `key`, and returns whether the next record has `key` as its pattern.
+    fn skip_to_and_contains(&mut self, key: &[u8]) -> Result<bool, ArgumentError> {
+        while !self.done && &self.record[..key.len()] < key {
+            self.advance()?;
+        }
+        Ok(!self.done && &self.record[..key.len()] == key)
+    }
+}
+
+pub(crate) struct DiskBackedBFS<'a> {
+    pub kpuzzle: &'a KPuzzle,
+    pub search_generators: &'a SearchGenerators<KPuzzle>,
+    pub canonical_fsm: &'a CanonicalFSM<KPuzzle>,
+    pub cached_inverses: &'a IndexedVec<FlatMoveIndex, KTransformation>,
+    pub options: &'a DiskBackedOptions,
+}
+
+impl DiskBackedBFS<'_> {
+    fn key_len(&self) -> usize {
+        num_pattern_bytes(self.kpuzzle)
+    }
+
+    fn record_len(&self) -> usize {
+        self.key_len() + 4
+    }
+
+    /// Returns the number of patterns at each depth, calling `on_depth_completed` after each depth.
+    pub fn run(
+        &self,
+        start_pattern: &KPattern,
+        mut on_depth_completed: impl FnMut(usize, usize),
+    ) -> Result<Vec<usize>, ArgumentError> {
+        let work_dir = self.options.temp_dir.join(format!(
+            "twsearch-gods-algorithm-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default()
+        ));
+        create_dir_all(&work_dir).map_err(|e| {
+            disk_error(format!(
+                "Could not create temp directory {}: {}",
+                work_dir.display(),
+                e
+            ))
+        })?;
+        let result = self.run_in_dir(&work_dir, start_pattern, &mut on_depth_completed);
+        let _ = remove_dir_all(&work_dir);
+        result
+    }
+
+    fn run_in_dir(
+        &self,
+        work_dir: &Path,
+        start_pattern: &KPattern,
+        on_depth_completed: &mut impl FnMut(usize, usize),
+    ) -> Result<Vec<usize>, ArgumentError> {
+        let frontier_path = |depth: usize| work_dir.join(format!("frontier-{:03}", depth));
+
+        let mut writer = BufWriter::new(File::create(frontier_path(0)).map_err(io_error)?);
+        write_record(
+            &mut writer,
+            pattern_bytes(start_pattern),
+            CANONICAL_FSM_START_STATE,
+        )?;
+        writer.flush().map_err(io_error)?;
+        drop(writer);
+
+        let mut depth_counts = vec![1];
+        on_depth_completed(0, 1);
+        let mut depth = 0;
+        loop {
+            let runs = self.write_candidate_runs(work_dir, depth, &frontier_path(depth))?;
+            let mut subtract = vec![frontier_path(depth)];
+            if depth > 0 {
+                subtract.push(frontier_path(depth - 1));
+            }
+            let count =
+                self.merge_candidate_runs(work_dir, runs, &frontier_path(depth + 1), &subtract)?;
+            if depth > 0 {
+                remove_file(frontier_path(depth - 1)).map_err(io_error)?;
+            }
+            depth += 1;
+            if count == 0 {
+                break;
+            }
+            on_depth_completed(depth, count);
+            depth_counts.push(count);
+        }
+        Ok(depth_counts)
+    }
+
+    // Expands every pattern in the frontier, writing the neighbours into sorted and deduplicated run files that each fit into the memory cap.
+    fn write_candidate_runs(
+        &self,
+        work_dir: &Path,
+        depth: usize,
+        frontier_path: &Path,
+    ) -> Result<Vec<PathBuf>, ArgumentError> {
+        let key_len = self.key_len();
+        let record_len = self.record_len();
+        let max_buffer_records = usize::max(
+            1,
+            self.options.memory_mebibytes * MEBIBYTE / (record_len + SORT_OVERHEAD_PER_RECORD),
+        );
+
+        let mut runs = vec![];
+        let mut buffer =
+            Vec::<u8>::with_capacity(usize::min(max_buffer_records, 1 << 16) * record_len);
+        let mut frontier = RecordReader::open(frontier_path, record_len)?;
+        while !frontier.done {
+            let Some(pattern) = pattern_from_bytes(self.kpuzzle, &frontier.record[..key_len])
+            else {
+                return Err(disk_error("Invalid frontier record.".to_owned()));
+            };
+            let canonical_fsm_state = read_state(&frontier.record[key_len..]);
+            for (move_class_index, moves_in_class) in self.search_generators.by_move_class.iter() {
+                let Some(next_state) = self
+                    .canonical_fsm
+                    .next_state(canonical_fsm_state, move_class_index)
+                else {
+                    continue;
+                };
+                for move_info in moves_in_class {
+                    let new_pattern = pattern
+                        .apply_transformation(self.cached_inverses.at(move_info.flat_move_index));
+                    buffer.extend_from_slice(pattern_bytes(&new_pattern));
+                    buffer.extend_from_slice(&(*next_state as u32).to_le_bytes());
+                    if buffer.len() / record_len >= max_buffer_records {
+                        runs.push(self.write_sorted_run(work_dir, depth, runs.len(), &buffer)?);
+                        buffer.clear();
+                    }
+                }
+            }
+            frontier.advance()?;
+        }
+        if !buffer.is_empty() || runs.is_empty() {
+            runs.push(self.write_sorted_run(work_dir, depth, runs.len(), &buffer)?);
+        }
+        Ok(runs)
+    }
+
+    fn write_sorted_run(
+        &self,
+        work_dir: &Path,
+        depth: usize,
+        run_index: usize,
+        buffer: &[u8],
+    ) -> Result<PathBuf, ArgumentError> {
+        let key_len = self.key_len();
+        let mut records: Vec<&[u8]> = buffer.chunks_exact(self.record_len()).collect();
+        records.sort_unstable();
+        let path = work_dir.join(format!("run-{:03}-{:06}", depth, run_index));
+        let mut writer = BufWriter::new(File::create(&path).map_err(io_error)?);
+        let mut previous_key: Option<&[u8]> = None;
+        for record in records {
+            // Keeping the first record for each pattern keeps the lowest canonical FSM state.
+            if previous_key == Some(&record[..key_len]) {
+                continue;
+            }
+            writer.write_all(record).map_err(io_error)?;
+            previous_key = Some(&record[..key_len]);
+        }
+        writer.flush().map_err(io_error)?;
+        Ok(path)
+    }
+
+    // Merges the runs into the next frontier, dropping every pattern that is in one of the `subtract` files.
+    // Returns the number of patterns in the new frontier.
+    fn merge_candidate_runs(
+        &self,
+        work_dir: &Path,
+        mut runs: Vec<PathBuf>,
+        output_path: &Path,
+        subtract: &[PathBuf],
+    ) -> Result<usize, ArgumentError> {
+        let mut pass = 0;
+        while runs.len() > MAX_MERGE_FAN_IN {
+            let mut merged_runs = vec![];
+            for (group_index, group) in runs.chunks(MAX_MERGE_FAN_IN).enumerate() {
+                let merged_path = work_dir.join(format!("merge-{:03}-{:06}", pass, group_index));
+                self.merge_files(group, &merged_path, &[])?;
+                for path in group {
+                    remove_file(path).map_err(io_error)?;
+                }
+                merged_runs.push(merged_path);
+            }
+            runs = merged_runs;
+            pass += 1;
+        }
+        let count = self.merge_files(&runs, output_path, subtract)?;
+        for path in runs {
+            remove_file(path).map_err(io_error)?;
+        }
+        Ok(count)
+    }
+
+    fn merge_files(
+        &self,
+        inputs: &[PathBuf],
+        output_path: &Path,
+        subtract: &[PathBuf],
+    ) -> Result<usize, ArgumentError> {
+        let key_len = self.key_len();
+        let record_len = self.record_len();
+        let mut readers = inputs
+            .iter()
+            .map(|path| RecordReader::open(path, record_len))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut subtract_readers = subtract
+            .iter()
+            .map(|path| RecordReader::open(path, record_len))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut heap = BinaryHeap::<Reverse<(Vec<u8>, usize)>>::new();
+        for (reader_index, reader) in readers.iter().enumerate() {
+            if !reader.done {
+                heap.push(Reverse((reader.record.clone(), reader_index)));
+            }
+        }
+
+        let mut writer = BufWriter::new(File::create(output_path).map_err(io_error)?);
+        let mut count = 0;
+        let mut previous_key: Option<Vec<u8>> = None;
+        while let Some(Reverse((record, reader_index))) = heap.pop() {
+            let reader = &mut readers[reader_index];
+            reader.advance()?;
+            if !reader.done {
+                heap.push(Reverse((reader.record.clone(), reader_index)));
+            }
+
+            let key = &record[..key_len];
+            if previous_key.as_deref() == Some(key) {
+                continue;
+            }
+            previous_key = Some(key.to_vec());
+            let mut seen_before = false;
+            for subtract_reader in &mut subtract_readers {
+                seen_before |= subtract_reader.skip_to_and_contains(key)?;
+            }
+            if seen_before {
+                continue;
+            }
+            writer.write_all(&record).map_err(io_error)?;
+            count += 1;
+        }
+        writer.flush().map_err(io_error)?;
+        Ok(count)
+    }
+}
+
+fn write_record(
+    writer: &mut impl Write,
+    key: &[u8],
+    canonical_fsm_state: CanonicalFSMState,
+) -> Result<(), ArgumentError> {
+    writer.write_all(key).map_err(io_error)?;
+    writer
+        .write_all(&(*canonical_fsm_state as u32).to_le_bytes())
+        .map_err(io_error)
+}
+
+fn read_state(bytes: &[u8]) -> CanonicalFSMState {
+    CanonicalFSMState(u32::from_le_bytes(
+        bytes
+            .try_into()
+            .expect("Internal error: invalid record size"),
+    ) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::puzzles::cube2x2x2_kpuzzle;
+
+    use crate::{
+        _internal::cli::args::{
+            GeneratorArgs, GodsAlgorithmOptionalArgs, MemoryArgs, PerformanceArgs,
+        },
+        experimental_lib_api::gods_algorithm,
+    };
+
+    #[test]
+    fn disk_backed_matches_in_memory_test() {
+        let args = |disk_backed| GodsAlgorithmOptionalArgs {
+            generator_args: GeneratorArgs {
+                generator_moves_string: Some("U,R".to_owned()),
+                ..Default::default()
+            },
+            disk_backed,
+            // With no memory, every candidate pattern is written to its own run, so the runs are merged in several passes.
+            performance_args: PerformanceArgs {
+                memory_args: MemoryArgs {
+                    memory_mebibytes: Some(0),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let in_memory_table = gods_algorithm(cube2x2x2_kpuzzle(), args(false)).unwrap();
+        let disk_backed_table = gods_algorithm(cube2x2x2_kpuzzle(), args(true)).unwrap();
+        assert_eq!(in_memory_table.num_patterns_total(), 29160);
+        assert_eq!(in_memory_table.max_depth(), Some(14));
+        assert_eq!(
+            disk_backed_table.depth_counts(),
+            in_memory_table.depth_counts()
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use std::fmt::Display;
 
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct PrimePower {
     prime: u64,
     power: u64,
@@ -27,12 +30,94 @@ impl Display for PrimePower {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Factorization {
-    prime_powers: Vec<PrimePower>,
+    pub(crate) prime_powers: Vec<PrimePower>,
+}
+
+impl Factorization {
+    pub(crate) fn one() -> Self {
+        Self {
+            prime_powers: vec![],
+        }
+    }
+
+    pub(crate) fn multiply_by(&mut self, n: u64) {
+        if n <= 1 {
+            return;
+        }
+        for prime_power in factor_number(n).prime_powers {
+            match self
+                .prime_powers
+                .binary_search_by_key(&prime_power.prime, |p| p.prime)
+            {
+                Ok(i) => self.prime_powers[i].power += prime_power.power,
+                Err(i) => self.prime_powers.insert(i, prime_power),
+            }
+        }
+    }
+
+    /// Panics if `divisor` does not divide `self`.
+    pub(crate) fn divide_by(&mut self, divisor: &Factorization) {
+        for prime_power in &divisor.prime_powers {
+            let i = self
+                .prime_powers
+                .binary_search_by_key(&prime_power.prime, |p| p.prime)
+                .expect("Internal error: divisor does not divide the number.");
+            assert!(
+                self.prime_powers[i].power >= prime_power.power,
+                "Internal error: divisor does not divide the number."
+            );
+            self.prime_powers[i].power -= prime_power.power;
+            if self.prime_powers[i].power == 0 {
+                self.prime_powers.remove(i);
+            }
+        }
+    }
+
+    /// Returns `None` if the product does not fit in a `u64`.
+    pub(crate) fn to_u64(&self) -> Option<u64> {
+        let mut product: u64 = 1;
+        for prime_power in &self.prime_powers {
+            let power = u32::try_from(prime_power.power).ok()?;
+            product = product.checked_mul(prime_power.prime.checked_pow(power)?)?;
+        }
+        Some(product)
+    }
+
+    /// Returns the product in decimal, which may exceed the range of any primitive integer type.
+    pub fn to_decimal_string(&self) -> String {
+        const LIMB_BASE: u64 = 1_000_000_000;
+        // Little-endian base-10⁹ limbs.
+        let mut limbs: Vec<u64> = vec![1];
+        for prime_power in &self.prime_powers {
+            for _ in 0..prime_power.power {
+                let mut carry = 0;
+                for limb in limbs.iter_mut() {
+                    let value = *limb * prime_power.prime + carry;
+                    *limb = value % LIMB_BASE;
+                    carry = value / LIMB_BASE;
+                }
+                while carry > 0 {
+                    limbs.push(carry % LIMB_BASE);
+                    carry /= LIMB_BASE;
+                }
+            }
+        }
+        let mut limbs_iter = limbs.iter().rev();
+        let mut decimal_string = limbs_iter.next().unwrap().to_string();
+        for limb in limbs_iter {
+            decimal_string.push_str(&format!("{:09}", limb));
+        }
+        decimal_string
+    }
 }
 
 impl Display for Factorization {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.prime_powers.is_empty() {
+            return write!(f, "1");
+        }
         let primer_powers_list: Vec<String> =
             self.prime_powers.iter().map(|p| p.to_string()).collect();
         write!(f, "{}", primer_powers_list.join(" × "))
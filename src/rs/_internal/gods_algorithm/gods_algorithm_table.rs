@@ -1,6 +1,17 @@
-use std::{collections::HashMap, mem, vec};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    mem,
+    path::{Path, PathBuf},
+    time::Duration,
+    vec,
+};
 
-use cubing::kpuzzle::{KPattern, KPuzzle, KTransformation};
+use cubing::{
+    alg::{Alg, AlgNode, Move},
+    kpuzzle::{KPattern, KPuzzle, KTransformation},
+};
 use thousands::Separable;
 
 use crate::_internal::{
@@ -9,20 +20,54 @@ use crate::_internal::{
         search_generators::{FlatMoveIndex, SearchGenerators},
     },
     cli::args::{Generators, MetricEnum},
-    errors::SearchError,
-    gods_algorithm::factor_number::factor_number,
+    errors::{ArgumentError, CommandError, SearchError},
+    gods_algorithm::{
+        checkpoint::{read_depth, read_metadata, write_depth, write_metadata, CheckpointMetadata},
+        disk_backed_bfs::{DiskBackedBFS, DiskBackedOptions},
+        factor_number::factor_number,
+        packed_pattern::{
+            num_pattern_bytes, pattern_bytes, pattern_from_bytes, DefinitionIdentity,
+        },
+        progress::{GodsAlgorithmProgressObserver, SilentGodsAlgorithmProgressObserver},
+        symmetry::SymmetryGroup,
+    },
     search::indexed_vec::IndexedVec,
 };
 
 type SearchDepth = usize;
 
-use indicatif::{MultiProgress, ProgressBar, ProgressFinish, ProgressStyle};
-
 use super::bulk_queue::BulkQueue;
 
+macro_rules! format_num {
+    ($n:expr) => {
+        $n.separate_with_underscores()
+    };
+}
+
+const TABLE_FILE_MAGIC: &[u8; 8] = b"TWSGODS2";
+
 pub struct GodsAlgorithmTable {
     completed: bool, // "completed" instead of "complete" to make an unambiguous adjective
     pub pattern_to_depth: HashMap<KPattern, /* depth */ SearchDepth>,
+    // All moves (including multiples) that were used to fill the table.
+    solving_moves: Vec<(Move, KTransformation)>,
+    depth_counts: Vec<usize>,
+    // `None` for depths that were loaded (from a checkpoint or file) rather than enumerated.
+    depth_durations: Vec<Option<Duration>>,
+    total_duration: Option<Duration>,
+    symmetry_reduced_counts: Option<SymmetryReducedCounts>,
+    // Disk-backed enumeration only keeps the start pattern in memory.
+    disk_backed: bool,
+    // `None` for tables that were not filled or loaded for a specific puzzle.
+    definition_identity: Option<DefinitionIdentity>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SymmetryReducedCounts {
+    /// The number of symmetries (including reflections).
+    pub num_symmetries: usize,
+    /// The number of symmetry classes at each depth.
+    pub depth_counts: Vec<usize>,
 }
 
 impl GodsAlgorithmTable {
@@ -30,8 +75,315 @@ impl GodsAlgorithmTable {
         Self {
             completed: false,
             pattern_to_depth: HashMap::new(),
+            solving_moves: vec![],
+            depth_counts: vec![],
+            depth_durations: vec![],
+            total_duration: None,
+            symmetry_reduced_counts: None,
+            disk_backed: false,
+            definition_identity: None,
         }
     }
+
+    pub fn completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Whether the table was filled by disk-backed enumeration. In that case,
+    /// `pattern_to_depth` only contains the start pattern.
+    pub fn disk_backed(&self) -> bool {
+        self.disk_backed
+    }
+
+    /// The number of patterns at each depth, starting with the start pattern at depth 0.
+    pub fn depth_counts(&self) -> &[usize] {
+        &self.depth_counts
+    }
+
+    /// The time spent enumerating each depth, if it was enumerated in this process.
+    pub fn depth_durations(&self) -> &[Option<Duration>] {
+        &self.depth_durations
+    }
+
+    /// The time spent by `fill`, if the table was filled in this process.
+    pub fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+
+    pub fn num_patterns_total(&self) -> usize {
+        self.depth_counts.iter().sum()
+    }
+
+    pub fn max_depth(&self) -> Option<SearchDepth> {
+        self.depth_counts.len().checked_sub(1)
+    }
+
+    /// All moves (including multiples) that were used to fill the table.
+    pub fn solving_moves(&self) -> impl Iterator<Item = &Move> {
+        self.solving_moves.iter().map(|(r#move, _)| r#move)
+    }
+
+    /// Only present if the table was filled with symmetry reduction. In that
+    /// case, `pattern_to_depth` only contains one representative per symmetry
+    /// class, while `depth_counts` still counts all patterns.
+    pub fn symmetry_reduced_counts(&self) -> Option<&SymmetryReducedCounts> {
+        self.symmetry_reduced_counts.as_ref()
+    }
+
+    pub fn summary(&self) -> String {
+        let num_patterns_total = self.num_patterns_total();
+        let symmetry_summary = match &self.symmetry_reduced_counts {
+            Some(symmetry_reduced_counts) => format!(
+                "\nUp to symmetry: {} classes ({} symmetries)",
+                format_num!(symmetry_reduced_counts.depth_counts.iter().sum::<usize>()),
+                symmetry_reduced_counts.num_symmetries
+            ),
+            None => "".to_owned(),
+        };
+        format!(
+            "Found {} ({}) pattern{}.{}\nMaximum depth: {} moves\nTotal time elapsed: {:?}",
+            format_num!(num_patterns_total),
+            factor_number(num_patterns_total.try_into().unwrap()),
+            if num_patterns_total == 1 { "" } else { "s" },
+            symmetry_summary,
+            self.max_depth().unwrap_or_default(),
+            self.total_duration.unwrap_or_default()
+        )
+    }
+
+    fn push_depth(&mut self, num_patterns: usize, duration: Option<Duration>) {
+        self.depth_counts.push(num_patterns);
+        self.depth_durations.push(duration);
+    }
+
+    fn set_depth_counts_from_patterns(&mut self) {
+        let mut depth_counts = Vec::<usize>::new();
+        for depth in self.pattern_to_depth.values() {
+            if *depth >= depth_counts.len() {
+                depth_counts.resize(depth + 1, 0);
+            }
+            depth_counts[*depth] += 1;
+        }
+        self.depth_durations = vec![None; depth_counts.len()];
+        self.depth_counts = depth_counts;
+    }
+
+    /// Returns an optimal solution by repeatedly moving to a neighbour that is one move closer to the start pattern.
+    pub fn solve(&self, pattern: &KPattern) -> Result<Alg, SearchError> {
+        if self.symmetry_reduced_counts.is_some() {
+            return Err(SearchError {
+                description: "Symmetry-reduced tables only hold one pattern per symmetry class, so they cannot be used to solve patterns.".to_owned(),
+            });
+        }
+        if self.disk_backed {
+            return Err(SearchError {
+                description: "Tables from disk-backed enumeration only hold the start pattern, so they cannot be used to solve patterns.".to_owned(),
+            });
+        }
+        let Some(mut depth) = self.pattern_to_depth.get(pattern).copied() else {
+            return Err(SearchError {
+                description: "Pattern is not in the table.".to_owned(),
+            });
+        };
+        let mut current_pattern = pattern.clone();
+        let mut nodes = Vec::<AlgNode>::with_capacity(depth);
+        while depth > 0 {
+            let Some((r#move, next_pattern)) =
+                self.solving_moves
+                    .iter()
+                    .find_map(|(r#move, transformation)| {
+                        let next_pattern = current_pattern.apply_transformation(transformation);
+                        match self.pattern_to_depth.get(&next_pattern) {
+                            Some(next_depth) if *next_depth + 1 == depth => {
+                                Some((r#move, next_pattern))
+                            }
+                            _ => None,
+                        }
+                    })
+            else {
+                return Err(SearchError {
+                    description: format!(
+                        "Could not find a move that leads from depth {} to depth {}. Was the table filled using the same moves?",
+                        depth,
+                        depth - 1
+                    ),
+                });
+            };
+            nodes.push(r#move.clone().into());
+            current_pattern = next_pattern;
+            depth -= 1;
+        }
+        Ok(Alg { nodes })
+    }
+
+    /// Format (all integers are little-endian):
+    ///
+    /// - 8 bytes: `TWSGODS2`
+    /// - `u32` + UTF-8 string: puzzle definition name
+    /// - `u64`: puzzle definition hash (see `DefinitionIdentity`)
+    /// - `u32`: number of bytes per pattern
+    /// - `u8`: 1 if the table was completed, else 0
+    /// - `u32` + UTF-8 string: comma-separated list of solving moves
+    /// - `u64`: number of entries
+    /// - each entry: raw pattern bytes followed by a `u8` depth
+    pub fn save(&self, path: &Path) -> Result<(), ArgumentError> {
+        if self.symmetry_reduced_counts.is_some() {
+            return Err(ArgumentError {
+                description: "Symmetry-reduced tables cannot be saved.".to_owned(),
+            });
+        }
+        if self.disk_backed {
+            return Err(ArgumentError {
+                description: "Tables from disk-backed enumeration only hold the start pattern, so they cannot be saved.".to_owned(),
+            });
+        }
+        let Some(definition_identity) = &self.definition_identity else {
+            return Err(ArgumentError {
+                description: "Only tables filled for a specific puzzle can be saved.".to_owned(),
+            });
+        };
+        let file = File::create(path).map_err(|e| ArgumentError {
+            description: format!("Could not create table file: {}", e),
+        })?;
+        let mut writer = BufWriter::new(file);
+        self.write_to(definition_identity, &mut writer)
+            .map_err(|e| ArgumentError {
+                description: format!("Could not write table file: {}", e),
+            })
+    }
+
+    fn write_to(
+        &self,
+        definition_identity: &DefinitionIdentity,
+        writer: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let num_bytes_per_pattern = match self.pattern_to_depth.keys().next() {
+            Some(pattern) => pattern_bytes(pattern).len(),
+            None => 0,
+        };
+        let moves_string = self
+            .solving_moves
+            .iter()
+            .map(|(r#move, _)| r#move.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        writer.write_all(TABLE_FILE_MAGIC)?;
+        writer.write_all(&(definition_identity.name.len() as u32).to_le_bytes())?;
+        writer.write_all(definition_identity.name.as_bytes())?;
+        writer.write_all(&definition_identity.hash.to_le_bytes())?;
+        writer.write_all(&(num_bytes_per_pattern as u32).to_le_bytes())?;
+        writer.write_all(&[self.completed as u8])?;
+        writer.write_all(&(moves_string.len() as u32).to_le_bytes())?;
+        writer.write_all(moves_string.as_bytes())?;
+        writer.write_all(&(self.pattern_to_depth.len() as u64).to_le_bytes())?;
+        for (pattern, depth) in &self.pattern_to_depth {
+            let Ok(depth) = u8::try_from(*depth) else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Depth {} is too large to store.", depth),
+                ));
+            };
+            writer.write_all(pattern_bytes(pattern))?;
+            writer.write_all(&[depth])?;
+        }
+        writer.flush()
+    }
+
+    /// Loads a table saved using [`GodsAlgorithmTable::save`]. Returns an error if the table was saved for a different puzzle definition, or contains invalid patterns.
+    pub fn load(kpuzzle: &KPuzzle, path: &Path) -> Result<Self, ArgumentError> {
+        let file = File::open(path).map_err(|e| ArgumentError {
+            description: format!("Could not open table file: {}", e),
+        })?;
+        let mut reader = BufReader::new(file);
+        Self::read_from(kpuzzle, &mut reader).map_err(|e| ArgumentError {
+            description: format!("Could not read table file: {}", e),
+        })
+    }
+
+    fn read_from(kpuzzle: &KPuzzle, reader: &mut impl Read) -> std::io::Result<Self> {
+        fn invalid_data(description: String) -> std::io::Error {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, description)
+        }
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != TABLE_FILE_MAGIC {
+            return Err(invalid_data("Not a God's algorithm table file.".to_owned()));
+        }
+        let mut name = vec![0u8; read_u32(reader)? as usize];
+        reader.read_exact(&mut name)?;
+        let name = String::from_utf8(name)
+            .map_err(|_| invalid_data("Invalid puzzle definition name.".to_owned()))?;
+        let hash = read_u64(reader)?;
+        let definition_identity = DefinitionIdentity::new(kpuzzle);
+        if name != definition_identity.name || hash != definition_identity.hash {
+            return Err(invalid_data(format!(
+                "The table was saved for a different puzzle definition (saved: {}, current: {}).",
+                name, definition_identity.name
+            )));
+        }
+        let num_bytes_per_pattern = read_u32(reader)? as usize;
+        let completed = read_u8(reader)? != 0;
+        let mut moves_string = vec![0u8; read_u32(reader)? as usize];
+        reader.read_exact(&mut moves_string)?;
+        let moves_string = String::from_utf8(moves_string)
+            .map_err(|_| invalid_data("Invalid move list.".to_owned()))?;
+        let mut solving_moves = vec![];
+        for move_str in moves_string.split(',').filter(|s| !s.is_empty()) {
+            let r#move = move_str
+                .parse::<Move>()
+                .map_err(|e| invalid_data(format!("Invalid move ({}): {}", e, move_str)))?;
+            let transformation = kpuzzle
+                .transformation_from_move(&r#move)
+                .map_err(|e| invalid_data(format!("Invalid move for puzzle: {}", e)))?;
+            solving_moves.push((r#move, transformation));
+        }
+        let num_entries = read_u64(reader)? as usize;
+        if num_entries > 0 && num_bytes_per_pattern != num_pattern_bytes(kpuzzle) {
+            return Err(invalid_data(
+                "The table was saved for a different puzzle definition.".to_owned(),
+            ));
+        }
+        let mut pattern_to_depth = HashMap::with_capacity(num_entries);
+        let mut entry = vec![0u8; num_bytes_per_pattern + 1];
+        for _ in 0..num_entries {
+            reader.read_exact(&mut entry)?;
+            let pattern = pattern_from_bytes(kpuzzle, &entry[..num_bytes_per_pattern])
+                .ok_or_else(|| invalid_data("Invalid pattern entry.".to_owned()))?;
+            pattern_to_depth.insert(pattern, entry[num_bytes_per_pattern] as SearchDepth);
+        }
+        let mut table = Self {
+            completed,
+            pattern_to_depth,
+            solving_moves,
+            depth_counts: vec![],
+            depth_durations: vec![],
+            total_duration: None,
+            symmetry_reduced_counts: None,
+            disk_backed: false,
+            definition_identity: Some(definition_identity),
+        };
+        table.set_depth_counts_from_patterns();
+        Ok(table)
+    }
+}
+
+fn read_u8(reader: &mut impl Read) -> std::io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
 }
 
 impl Default for GodsAlgorithmTable {
@@ -45,11 +397,26 @@ struct QueueItem {
     pattern: KPattern,
 }
 
+#[derive(Debug, Default)]
+pub struct GodsAlgorithmSearchConstructionOptions {
+    /// If set, the patterns at each depth are written to this directory as soon as the depth is complete.
+    pub checkpoint_dir: Option<PathBuf>,
+    /// Continue from the last completed depth in `checkpoint_dir` (if there is a checkpoint).
+    pub resume: bool,
+    /// If set, only the most recent frontiers are kept (on disk) instead of the full table in memory.
+    /// The resulting table only contains the start pattern and cannot be used for solving.
+    pub disk_backed: Option<DiskBackedOptions>,
+    /// Store only one representative per symmetry class (see `GodsAlgorithmTable::symmetry_reduced_counts`).
+    /// This does not use the canonical FSM, since it does not respect symmetries.
+    pub symmetry_reduced: bool,
+}
+
 pub struct GodsAlgorithmSearch {
     // params
     kpuzzle: KPuzzle,
     start_pattern: Option<KPattern>,
     search_generators: SearchGenerators<KPuzzle>,
+    options: GodsAlgorithmSearchConstructionOptions,
 
     // TODO: find more elegant way to store this.
     cached_inverses: IndexedVec<FlatMoveIndex, KTransformation>,
@@ -57,14 +424,6 @@ pub struct GodsAlgorithmSearch {
     canonical_fsm: CanonicalFSM<KPuzzle>,
     pub(crate) table: GodsAlgorithmTable,
     bulk_queues: Vec<BulkQueue<QueueItem>>, // TODO: `HashMap` instead of `Vec` for the other layer for sparse rep?
-
-    multi_progress_bar: MultiProgress,
-}
-
-macro_rules! format_num {
-    ($n:expr) => {
-        $n.separate_with_underscores()
-    };
 }
 
 impl GodsAlgorithmSearch {
@@ -73,6 +432,7 @@ impl GodsAlgorithmSearch {
         start_pattern: Option<KPattern>,
         generators: &Generators,
         quantum_metric: &MetricEnum,
+        options: GodsAlgorithmSearchConstructionOptions,
     ) -> Result<Self, SearchError> {
         let depth_to_patterns = vec![];
         let search_generators = SearchGenerators::try_new(
@@ -100,38 +460,322 @@ impl GodsAlgorithmSearch {
                 .collect(),
         );
 
+        let solving_moves = search_generators
+            .flat
+            .iter()
+            .map(|(_, info)| (info.r#move.clone(), info.transformation.clone()))
+            .collect();
+
+        let definition_identity = DefinitionIdentity::new(&kpuzzle);
+
         Ok(Self {
             kpuzzle,
             start_pattern,
             search_generators,
+            options,
             canonical_fsm,
             table: GodsAlgorithmTable {
                 completed: false,
                 pattern_to_depth: HashMap::new(),
+                solving_moves,
+                depth_counts: vec![],
+                depth_durations: vec![],
+                total_duration: None,
+                symmetry_reduced_counts: None,
+                disk_backed: false,
+                definition_identity: Some(definition_identity),
             },
             bulk_queues: depth_to_patterns,
             cached_inverses,
-            multi_progress_bar: MultiProgress::new(),
         })
     }
 
-    pub fn fill(&mut self) {
+    fn checkpoint_metadata(
+        &self,
+        last_completed_depth: SearchDepth,
+        num_patterns_total: usize,
+    ) -> CheckpointMetadata {
         let start_pattern = match &self.start_pattern {
             Some(start_pattern) => start_pattern.clone(),
             None => self.kpuzzle.default_pattern(),
         };
-        self.table.pattern_to_depth.insert(start_pattern.clone(), 0);
-        let start_item = QueueItem {
-            canonical_fsm_state: CANONICAL_FSM_START_STATE,
-            pattern: start_pattern,
+        CheckpointMetadata {
+            definition: DefinitionIdentity::new(&self.kpuzzle),
+            num_bytes_per_pattern: num_pattern_bytes(&self.kpuzzle),
+            start_pattern_hash: cityhasher::hash(pattern_bytes(&start_pattern)),
+            flat_moves: self
+                .search_generators
+                .flat
+                .iter()
+                .map(|(_, info)| info.r#move.to_string())
+                .collect(),
+            last_completed_depth,
+            num_patterns_total,
+        }
+    }
+
+    fn write_checkpoint(
+        &self,
+        depth: SearchDepth,
+        num_patterns_total: usize,
+    ) -> Result<(), ArgumentError> {
+        let Some(checkpoint_dir) = &self.options.checkpoint_dir else {
+            return Ok(());
+        };
+        write_depth(
+            checkpoint_dir,
+            depth,
+            self.bulk_queues[depth]
+                .iter()
+                .map(|queue_item| (&queue_item.pattern, queue_item.canonical_fsm_state)),
+        )?;
+        write_metadata(
+            checkpoint_dir,
+            &self.checkpoint_metadata(depth, num_patterns_total),
+        )
+    }
+
+    // Returns the last completed depth and the total number of patterns so far, or `None` if there is no checkpoint to resume from.
+    fn resume_from_checkpoint(&mut self) -> Result<Option<(SearchDepth, usize)>, ArgumentError> {
+        let Some(checkpoint_dir) = &self.options.checkpoint_dir else {
+            return Ok(None);
+        };
+        if !self.options.resume {
+            return Ok(None);
+        }
+        let Some(metadata) = read_metadata(checkpoint_dir)? else {
+            return Ok(None);
+        };
+        let expected_metadata =
+            self.checkpoint_metadata(metadata.last_completed_depth, metadata.num_patterns_total);
+        if metadata != expected_metadata {
+            return Err(ArgumentError {
+                description: format!(
+                    "The checkpoint in {} was created with a different definition, start pattern, moves, or metric.",
+                    checkpoint_dir.display()
+                ),
+            });
+        }
+        for depth in 0..=metadata.last_completed_depth {
+            let mut bulk_queue = BulkQueue::new(None);
+            read_depth(
+                checkpoint_dir,
+                depth,
+                &self.kpuzzle,
+                |pattern, canonical_fsm_state| {
+                    self.table.pattern_to_depth.insert(pattern.clone(), depth);
+                    if depth == metadata.last_completed_depth {
+                        bulk_queue.push(QueueItem {
+                            canonical_fsm_state,
+                            pattern,
+                        });
+                    }
+                },
+            )?;
+            self.bulk_queues.push(bulk_queue);
+        }
+        if self.table.pattern_to_depth.len() != metadata.num_patterns_total {
+            return Err(ArgumentError {
+                description: "The checkpoint is inconsistent (unexpected number of patterns)."
+                    .to_owned(),
+            });
+        }
+        Ok(Some((
+            metadata.last_completed_depth,
+            metadata.num_patterns_total,
+        )))
+    }
+
+    fn fill_disk_backed(
+        &mut self,
+        disk_backed_options: &DiskBackedOptions,
+        observer: &mut dyn GodsAlgorithmProgressObserver,
+    ) -> Result<(), CommandError> {
+        if self.options.checkpoint_dir.is_some() {
+            return Err(ArgumentError {
+                description: "Checkpoints are not supported for disk-backed enumeration."
+                    .to_owned(),
+            }
+            .into());
+        }
+        let start_pattern = match &self.start_pattern {
+            Some(start_pattern) => start_pattern.clone(),
+            None => self.kpuzzle.default_pattern(),
         };
-        self.bulk_queues.push(BulkQueue::new(Some(start_item)));
+        let start_time = instant::Instant::now();
+        let mut depth_start_time = start_time;
+        let mut num_patterns_total = 0;
+        let mut depth_counts_and_durations = vec![];
+        DiskBackedBFS {
+            kpuzzle: &self.kpuzzle,
+            search_generators: &self.search_generators,
+            canonical_fsm: &self.canonical_fsm,
+            cached_inverses: &self.cached_inverses,
+            options: disk_backed_options,
+        }
+        .run(&start_pattern, |depth, num_patterns_at_depth| {
+            let now = instant::Instant::now();
+            depth_counts_and_durations.push((num_patterns_at_depth, now - depth_start_time));
+            depth_start_time = now;
+            num_patterns_total += num_patterns_at_depth;
+            if depth > 0 {
+                observer.depth_completed(depth, num_patterns_at_depth, num_patterns_total);
+            }
+        })?;
+        self.table.pattern_to_depth.insert(start_pattern, 0);
+        self.table.disk_backed = true;
+        for (num_patterns, duration) in depth_counts_and_durations {
+            self.table.push_depth(num_patterns, Some(duration));
+        }
+        self.table.completed = true;
+        self.table.total_duration = Some(instant::Instant::now() - start_time);
+        Ok(())
+    }
 
+    fn fill_symmetry_reduced(
+        &mut self,
+        observer: &mut dyn GodsAlgorithmProgressObserver,
+    ) -> Result<(), CommandError> {
+        if self.options.checkpoint_dir.is_some() || self.options.disk_backed.is_some() {
+            return Err(ArgumentError {
+                description:
+                    "Symmetry reduction is not supported with checkpoints or disk-backed enumeration."
+                        .to_owned(),
+            }
+            .into());
+        }
+        let start_pattern = match &self.start_pattern {
+            Some(start_pattern) => start_pattern.clone(),
+            None => self.kpuzzle.default_pattern(),
+        };
+        let search_move_transformations: Vec<KTransformation> = self
+            .search_generators
+            .flat
+            .iter()
+            .map(|(_, info)| info.transformation.clone())
+            .collect();
+        let symmetry_group =
+            SymmetryGroup::try_new(&self.kpuzzle, &start_pattern, &search_move_transformations)?;
+
+        let start_time = instant::Instant::now();
+        let (start_representative, start_class_size) = symmetry_group.canonicalize(&start_pattern);
+        self.table
+            .pattern_to_depth
+            .insert(start_representative.clone(), 0);
+        self.table
+            .push_depth(start_class_size, Some(Duration::ZERO));
+        let mut reduced_depth_counts = vec![1];
+        let mut num_patterns_total = start_class_size;
+
+        let mut last_depth_representatives = vec![start_representative];
         let mut current_depth = 0;
-        let mut num_patterns_total = 1;
+        while !last_depth_representatives.is_empty() {
+            let depth_start_time = instant::Instant::now();
+            current_depth += 1;
+            let num_to_test_at_current_depth =
+                last_depth_representatives.len() * search_move_transformations.len();
+            observer.depth_started(current_depth, num_to_test_at_current_depth);
+
+            let mut num_tested_at_current_depth = 0;
+            let mut num_patterns_at_current_depth = 0;
+            let mut representatives_at_current_depth = vec![];
+            for representative in &last_depth_representatives {
+                for transformation in &search_move_transformations {
+                    num_tested_at_current_depth += 1;
+                    let (new_representative, class_size) = symmetry_group
+                        .canonicalize(&representative.apply_transformation(transformation));
+                    if self
+                        .table
+                        .pattern_to_depth
+                        .contains_key(&new_representative)
+                    {
+                        continue;
+                    }
+                    self.table
+                        .pattern_to_depth
+                        .insert(new_representative.clone(), current_depth);
+                    representatives_at_current_depth.push(new_representative);
+                    num_patterns_at_current_depth += class_size;
+
+                    if num_tested_at_current_depth % 1000 == 0 {
+                        observer.depth_progress(
+                            current_depth,
+                            num_tested_at_current_depth,
+                            num_patterns_at_current_depth,
+                            num_patterns_total + num_patterns_at_current_depth,
+                        );
+                    }
+                }
+            }
+            num_patterns_total += num_patterns_at_current_depth;
+            observer.depth_completed(
+                current_depth,
+                num_patterns_at_current_depth,
+                num_patterns_total,
+            );
+            if !representatives_at_current_depth.is_empty() {
+                self.table.push_depth(
+                    num_patterns_at_current_depth,
+                    Some(instant::Instant::now() - depth_start_time),
+                );
+                reduced_depth_counts.push(representatives_at_current_depth.len());
+            }
+            last_depth_representatives = representatives_at_current_depth;
+        }
+        self.table.symmetry_reduced_counts = Some(SymmetryReducedCounts {
+            num_symmetries: symmetry_group.num_symmetries(),
+            depth_counts: reduced_depth_counts,
+        });
+        self.table.completed = true;
+        self.table.total_duration = Some(instant::Instant::now() - start_time);
+        Ok(())
+    }
+
+    pub fn fill(&mut self) -> Result<(), CommandError> {
+        self.fill_with_observer(&mut SilentGodsAlgorithmProgressObserver)
+    }
+
+    pub fn fill_with_observer(
+        &mut self,
+        observer: &mut dyn GodsAlgorithmProgressObserver,
+    ) -> Result<(), CommandError> {
+        if self.options.symmetry_reduced {
+            return self.fill_symmetry_reduced(observer);
+        }
+        if let Some(disk_backed_options) = self.options.disk_backed.take() {
+            let result = self.fill_disk_backed(&disk_backed_options, observer);
+            self.options.disk_backed = Some(disk_backed_options);
+            return result;
+        }
+        let (mut current_depth, mut num_patterns_total) = match self.resume_from_checkpoint()? {
+            Some((last_completed_depth, num_patterns_total)) => {
+                observer.resumed_from_checkpoint(last_completed_depth, num_patterns_total);
+                self.table.set_depth_counts_from_patterns();
+                if self.bulk_queues[last_completed_depth].size() == 0 {
+                    self.table.completed = true;
+                }
+                (last_completed_depth, num_patterns_total)
+            }
+            None => {
+                let start_pattern = match &self.start_pattern {
+                    Some(start_pattern) => start_pattern.clone(),
+                    None => self.kpuzzle.default_pattern(),
+                };
+                self.table.pattern_to_depth.insert(start_pattern.clone(), 0);
+                let start_item = QueueItem {
+                    canonical_fsm_state: CANONICAL_FSM_START_STATE,
+                    pattern: start_pattern,
+                };
+                self.bulk_queues.push(BulkQueue::new(Some(start_item)));
+                self.table.push_depth(1, Some(Duration::ZERO));
+                self.write_checkpoint(0, 1)?;
+                (0, 1)
+            }
+        };
 
         let start_time = instant::Instant::now();
         while !self.table.completed {
+            let depth_start_time = instant::Instant::now();
             let last_depth_patterns: BulkQueue<QueueItem> = mem::replace(
                 &mut self.bulk_queues[current_depth],
                 BulkQueue::bogus_new(), // TODO: change the field to avoid the need for this?
@@ -140,20 +784,9 @@ impl GodsAlgorithmSearch {
 
             current_depth += 1;
 
-            let progress_bar = ProgressBar::new(num_last_depth_patterns.try_into().unwrap());
-            let progress_bar = self.multi_progress_bar.insert_from_back(0, progress_bar);
-            let progress_bar = progress_bar.with_finish(ProgressFinish::AndLeave);
-            // TODO share the progress bar style?
-            let progress_bar_style = ProgressStyle::with_template(
-                "{prefix:3} {bar:12.cyan/blue} {elapsed:.2} {wide_msg}",
-            )
-            .expect("Could not construct progress bar.");
-            // .progress_chars("=> ");
-            progress_bar.set_style(progress_bar_style);
-            progress_bar.set_prefix(current_depth.to_string());
-
             let num_to_test_at_current_depth: usize =
                 num_last_depth_patterns * self.search_generators.flat.len();
+            observer.depth_started(current_depth, num_to_test_at_current_depth);
             let mut num_tested_at_current_depth = 0;
             let mut patterns_at_current_depth = BulkQueue::new(None);
             for queue_item in last_depth_patterns.into_iter() {
@@ -188,55 +821,36 @@ impl GodsAlgorithmSearch {
                             .insert(new_pattern, current_depth);
 
                         if num_tested_at_current_depth % 1000 == 0 {
-                            progress_bar
-                                .set_length(num_to_test_at_current_depth.try_into().unwrap());
-                            progress_bar.set_position(num_tested_at_current_depth as u64);
-                            progress_bar.set_message(format!(
-                                "{} patterns ({} cumulative) — {} remaining candidates",
-                                format_num!(patterns_at_current_depth.size()),
-                                format_num!(num_patterns_total + patterns_at_current_depth.size()), // TODO: increment before
-                                format_num!(
-                                    num_to_test_at_current_depth
-                                        - std::convert::TryInto::<usize>::try_into(
-                                            num_tested_at_current_depth
-                                        )
-                                        .unwrap()
-                                )
-                            ))
+                            observer.depth_progress(
+                                current_depth,
+                                num_tested_at_current_depth,
+                                patterns_at_current_depth.size(),
+                                num_patterns_total + patterns_at_current_depth.size(), // TODO: increment before
+                            );
                         }
                     }
                 }
             }
             let num_patterns_at_current_depth = patterns_at_current_depth.size();
             num_patterns_total += num_patterns_at_current_depth;
-            {
-                progress_bar.set_length(patterns_at_current_depth.size().try_into().unwrap());
-                progress_bar.set_position(patterns_at_current_depth.size().try_into().unwrap());
-                progress_bar.set_message(format!(
-                    "{} patterns ({} cumulative)",
-                    format_num!(num_patterns_at_current_depth),
-                    format_num!(num_patterns_total)
-                ))
-            }
+            observer.depth_completed(
+                current_depth,
+                num_patterns_at_current_depth,
+                num_patterns_total,
+            );
             self.bulk_queues.push(patterns_at_current_depth);
+            self.write_checkpoint(current_depth, num_patterns_total)?;
 
             if num_patterns_at_current_depth == 0 {
-                progress_bar.finish_and_clear();
                 self.table.completed = true;
             } else {
-                progress_bar.finish();
+                self.table.push_depth(
+                    num_patterns_at_current_depth,
+                    Some(instant::Instant::now() - depth_start_time),
+                );
             }
         }
-        let max_depth = current_depth - 1;
-        println!();
-        println!();
-        println!(
-            "Found {} ({}) pattern{}.\nMaximum depth: {} moves\nTotal time elapsed: {:?}",
-            format_num!(num_patterns_total),
-            factor_number(num_patterns_total.try_into().unwrap()),
-            if num_patterns_total == 1 { "" } else { "s" },
-            max_depth,
-            instant::Instant::now() - start_time
-        );
+        self.table.total_duration = Some(instant::Instant::now() - start_time);
+        Ok(())
     }
 }
@@ -1,3 +1,10 @@
 mod bulk_queue;
-mod factor_number;
+mod checkpoint;
+pub mod coset;
+pub mod disk_backed_bfs;
+pub mod factor_number;
 pub mod gods_algorithm_table;
+pub(crate) mod packed_pattern;
+pub mod progress;
+pub mod report;
+mod symmetry;
@@ -0,0 +1,100 @@
+use cubing::{
+    alg::Move,
+    kpuzzle::{KPattern, KPuzzle},
+};
+use serde::{Deserialize, Serialize};
+
+// Patterns are stored using the raw packed representation from `cubing`. This
+// is only meaningful when read back with the exact same definition, so callers
+// record a `DefinitionIdentity` alongside the patterns and check it on load.
+
+/// Identifies a puzzle definition across runs: its name plus a hash of its
+/// orbits, default pattern, and move transformations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DefinitionIdentity {
+    pub name: String,
+    pub hash: u64,
+}
+
+impl DefinitionIdentity {
+    pub fn new(kpuzzle: &KPuzzle) -> Self {
+        let definition = kpuzzle.definition();
+        let mut data = vec![];
+        for orbit_info in kpuzzle.orbit_info_iter() {
+            data.extend(orbit_info.name.to_string().as_bytes());
+            data.push(0);
+            data.push(orbit_info.num_pieces);
+            data.push(orbit_info.num_orientations);
+        }
+        data.extend(pattern_bytes(&kpuzzle.default_pattern()));
+        // `HashMap` iteration order is not stable, so sort the moves first.
+        let mut moves: Vec<&Move> = definition.moves.keys().collect();
+        if let Some(derived_moves) = &definition.derived_moves {
+            moves.extend(derived_moves.keys());
+        }
+        moves.sort_by_cached_key(|r#move| r#move.to_string());
+        for r#move in moves {
+            data.extend(r#move.to_string().as_bytes());
+            data.push(0);
+            if let Ok(transformation) = kpuzzle.transformation_from_move(r#move) {
+                // SAFETY: `byte_slice()` borrows the transformation's own
+                // allocation of exactly `num_bytes` for its puzzle. The slice
+                // is copied before `transformation` is dropped.
+                data.extend(unsafe { transformation.byte_slice() });
+            }
+        }
+        Self {
+            name: definition.name.clone(),
+            hash: cityhasher::hash(data),
+        }
+    }
+}
+
+pub(crate) fn num_pattern_bytes(kpuzzle: &KPuzzle) -> usize {
+    kpuzzle
+        .orbit_info_iter()
+        .map(|orbit_info| (orbit_info.num_pieces as usize) * 2)
+        .sum()
+}
+
+pub(crate) fn pattern_bytes(pattern: &KPattern) -> &[u8] {
+    // SAFETY: the pattern's bytes are a single `u8` allocation (so alignment is
+    // trivially satisfied) of exactly `num_bytes` for its puzzle, and the
+    // returned slice borrows `pattern` so it cannot outlive the allocation.
+    unsafe { pattern.byte_slice() }
+}
+
+/// Returns `None` if the number of bytes does not match the definition, or if
+/// any piece or orientation is out of range for its orbit.
+pub(crate) fn pattern_from_bytes(kpuzzle: &KPuzzle, bytes: &[u8]) -> Option<KPattern> {
+    if bytes.len() != num_pattern_bytes(kpuzzle) {
+        return None;
+    }
+    for orbit_info in kpuzzle.orbit_info_iter() {
+        for i in 0..(orbit_info.num_pieces as usize) {
+            if bytes[orbit_info.pieces_or_permutations_offset + i] >= orbit_info.num_pieces {
+                return None;
+            }
+            let orientation_with_mod = orbit_info
+                .orientation_packer
+                .unpack(&bytes[orbit_info.orientations_offset + i]);
+            // Invalid packed values unpack to an out-of-range orientation.
+            if orientation_with_mod.orientation >= orbit_info.num_orientations {
+                return None;
+            }
+        }
+    }
+    let mut pattern = kpuzzle.default_pattern();
+    // SAFETY: `pattern` belongs to `kpuzzle`, so its buffer holds exactly
+    // `num_pattern_bytes(kpuzzle)` bytes, which we checked equals
+    // `bytes.len()` above. Both sides are `u8`, so any alignment is valid, and
+    // `bytes` is borrowed from the caller so it cannot overlap the freshly
+    // allocated pattern buffer. Every byte was range-checked above, so the
+    // pattern satisfies the invariants the rest of `cubing` relies on.
+    unsafe {
+        let packed_orbit_data = pattern.packed_orbit_data_mut();
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), packed_orbit_data.bytes, bytes.len());
+    }
+    Some(pattern)
+}
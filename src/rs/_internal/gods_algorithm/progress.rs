@@ -0,0 +1,38 @@
+/// Receives progress updates while a God's algorithm table is being filled.
+/// All methods do nothing by default, so implementations only need to handle the updates they care about.
+pub trait GodsAlgorithmProgressObserver {
+    fn resumed_from_checkpoint(
+        &mut self,
+        _last_completed_depth: usize,
+        _num_patterns_total: usize,
+    ) {
+    }
+
+    /// `num_candidates` is the number of (pattern, move) pairs that will be tested for this depth.
+    fn depth_started(&mut self, _depth: usize, _num_candidates: usize) {}
+
+    /// Called periodically while a depth is being enumerated.
+    fn depth_progress(
+        &mut self,
+        _depth: usize,
+        _num_candidates_tested: usize,
+        _num_patterns_at_depth: usize,
+        _num_patterns_total: usize,
+    ) {
+    }
+
+    /// This is also called (with `num_patterns_at_depth == 0`) for the first depth that has no patterns, if that depth was enumerated.
+    fn depth_completed(
+        &mut self,
+        _depth: usize,
+        _num_patterns_at_depth: usize,
+        _num_patterns_total: usize,
+    ) {
+    }
+}
+
+/// The default for library use.
+#[derive(Debug, Default)]
+pub struct SilentGodsAlgorithmProgressObserver;
+
+impl GodsAlgorithmProgressObserver for SilentGodsAlgorithmProgressObserver {}
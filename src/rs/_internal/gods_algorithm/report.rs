@@ -0,0 +1,226 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::_internal::cli::args::MetricEnum;
+
+use super::{
+    factor_number::{factor_number, PrimePower},
+    gods_algorithm_table::GodsAlgorithmTable,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GodsAlgorithmReportDepth {
+    pub depth: usize,
+    pub num_patterns: usize,
+    pub cumulative_num_patterns: usize,
+    /// Only present for symmetry-reduced enumeration.
+    pub num_symmetry_classes: Option<usize>,
+    pub seconds: Option<f64>,
+}
+
+/// A machine-readable summary of a God's algorithm table (e.g. for plotting the distance distribution).
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GodsAlgorithmReport {
+    pub puzzle_name: String,
+    pub metric: String,
+    // All moves (including multiples) used for the enumeration.
+    pub generators: Vec<String>,
+    pub completed: bool,
+    pub num_patterns_total: usize,
+    pub factorization: String,
+    pub prime_powers: Vec<PrimePower>,
+    pub num_symmetries: Option<usize>,
+    pub num_symmetry_classes_total: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub total_seconds: Option<f64>,
+    pub depths: Vec<GodsAlgorithmReportDepth>,
+}
+
+impl GodsAlgorithmReport {
+    pub fn new(table: &GodsAlgorithmTable, puzzle_name: &str, metric: &MetricEnum) -> Self {
+        let num_patterns_total = table.num_patterns_total();
+        let factorization = factor_number(num_patterns_total as u64);
+        let symmetry_reduced_counts = table.symmetry_reduced_counts();
+        let mut cumulative_num_patterns = 0;
+        let depths = table
+            .depth_counts()
+            .iter()
+            .zip(table.depth_durations())
+            .enumerate()
+            .map(|(depth, (num_patterns, duration))| {
+                cumulative_num_patterns += num_patterns;
+                GodsAlgorithmReportDepth {
+                    depth,
+                    num_patterns: *num_patterns,
+                    cumulative_num_patterns,
+                    num_symmetry_classes: symmetry_reduced_counts
+                        .map(|symmetry_reduced_counts| symmetry_reduced_counts.depth_counts[depth]),
+                    seconds: duration.map(|duration| duration.as_secs_f64()),
+                }
+            })
+            .collect();
+        Self {
+            puzzle_name: puzzle_name.to_owned(),
+            metric: metric.to_string(),
+            generators: table
+                .solving_moves()
+                .map(|r#move| r#move.to_string())
+                .collect(),
+            completed: table.completed(),
+            num_patterns_total,
+            factorization: factorization.to_string(),
+            prime_powers: factorization.prime_powers,
+            num_symmetries: symmetry_reduced_counts
+                .map(|symmetry_reduced_counts| symmetry_reduced_counts.num_symmetries),
+            num_symmetry_classes_total: symmetry_reduced_counts
+                .map(|symmetry_reduced_counts| symmetry_reduced_counts.depth_counts.iter().sum()),
+            max_depth: table.max_depth(),
+            total_seconds: table
+                .total_duration()
+                .map(|duration| duration.as_secs_f64()),
+            depths,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Could not serialize report.")
+    }
+
+    /// Metadata is written as `#` comment lines before the table of depths.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        // Writing to a `String` cannot fail.
+        let _ = writeln!(csv, "# puzzle: {}", self.puzzle_name);
+        let _ = writeln!(csv, "# metric: {}", self.metric);
+        let _ = writeln!(csv, "# generators: {}", self.generators.join(" "));
+        let _ = writeln!(csv, "# completed: {}", self.completed);
+        let _ = writeln!(csv, "# total: {}", self.num_patterns_total);
+        let _ = writeln!(csv, "# factorization: {}", self.factorization);
+        if let (Some(num_symmetries), Some(num_symmetry_classes_total)) =
+            (self.num_symmetries, self.num_symmetry_classes_total)
+        {
+            let _ = writeln!(csv, "# symmetries: {}", num_symmetries);
+            let _ = writeln!(csv, "# symmetry_classes: {}", num_symmetry_classes_total);
+        }
+        if let Some(total_seconds) = self.total_seconds {
+            let _ = writeln!(csv, "# total_seconds: {}", total_seconds);
+        }
+        let _ = writeln!(
+            csv,
+            "depth,num_patterns,cumulative_num_patterns,num_symmetry_classes,seconds"
+        );
+        for depth in &self.depths {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{}",
+                depth.depth,
+                depth.num_patterns,
+                depth.cumulative_num_patterns,
+                depth
+                    .num_symmetry_classes
+                    .map(|num_symmetry_classes| num_symmetry_classes.to_string())
+                    .unwrap_or_default(),
+                depth
+                    .seconds
+                    .map(|seconds| seconds.to_string())
+                    .unwrap_or_default()
+            );
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::puzzles::cube3x3x3_kpuzzle;
+
+    use crate::{
+        _internal::cli::args::{GeneratorArgs, GodsAlgorithmOptionalArgs, MetricEnum},
+        experimental_lib_api::gods_algorithm,
+    };
+
+    use super::GodsAlgorithmReport;
+
+    fn report() -> GodsAlgorithmReport {
+        let table = gods_algorithm(
+            cube3x3x3_kpuzzle(),
+            GodsAlgorithmOptionalArgs {
+                generator_args: GeneratorArgs {
+                    generator_moves_string: Some("R2,U2".to_owned()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        GodsAlgorithmReport::new(&table, "3x3x3", &MetricEnum::Hand)
+    }
+
+    #[test]
+    fn gods_algorithm_report_json_test() {
+        let json: serde_json::Value = serde_json::from_str(&report().to_json()).unwrap();
+        let mut keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|key| key.as_str())
+            .collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "completed",
+                "depths",
+                "factorization",
+                "generators",
+                "maxDepth",
+                "metric",
+                "numPatternsTotal",
+                "numSymmetries",
+                "numSymmetryClassesTotal",
+                "primePowers",
+                "puzzleName",
+                "totalSeconds",
+            ]
+        );
+        assert_eq!(json["puzzleName"], "3x3x3");
+        assert_eq!(json["numPatternsTotal"], 12);
+        assert_eq!(json["factorization"], "2² × 3¹");
+        assert_eq!(
+            json["primePowers"],
+            serde_json::json!([{ "prime": 2, "power": 2 }, { "prime": 3, "power": 1 }])
+        );
+        assert_eq!(json["maxDepth"], 6);
+        assert!(json["numSymmetries"].is_null());
+        assert!(json["totalSeconds"].is_number());
+
+        let depths = json["depths"].as_array().unwrap();
+        assert_eq!(depths.len(), 7);
+        assert_eq!(depths[1]["depth"], 1);
+        assert_eq!(depths[1]["numPatterns"], 2);
+        assert_eq!(depths[1]["cumulativeNumPatterns"], 3);
+        assert!(depths[1]["numSymmetryClasses"].is_null());
+        assert!(depths[1]["seconds"].is_number());
+    }
+
+    #[test]
+    fn gods_algorithm_report_csv_test() {
+        let csv = report().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "# puzzle: 3x3x3");
+        assert_eq!(lines[4], "# total: 12");
+        let header_index = lines
+            .iter()
+            .position(|line| !line.starts_with('#'))
+            .unwrap();
+        assert_eq!(
+            lines[header_index],
+            "depth,num_patterns,cumulative_num_patterns,num_symmetry_classes,seconds"
+        );
+        assert_eq!(lines.len(), header_index + 1 + 7);
+        assert!(lines[header_index + 2].starts_with("1,2,3,,"));
+    }
+}
@@ -0,0 +1,750 @@
+use cubing::kpuzzle::{KPattern, KPuzzle, KPuzzleOrbitInfo, KTransformation, OrientationWithMod};
+
+use crate::_internal::errors::SearchError;
+
+use super::packed_pattern::pattern_bytes;
+
+// Guards against definitions with pieces that no move affects (where any relabeling of them would count).
+const MAX_SYMMETRY_GROUP_SIZE: usize = 1024;
+
+// A piece label, i.e. a piece value together with its orientation mod (`0` for no orientation wildcard).
+type Label = (u8, u8);
+
+#[derive(Clone, Copy)]
+struct Relabel {
+    label: Label,
+    orientation_offset: u8,
+}
+
+// A relabeling `s` of the positions of the puzzle: position `i` of an orbit
+// receives the piece at position `permutation[i]`, with its orientation
+// negated (if `reflection` is set) and `orientation_delta[i]` added. Without
+// `reflection`, this is the same as applying a transformation.
+#[derive(Clone)]
+struct Automorphism {
+    // Indexed by orbit, then by position.
+    permutation: Vec<Vec<u8>>,
+    orientation_delta: Vec<Vec<u8>>,
+    reflection: bool,
+}
+
+struct Symmetry {
+    automorphism: Automorphism,
+    // Indexed by orbit, then by `label_index(…)`.
+    relabeling: Vec<Vec<Option<Relabel>>>,
+}
+
+/// The symmetries of a search: relabelings `s` of the positions such that
+/// conjugation by `s` maps the set of search moves to itself and fixes the
+/// start pattern. This means that conjugation by `s` preserves the distance of
+/// every pattern.
+///
+/// Candidate symmetries are the automorphisms of the definition: relabelings
+/// that map each move of the definition to a move or its inverse. A relabeling
+/// may reverse the orientation of all pieces, which maps clockwise moves to
+/// counterclockwise moves. This means that reflections are included (so a cube
+/// has 48 symmetries), and that definitions do not need rotation moves.
+/// Pieces are only relabeled within their orbit, so symmetries that exchange
+/// orbits (e.g. of definitions with an orbit per piece) are not found.
+///
+/// Patterns are conjugated by relabeling pieces rather than by treating them
+/// as transformations, so start patterns with identical pieces or orientation
+/// wildcards are supported. An automorphism is only a symmetry if it maps each
+/// label of the start pattern to a single label.
+pub(crate) struct SymmetryGroup {
+    kpuzzle: KPuzzle,
+    symmetries: Vec<Symmetry>, // Includes the identity.
+}
+
+fn label_index(orbit_info: &KPuzzleOrbitInfo, label: Label) -> usize {
+    label.0 as usize * (orbit_info.num_orientations as usize + 1) + label.1 as usize
+}
+
+fn effective_orientation_mod(orbit_info: &KPuzzleOrbitInfo, orientation_mod: u8) -> u8 {
+    match orientation_mod {
+        0 => orbit_info.num_orientations,
+        orientation_mod => orientation_mod,
+    }
+}
+
+fn signed_orientation(orientation: u8, reflection: bool, orientation_mod: u8) -> u8 {
+    if reflection {
+        (orientation_mod - orientation % orientation_mod) % orientation_mod
+    } else {
+        orientation % orientation_mod
+    }
+}
+
+// The lengths of the cycles of each orbit, together with the total orientation
+// delta of each cycle (negated for a reflection). Conjugation preserves this.
+fn cycle_signature(
+    kpuzzle: &KPuzzle,
+    transformation: &KTransformation,
+    reflection: bool,
+) -> Vec<Vec<(u8, u8)>> {
+    let mut signature = vec![];
+    for orbit_info in kpuzzle.orbit_info_iter() {
+        let mut seen = vec![false; orbit_info.num_pieces as usize];
+        let mut cycles = vec![];
+        for start in 0..orbit_info.num_pieces {
+            let mut length = 0;
+            let mut orientation_delta = 0;
+            let mut i = start;
+            while !seen[i as usize] {
+                seen[i as usize] = true;
+                length += 1;
+                orientation_delta += transformation.get_orientation_delta(orbit_info, i);
+                orientation_delta %= orbit_info.num_orientations;
+                i = transformation.get_permutation_idx(orbit_info, i);
+            }
+            if length > 0 {
+                cycles.push((
+                    length,
+                    signed_orientation(orientation_delta, reflection, orbit_info.num_orientations),
+                ));
+            }
+        }
+        cycles.sort_unstable();
+        signature.push(cycles);
+    }
+    signature
+}
+
+impl Automorphism {
+    fn apply_to_pattern(&self, kpuzzle: &KPuzzle, pattern: &KPattern) -> KPattern {
+        let mut result = pattern.clone();
+        for (orbit_index, orbit_info) in kpuzzle.orbit_info_iter().enumerate() {
+            for i in 0..orbit_info.num_pieces {
+                let from = self.permutation[orbit_index][i as usize];
+                let orientation_with_mod = pattern.get_orientation_with_mod(orbit_info, from);
+                let orientation_mod =
+                    effective_orientation_mod(orbit_info, orientation_with_mod.orientation_mod);
+                result.set_piece(orbit_info, i, pattern.get_piece(orbit_info, from));
+                result.set_orientation_with_mod(
+                    orbit_info,
+                    i,
+                    &OrientationWithMod {
+                        orientation: (signed_orientation(
+                            orientation_with_mod.orientation,
+                            self.reflection,
+                            orientation_mod,
+                        ) + self.orientation_delta[orbit_index][i as usize])
+                            % orientation_mod,
+                        orientation_mod: orientation_with_mod.orientation_mod,
+                    },
+                );
+            }
+        }
+        result
+    }
+
+    // Calculates `s⁻¹ · t · s`, which is a transformation even if `s` is a reflection.
+    fn conjugate_transformation(
+        &self,
+        kpuzzle: &KPuzzle,
+        transformation: &KTransformation,
+    ) -> KTransformation {
+        let mut conjugated = kpuzzle.identity_transformation();
+        for (orbit_index, orbit_info) in kpuzzle.orbit_info_iter().enumerate() {
+            let permutation = &self.permutation[orbit_index];
+            let orientation_delta = &self.orientation_delta[orbit_index];
+            let mut inverse_permutation = vec![0; permutation.len()];
+            for (i, from) in permutation.iter().enumerate() {
+                inverse_permutation[*from as usize] = i as u8;
+            }
+            for i in 0..orbit_info.num_pieces {
+                let from = permutation[i as usize];
+                let j = inverse_permutation
+                    [transformation.get_permutation_idx(orbit_info, from) as usize];
+                conjugated.set_permutation_idx(orbit_info, i, j);
+                conjugated.set_orientation_delta(
+                    orbit_info,
+                    i,
+                    (signed_orientation(
+                        transformation.get_orientation_delta(orbit_info, from),
+                        self.reflection,
+                        orbit_info.num_orientations,
+                    ) + orientation_delta[i as usize]
+                        + orbit_info.num_orientations
+                        - orientation_delta[j as usize])
+                        % orbit_info.num_orientations,
+                );
+            }
+        }
+        conjugated
+    }
+}
+
+// An automorphism that is only known for some positions, during the search.
+#[derive(Clone)]
+struct PartialAutomorphism {
+    // Indexed by orbit, then by position.
+    permutation: Vec<Vec<Option<u8>>>,
+    inverse_permutation: Vec<Vec<Option<u8>>>,
+    orientation_delta: Vec<Vec<u8>>,
+    reflection: bool,
+    // For each move, the indices of the images that its conjugate can still be.
+    move_images: Vec<Vec<usize>>,
+}
+
+impl PartialAutomorphism {
+    // Returns `false` if this contradicts the positions that are already known.
+    fn assign(&mut self, orbit_index: usize, i: u8, from: u8, orientation_delta: u8) -> bool {
+        if let Some(existing) = self.permutation[orbit_index][i as usize] {
+            return existing == from
+                && self.orientation_delta[orbit_index][i as usize] == orientation_delta;
+        }
+        if self.inverse_permutation[orbit_index][from as usize].is_some() {
+            return false;
+        }
+        self.permutation[orbit_index][i as usize] = Some(from);
+        self.inverse_permutation[orbit_index][from as usize] = Some(i);
+        self.orientation_delta[orbit_index][i as usize] = orientation_delta;
+        true
+    }
+}
+
+// Finds the automorphisms of the definition with a backtracking search over
+// the positions and the images of the moves.
+struct AutomorphismSearch<'a> {
+    kpuzzle: &'a KPuzzle,
+    moves: Vec<KTransformation>,
+    // The base and derived moves, and their inverses.
+    images: Vec<KTransformation>,
+    // The component (orbit under the moves) of each position. Indexed by orbit, then by position.
+    components: Vec<Vec<u8>>,
+    automorphisms: Vec<Automorphism>,
+}
+
+impl<'a> AutomorphismSearch<'a> {
+    fn new(kpuzzle: &'a KPuzzle) -> Self {
+        let definition = kpuzzle.definition();
+        // Base moves are converted from their data, since their names may not parse as themselves (e.g. `B2`).
+        let moves: Vec<KTransformation> = definition
+            .moves
+            .values()
+            .map(|transformation_data| {
+                KTransformation::try_from_data(kpuzzle, transformation_data)
+                    .expect("Internal error: could not convert a move from the definition.")
+            })
+            .collect();
+        // Derived moves are only needed as images, since they are generated by the base moves.
+        let derived_moves = definition
+            .derived_moves
+            .iter()
+            .flat_map(|derived_moves| derived_moves.values())
+            .filter_map(|alg| kpuzzle.transformation_from_alg(alg).ok());
+        let mut images: Vec<KTransformation> = vec![];
+        for transformation in moves.iter().cloned().chain(derived_moves) {
+            for image in [transformation.clone(), transformation.invert()] {
+                if !images.contains(&image) {
+                    images.push(image);
+                }
+            }
+        }
+        let mut components = vec![];
+        for orbit_info in kpuzzle.orbit_info_iter() {
+            let mut orbit_components: Vec<u8> = (0..orbit_info.num_pieces).collect();
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for transformation in &moves {
+                    for i in 0..orbit_info.num_pieces {
+                        let j = transformation.get_permutation_idx(orbit_info, i);
+                        let component =
+                            orbit_components[i as usize].min(orbit_components[j as usize]);
+                        for k in [i, j] {
+                            if orbit_components[k as usize] != component {
+                                orbit_components[k as usize] = component;
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+            components.push(orbit_components);
+        }
+        Self {
+            kpuzzle,
+            moves,
+            images,
+            components,
+            automorphisms: vec![],
+        }
+    }
+
+    fn find_all(mut self) -> Result<Vec<Automorphism>, SearchError> {
+        let image_signatures: Vec<Vec<Vec<(u8, u8)>>> = self
+            .images
+            .iter()
+            .map(|image| cycle_signature(self.kpuzzle, image, false))
+            .collect();
+        for reflection in [false, true] {
+            let move_images = self
+                .moves
+                .iter()
+                .map(|r#move| {
+                    let signature = cycle_signature(self.kpuzzle, r#move, reflection);
+                    (0..self.images.len())
+                        .filter(|image_index| image_signatures[*image_index] == signature)
+                        .collect()
+                })
+                .collect();
+            let unknown = || -> Vec<Vec<Option<u8>>> {
+                self.kpuzzle
+                    .orbit_info_iter()
+                    .map(|orbit_info| vec![None; orbit_info.num_pieces as usize])
+                    .collect()
+            };
+            let partial = PartialAutomorphism {
+                permutation: unknown(),
+                inverse_permutation: unknown(),
+                orientation_delta: self
+                    .kpuzzle
+                    .orbit_info_iter()
+                    .map(|orbit_info| vec![0; orbit_info.num_pieces as usize])
+                    .collect(),
+                reflection,
+                move_images,
+            };
+            self.search(partial)?;
+        }
+        Ok(self.automorphisms)
+    }
+
+    // If position `i` receives the piece at position `from` and the move is
+    // conjugated to the image, returns the position `j` that this determines,
+    // together with the position and orientation delta that `j` receives.
+    fn implied_assignment(
+        &self,
+        partial: &PartialAutomorphism,
+        (orbit_index, orbit_info): (usize, &KPuzzleOrbitInfo),
+        (move_index, image_index): (usize, usize),
+        (i, from): (u8, u8),
+    ) -> (u8, u8, u8) {
+        let r#move = &self.moves[move_index];
+        let image = &self.images[image_index];
+        let num_orientations = orbit_info.num_orientations;
+        (
+            image.get_permutation_idx(orbit_info, i),
+            r#move.get_permutation_idx(orbit_info, from),
+            (signed_orientation(
+                r#move.get_orientation_delta(orbit_info, from),
+                partial.reflection,
+                num_orientations,
+            ) + partial.orientation_delta[orbit_index][i as usize]
+                + num_orientations
+                - image.get_orientation_delta(orbit_info, i))
+                % num_orientations,
+        )
+    }
+
+    fn is_consistent_image(
+        &self,
+        partial: &PartialAutomorphism,
+        move_index: usize,
+        image_index: usize,
+    ) -> bool {
+        for (orbit_index, orbit_info) in self.kpuzzle.orbit_info_iter().enumerate() {
+            for i in 0..orbit_info.num_pieces {
+                let Some(from) = partial.permutation[orbit_index][i as usize] else {
+                    continue;
+                };
+                let (j, j_from, j_orientation_delta) = self.implied_assignment(
+                    partial,
+                    (orbit_index, orbit_info),
+                    (move_index, image_index),
+                    (i, from),
+                );
+                let consistent = match partial.permutation[orbit_index][j as usize] {
+                    Some(existing) => {
+                        existing == j_from
+                            && partial.orientation_delta[orbit_index][j as usize]
+                                == j_orientation_delta
+                    }
+                    None => partial.inverse_permutation[orbit_index][j_from as usize].is_none(),
+                };
+                if !consistent {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // Removes inconsistent images, and assigns the positions that are
+    // determined by moves with a single image. Returns `false` if there is a
+    // contradiction.
+    fn propagate(&self, partial: &mut PartialAutomorphism) -> bool {
+        loop {
+            let mut move_images = std::mem::take(&mut partial.move_images);
+            for (move_index, images) in move_images.iter_mut().enumerate() {
+                images.retain(|image_index| {
+                    self.is_consistent_image(partial, move_index, *image_index)
+                });
+                if images.is_empty() {
+                    return false;
+                }
+            }
+            partial.move_images = move_images;
+
+            let mut changed = false;
+            for move_index in 0..self.moves.len() {
+                let [image_index] = partial.move_images[move_index][..] else {
+                    continue;
+                };
+                for (orbit_index, orbit_info) in self.kpuzzle.orbit_info_iter().enumerate() {
+                    for i in 0..orbit_info.num_pieces {
+                        let Some(from) = partial.permutation[orbit_index][i as usize] else {
+                            continue;
+                        };
+                        let (j, j_from, j_orientation_delta) = self.implied_assignment(
+                            partial,
+                            (orbit_index, orbit_info),
+                            (move_index, image_index),
+                            (i, from),
+                        );
+                        let was_unknown = partial.permutation[orbit_index][j as usize].is_none();
+                        if !partial.assign(orbit_index, j, j_from, j_orientation_delta) {
+                            return false;
+                        }
+                        changed |= was_unknown;
+                    }
+                }
+            }
+            if !changed {
+                return true;
+            }
+        }
+    }
+
+    // Whether the move affects a position whose piece is known to come from there.
+    fn affects_known_position(&self, partial: &PartialAutomorphism, move_index: usize) -> bool {
+        let r#move = &self.moves[move_index];
+        self.kpuzzle
+            .orbit_info_iter()
+            .enumerate()
+            .any(|(orbit_index, orbit_info)| {
+                partial.permutation[orbit_index]
+                    .iter()
+                    .flatten()
+                    .any(|from| {
+                        r#move.get_permutation_idx(orbit_info, *from) != *from
+                            || r#move.get_orientation_delta(orbit_info, *from) != 0
+                    })
+            })
+    }
+
+    fn search(&mut self, mut partial: PartialAutomorphism) -> Result<(), SearchError> {
+        if !self.propagate(&mut partial) {
+            return Ok(());
+        }
+
+        // Fixing the image of a move that affects a known position determines more positions.
+        if let Some(move_index) = (0..self.moves.len())
+            .filter(|move_index| {
+                partial.move_images[*move_index].len() > 1
+                    && self.affects_known_position(&partial, *move_index)
+            })
+            .min_by_key(|move_index| partial.move_images[*move_index].len())
+        {
+            for image_index in partial.move_images[move_index].clone() {
+                let mut branch = partial.clone();
+                branch.move_images[move_index] = vec![image_index];
+                self.search(branch)?;
+            }
+            return Ok(());
+        }
+
+        let Some((orbit_index, orbit_info, i)) = self
+            .kpuzzle
+            .orbit_info_iter()
+            .enumerate()
+            .find_map(|(orbit_index, orbit_info)| {
+                (0..orbit_info.num_pieces)
+                    .find(|i| partial.permutation[orbit_index][*i as usize].is_none())
+                    .map(|i| (orbit_index, orbit_info, i))
+            })
+        else {
+            if self.automorphisms.len() == MAX_SYMMETRY_GROUP_SIZE {
+                return Err(SearchError {
+                    description: format!(
+                        "Could not determine the symmetry group (more than {} candidate symmetries).",
+                        MAX_SYMMETRY_GROUP_SIZE
+                    ),
+                });
+            }
+            self.automorphisms.push(Automorphism {
+                permutation: partial
+                    .permutation
+                    .iter()
+                    .map(|orbit_permutation| orbit_permutation.iter().flatten().copied().collect())
+                    .collect(),
+                orientation_delta: partial.orientation_delta,
+                reflection: partial.reflection,
+            });
+            return Ok(());
+        };
+
+        // Adding the same orientation delta to every position of a component
+        // conjugates each move the same way, so the orientation delta is only
+        // chosen if it is not determined by another position of the component.
+        let component = &self.components[orbit_index];
+        let is_new_component = (0..orbit_info.num_pieces).all(|j| {
+            component[j as usize] != component[i as usize]
+                || partial.permutation[orbit_index][j as usize].is_none()
+        });
+        let num_orientation_deltas = if is_new_component {
+            1
+        } else {
+            orbit_info.num_orientations
+        };
+        for from in 0..orbit_info.num_pieces {
+            if partial.inverse_permutation[orbit_index][from as usize].is_some() {
+                continue;
+            }
+            for orientation_delta in 0..num_orientation_deltas {
+                let mut branch = partial.clone();
+                branch.assign(orbit_index, i, from, orientation_delta);
+                self.search(branch)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Finds the relabeling `R` such that `R(start · s) = start`, which commutes
+// with applying transformations. Returns `None` if there is no such relabeling
+// (i.e. `s` does not fix the start pattern).
+fn relabeling(
+    kpuzzle: &KPuzzle,
+    start_pattern: &KPattern,
+    automorphism: &Automorphism,
+) -> Option<Vec<Vec<Option<Relabel>>>> {
+    let transformed = automorphism.apply_to_pattern(kpuzzle, start_pattern);
+    let mut relabeling = vec![];
+    for orbit_info in kpuzzle.orbit_info_iter() {
+        let mut orbit_relabeling: Vec<Option<Relabel>> =
+            vec![None; orbit_info.num_pieces as usize * (orbit_info.num_orientations as usize + 1)];
+        for i in 0..orbit_info.num_pieces {
+            let from = transformed.get_orientation_with_mod(orbit_info, i);
+            let to = start_pattern.get_orientation_with_mod(orbit_info, i);
+            let from_label = (transformed.get_piece(orbit_info, i), from.orientation_mod);
+            let to_label = (start_pattern.get_piece(orbit_info, i), to.orientation_mod);
+            let to_orientation_mod = effective_orientation_mod(orbit_info, to.orientation_mod);
+            let entry = &mut orbit_relabeling[label_index(orbit_info, from_label)];
+            let relabel = entry.get_or_insert(Relabel {
+                label: to_label,
+                orientation_offset: (to_orientation_mod - from.orientation % to_orientation_mod
+                    + to.orientation)
+                    % to_orientation_mod,
+            });
+            if relabel.label != to_label
+                || (from.orientation + relabel.orientation_offset) % to_orientation_mod
+                    != to.orientation
+            {
+                return None;
+            }
+        }
+        relabeling.push(orbit_relabeling);
+    }
+    Some(relabeling)
+}
+
+impl SymmetryGroup {
+    pub fn try_new(
+        kpuzzle: &KPuzzle,
+        start_pattern: &KPattern,
+        search_move_transformations: &[KTransformation],
+    ) -> Result<Self, SearchError> {
+        let automorphisms = AutomorphismSearch::new(kpuzzle).find_all()?;
+
+        let mut symmetry_group = Self {
+            kpuzzle: kpuzzle.clone(),
+            symmetries: vec![],
+        };
+        for automorphism in automorphisms {
+            let preserves_moves = search_move_transformations.iter().all(|search_move| {
+                search_move_transformations
+                    .contains(&automorphism.conjugate_transformation(kpuzzle, search_move))
+            });
+            if !preserves_moves {
+                continue;
+            }
+            let Some(relabeling) = relabeling(kpuzzle, start_pattern, &automorphism) else {
+                continue;
+            };
+            symmetry_group.symmetries.push(Symmetry {
+                automorphism,
+                relabeling,
+            });
+        }
+        Ok(symmetry_group)
+    }
+
+    pub fn num_symmetries(&self) -> usize {
+        self.symmetries.len()
+    }
+
+    // For `pattern = start · t`, calculates `start · (s⁻¹ · t · s)` as `R(pattern · s)`.
+    fn conjugate_pattern(&self, pattern: &KPattern, symmetry: &Symmetry) -> KPattern {
+        let mut conjugated = symmetry
+            .automorphism
+            .apply_to_pattern(&self.kpuzzle, pattern);
+        for (orbit_info, orbit_relabeling) in
+            self.kpuzzle.orbit_info_iter().zip(&symmetry.relabeling)
+        {
+            for i in 0..orbit_info.num_pieces {
+                let orientation_with_mod = conjugated.get_orientation_with_mod(orbit_info, i);
+                let label = (
+                    conjugated.get_piece(orbit_info, i),
+                    orientation_with_mod.orientation_mod,
+                );
+                let relabel = orbit_relabeling[label_index(orbit_info, label)].expect(
+                    "Internal error: a pattern contains a piece that is not in the start pattern.",
+                );
+                let orientation_mod = effective_orientation_mod(orbit_info, relabel.label.1);
+                conjugated.set_piece(orbit_info, i, relabel.label.0);
+                conjugated.set_orientation_with_mod(
+                    orbit_info,
+                    i,
+                    &OrientationWithMod {
+                        orientation: (orientation_with_mod.orientation
+                            + relabel.orientation_offset)
+                            % orientation_mod,
+                        orientation_mod: relabel.label.1,
+                    },
+                );
+            }
+        }
+        conjugated
+    }
+
+    /// Returns the representative of the symmetry class of the pattern (the
+    /// conjugate with the smallest packed representation), as well as the
+    /// number of distinct patterns in the class.
+    pub fn canonicalize(&self, pattern: &KPattern) -> (KPattern, usize) {
+        let mut conjugates: Vec<KPattern> = self
+            .symmetries
+            .iter()
+            .map(|symmetry| self.conjugate_pattern(pattern, symmetry))
+            .collect();
+        conjugates.sort_unstable_by(|a, b| pattern_bytes(a).cmp(pattern_bytes(b)));
+        conjugates.dedup();
+        let class_size = conjugates.len();
+        (conjugates.swap_remove(0), class_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use cubing::{
+        alg::parse_move,
+        kpuzzle::{KTransformation, OrientationWithMod},
+        puzzles::{cube2x2x2_kpuzzle, cube3x3x3_kpuzzle},
+    };
+
+    use super::SymmetryGroup;
+
+    #[test]
+    fn reflections_3x3x3_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let search_move_transformations: Vec<KTransformation> = ["U", "L", "F", "R", "B", "D"]
+            .iter()
+            .flat_map(|family| {
+                [
+                    family.to_string(),
+                    format!("{}2", family),
+                    format!("{}'", family),
+                ]
+            })
+            .map(|r#move| {
+                kpuzzle
+                    .transformation_from_move(&r#move.parse().unwrap())
+                    .unwrap()
+            })
+            .collect();
+        let start_pattern = kpuzzle.default_pattern();
+        let symmetry_group =
+            SymmetryGroup::try_new(kpuzzle, &start_pattern, &search_move_transformations).unwrap();
+        assert_eq!(symmetry_group.num_symmetries(), 48);
+
+        // The number of positions at each distance (in the half-turn metric) up to the 48 symmetries, from https://cube20.org/
+        let mut seen = HashSet::from([symmetry_group.canonicalize(&start_pattern).0]);
+        let mut frontier = vec![start_pattern];
+        let mut depth_counts = vec![1];
+        for _ in 0..4 {
+            let mut next_frontier = vec![];
+            for pattern in &frontier {
+                for transformation in &search_move_transformations {
+                    let (representative, _) =
+                        symmetry_group.canonicalize(&pattern.apply_transformation(transformation));
+                    if seen.insert(representative.clone()) {
+                        next_frontier.push(representative);
+                    }
+                }
+            }
+            depth_counts.push(next_frontier.len());
+            frontier = next_frontier;
+        }
+        assert_eq!(depth_counts, vec![1, 2, 9, 75, 934]);
+    }
+
+    #[test]
+    fn identical_pieces_symmetry_test() {
+        let kpuzzle = cube2x2x2_kpuzzle();
+        let search_move_transformations: Vec<KTransformation> = ["U", "R", "F", "U'", "R'", "F'"]
+            .iter()
+            .map(|r#move| {
+                kpuzzle
+                    .transformation_from_move(&r#move.parse().unwrap())
+                    .unwrap()
+            })
+            .collect();
+        let orbit_info = kpuzzle.orbit_info_iter().next().unwrap();
+        let u = kpuzzle.transformation_from_move(parse_move!("U")).unwrap();
+
+        // `UFR`, `DBL`, and the two orbits of the other corners under rotation around `UFR`.
+        let mut identical_pieces = kpuzzle.default_pattern();
+        // Only permutation is tracked.
+        let mut orientation_wildcards = kpuzzle.default_pattern();
+        for (i, piece) in [0, 2, 3, 2, 2, 3, 1, 3].into_iter().enumerate() {
+            identical_pieces.set_piece(orbit_info, i as u8, piece);
+        }
+        for i in 0..8 {
+            orientation_wildcards.set_orientation_with_mod(
+                orbit_info,
+                i,
+                &OrientationWithMod {
+                    orientation: 0,
+                    orientation_mod: 1,
+                },
+            );
+        }
+
+        for start_pattern in [
+            kpuzzle.default_pattern(),
+            identical_pieces,
+            orientation_wildcards,
+        ] {
+            // The rotations around the `UFR` corner, and the reflections through the planes that contain it.
+            let symmetry_group =
+                SymmetryGroup::try_new(kpuzzle, &start_pattern, &search_move_transformations)
+                    .unwrap();
+            assert_eq!(symmetry_group.num_symmetries(), 6);
+            assert_eq!(
+                symmetry_group.canonicalize(&start_pattern),
+                (start_pattern.clone(), 1)
+            );
+            // `U`, `R`, `F`, `U'`, `R'`, and `F'`.
+            assert_eq!(
+                symmetry_group
+                    .canonicalize(&start_pattern.apply_transformation(&u))
+                    .1,
+                6
+            );
+        }
+    }
+}
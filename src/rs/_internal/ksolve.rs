@@ -0,0 +1,905 @@
+//! Reading and writing the ksolve text format used by `twsearch` (`.tws` definition files and `.scr` scramble files).
+
+use std::{collections::HashMap, fmt::Write};
+
+use cubing::{
+    alg::{Alg, Move},
+    kpuzzle::{
+        KPattern, KPatternData, KPatternOrbitData, KPuzzle, KPuzzleDefinition,
+        KPuzzleOrbitDefinition, KPuzzleOrbitName, KTransformationData, KTransformationOrbitData,
+    },
+};
+
+use super::errors::ArgumentError;
+
+const END: &str = "End";
+const WILDCARD_ORIENTATION: &str = "?";
+
+/// A heuristic for files that may be in either format: JSON files start with `{` (or `[`).
+pub fn is_probably_json(input: &str) -> bool {
+    matches!(input.trim_start().chars().next(), Some('{' | '['))
+}
+
+/// A heuristic for scramble files that may contain either ksolve scramble blocks or one alg per line.
+pub fn is_probably_ksolve_scrambles(input: &str) -> bool {
+    Lines::new(input).next_tokens().is_some_and(|tokens| {
+        matches!(
+            tokens[0],
+            "Scramble" | "ScrambleState" | "StartState" | "ScrambleAlg"
+        )
+    })
+}
+
+struct Lines<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+    line_number: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            lines: input.lines().enumerate(),
+            line_number: 0,
+        }
+    }
+
+    // Returns the tokens of the next line that is not blank or a comment.
+    fn next_tokens(&mut self) -> Option<Vec<&'a str>> {
+        for (i, line) in self.lines.by_ref() {
+            let line = match line.find('#') {
+                Some(comment_start) => &line[..comment_start],
+                None => line,
+            };
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if !tokens.is_empty() {
+                self.line_number = i + 1;
+                return Some(tokens);
+            }
+        }
+        None
+    }
+
+    fn error(&self, message: impl Into<String>) -> ArgumentError {
+        line_error(self.line_number, message)
+    }
+
+    fn expect_num_tokens(&self, tokens: &[&str], num_tokens: usize) -> Result<(), ArgumentError> {
+        if tokens.len() != num_tokens {
+            return Err(self.error(format!(
+                "Expected {} tokens for `{}` but found {}.",
+                num_tokens,
+                tokens[0],
+                tokens.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn line_error(line_number: usize, message: impl Into<String>) -> ArgumentError {
+    ArgumentError {
+        description: format!("Line {}: {}", line_number, message.into()),
+    }
+}
+
+// A line of numbers, kept with its line number so that errors point at the right line.
+struct NumberLine<'a> {
+    line_number: usize,
+    tokens: Vec<&'a str>,
+}
+
+// The number lines of a block for each orbit, before they are converted to a pattern or transformation.
+struct RawBlock<'a> {
+    orbits: HashMap<usize, Vec<NumberLine<'a>>>,
+}
+
+fn read_block<'a>(
+    lines: &mut Lines<'a>,
+    orbits: &[KPuzzleOrbitDefinition],
+) -> Result<RawBlock<'a>, ArgumentError> {
+    let mut raw_block = RawBlock {
+        orbits: HashMap::default(),
+    };
+    let mut current_orbit: Option<usize> = None;
+    loop {
+        let Some(tokens) = lines.next_tokens() else {
+            return Err(lines.error(format!("Missing `{}`.", END)));
+        };
+        if tokens[0] == END {
+            lines.expect_num_tokens(&tokens, 1)?;
+            return Ok(raw_block);
+        }
+        let is_number_line = tokens
+            .iter()
+            .all(|token| *token == WILDCARD_ORIENTATION || token.parse::<u8>().is_ok());
+        if is_number_line {
+            let Some(orbit_index) = current_orbit else {
+                return Err(lines.error("Numbers must follow a set name."));
+            };
+            let orbit_lines = raw_block.orbits.get_mut(&orbit_index).unwrap();
+            if orbit_lines.len() == 2 {
+                return Err(lines.error("Expected at most two lines of numbers for a set."));
+            }
+            if tokens.len() != orbits[orbit_index].num_pieces as usize {
+                return Err(lines.error(format!(
+                    "Expected {} numbers for set `{}` but found {}.",
+                    orbits[orbit_index].num_pieces,
+                    orbits[orbit_index].orbit_name,
+                    tokens.len()
+                )));
+            }
+            orbit_lines.push(NumberLine {
+                line_number: lines.line_number,
+                tokens,
+            });
+            continue;
+        }
+        lines.expect_num_tokens(&tokens, 1)?;
+        let Some(orbit_index) = orbits
+            .iter()
+            .position(|orbit| orbit.orbit_name.0 == tokens[0])
+        else {
+            return Err(lines.error(format!("Unknown set name: {}", tokens[0])));
+        };
+        if raw_block.orbits.insert(orbit_index, vec![]).is_some() {
+            return Err(lines.error(format!("Set specified twice: {}", tokens[0])));
+        }
+        current_orbit = Some(orbit_index);
+    }
+}
+
+// Parses a line of numbers with the given `offset` (1 for one-indexed values).
+fn parse_numbers(line: &NumberLine, offset: u8, max: u8) -> Result<Vec<u8>, ArgumentError> {
+    line.tokens
+        .iter()
+        .map(|token| parse_number(line.line_number, token, offset, max))
+        .collect()
+}
+
+fn parse_number(line_number: usize, token: &str, offset: u8, max: u8) -> Result<u8, ArgumentError> {
+    match token.parse::<u8>() {
+        Ok(value) if value >= offset && value - offset < max => Ok(value - offset),
+        _ => Err(line_error(line_number, format!("Invalid value: {}", token))),
+    }
+}
+
+fn pattern_orbit_data(
+    orbit: &KPuzzleOrbitDefinition,
+    orbit_lines: &[NumberLine],
+    zero_indexed: bool,
+) -> Result<KPatternOrbitData, ArgumentError> {
+    let pieces = parse_numbers(
+        &orbit_lines[0],
+        if zero_indexed { 0 } else { 1 },
+        orbit.num_pieces,
+    )?;
+    let mut orientation = vec![0; orbit.num_pieces as usize];
+    let mut orientation_mod = None;
+    if let Some(orientation_line) = orbit_lines.get(1) {
+        for (i, token) in orientation_line.tokens.iter().enumerate() {
+            if *token == WILDCARD_ORIENTATION {
+                orientation_mod.get_or_insert_with(|| vec![0; orbit.num_pieces as usize])[i] = 1;
+            } else {
+                orientation[i] = parse_number(
+                    orientation_line.line_number,
+                    token,
+                    0,
+                    orbit.num_orientations,
+                )?;
+            }
+        }
+    }
+    Ok(KPatternOrbitData {
+        pieces,
+        orientation,
+        orientation_mod,
+    })
+}
+
+fn transformation_orbit_data(
+    orbit: &KPuzzleOrbitDefinition,
+    orbit_lines: &[NumberLine],
+    zero_indexed: bool,
+) -> Result<KTransformationOrbitData, ArgumentError> {
+    if let Some(line) = orbit_lines
+        .iter()
+        .find(|line| line.tokens.contains(&WILDCARD_ORIENTATION))
+    {
+        return Err(line_error(line.line_number, "Moves cannot use `?`."));
+    }
+    let permutation = parse_numbers(
+        &orbit_lines[0],
+        if zero_indexed { 0 } else { 1 },
+        orbit.num_pieces,
+    )?;
+    let mut sorted_permutation = permutation.clone();
+    sorted_permutation.sort();
+    if sorted_permutation != (0..orbit.num_pieces).collect::<Vec<u8>>() {
+        return Err(line_error(
+            orbit_lines[0].line_number,
+            "Move permutations must contain each position exactly once.",
+        ));
+    }
+    let orientation_delta = match orbit_lines.get(1) {
+        Some(orientation_line) => {
+            let orientation_delta = parse_numbers(orientation_line, 0, orbit.num_orientations)?;
+            if zero_indexed {
+                orientation_delta
+            } else {
+                // In the one-indexed (ksolve) format, orientation changes are listed by the position that a piece moves from.
+                permutation
+                    .iter()
+                    .map(|from| orientation_delta[*from as usize])
+                    .collect()
+            }
+        }
+        None => vec![0; orbit.num_pieces as usize],
+    };
+    Ok(KTransformationOrbitData {
+        permutation,
+        orientation_delta,
+    })
+}
+
+fn read_pattern(
+    lines: &mut Lines,
+    orbits: &[KPuzzleOrbitDefinition],
+    zero_indexed: bool,
+    default_pattern: Option<&KPatternData>,
+) -> Result<KPatternData, ArgumentError> {
+    let raw_block = read_block(lines, orbits)?;
+    let mut pattern_data = KPatternData::default();
+    for (orbit_index, orbit) in orbits.iter().enumerate() {
+        let orbit_data = match (raw_block.orbits.get(&orbit_index), default_pattern) {
+            (Some(orbit_lines), _) if !orbit_lines.is_empty() => {
+                pattern_orbit_data(orbit, orbit_lines, zero_indexed)?
+            }
+            // Sets that are not specified are unchanged.
+            (_, Some(default_pattern)) => default_pattern[&orbit.orbit_name].clone(),
+            (_, None) => KPatternOrbitData {
+                pieces: (0..orbit.num_pieces).collect(),
+                orientation: vec![0; orbit.num_pieces as usize],
+                orientation_mod: None,
+            },
+        };
+        pattern_data.insert(orbit.orbit_name.clone(), orbit_data);
+    }
+    Ok(pattern_data)
+}
+
+fn read_transformation(
+    lines: &mut Lines,
+    orbits: &[KPuzzleOrbitDefinition],
+    zero_indexed: bool,
+) -> Result<KTransformationData, ArgumentError> {
+    let raw_block = read_block(lines, orbits)?;
+    let mut transformation_data = KTransformationData::default();
+    for (orbit_index, orbit) in orbits.iter().enumerate() {
+        let orbit_data = match raw_block.orbits.get(&orbit_index) {
+            Some(orbit_lines) if !orbit_lines.is_empty() => {
+                transformation_orbit_data(orbit, orbit_lines, zero_indexed)?
+            }
+            _ => KTransformationOrbitData {
+                permutation: (0..orbit.num_pieces).collect(),
+                orientation_delta: vec![0; orbit.num_pieces as usize],
+            },
+        };
+        transformation_data.insert(orbit.orbit_name.clone(), orbit_data);
+    }
+    Ok(transformation_data)
+}
+
+// In an `Ignore` block, each set has a line of flags for pieces and (optionally) a line of flags for orientations.
+// Ignored pieces are made identical, and ignored orientations become wildcards.
+fn apply_ignore_block(
+    lines: &mut Lines,
+    orbits: &[KPuzzleOrbitDefinition],
+    default_pattern: &mut KPatternData,
+) -> Result<(), ArgumentError> {
+    let raw_block = read_block(lines, orbits)?;
+    for (orbit_index, orbit_lines) in raw_block.orbits {
+        let orbit = &orbits[orbit_index];
+        let orbit_data = default_pattern.get_mut(&orbit.orbit_name).unwrap();
+        if let Some(piece_flags) = orbit_lines.first() {
+            let piece_flags = parse_numbers(piece_flags, 0, 2)?;
+            let mut shared_piece = None;
+            for (i, flag) in piece_flags.iter().enumerate() {
+                if *flag == 1 {
+                    orbit_data.pieces[i] = *shared_piece.get_or_insert(orbit_data.pieces[i]);
+                }
+            }
+        }
+        if let Some(orientation_flags) = orbit_lines.get(1) {
+            let orientation_flags = parse_numbers(orientation_flags, 0, 2)?;
+            for (i, flag) in orientation_flags.iter().enumerate() {
+                if *flag == 1 {
+                    orbit_data.orientation[i] = 0;
+                    orbit_data
+                        .orientation_mod
+                        .get_or_insert_with(|| vec![0; orbit.num_pieces as usize])[i] = 1;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_move(lines: &Lines, move_str: &str) -> Result<Move, ArgumentError> {
+    move_str
+        .parse::<Move>()
+        .map_err(|_| lines.error(format!("Invalid move name: {}", move_str)))
+}
+
+/// Parses a puzzle definition in the ksolve text format (as used by `.tws` files).
+///
+/// Supports `Name`, `Set`, `Solved`/`StartState`, `Move`/`MoveTransformation`,
+/// `Ignore`, `MoveAlias`, and `MoveSequence` blocks. Blocks using the ksolve
+/// names (`Solved`, `Move`) use one-indexed permutations, while
+/// `StartState` and `MoveTransformation` are zero-indexed.
+pub fn parse_ksolve_definition(input: &str) -> Result<KPuzzleDefinition, ArgumentError> {
+    let mut lines = Lines::new(input);
+    let mut name: Option<String> = None;
+    let mut orbits: Vec<KPuzzleOrbitDefinition> = vec![];
+    let mut default_pattern: Option<KPatternData> = None;
+    let mut moves: HashMap<Move, KTransformationData> = HashMap::default();
+    let mut derived_moves: HashMap<Move, Alg> = HashMap::default();
+    while let Some(tokens) = lines.next_tokens() {
+        match tokens[0] {
+            "Name" => {
+                if name.is_some() || !orbits.is_empty() {
+                    return Err(lines.error("`Name` must be at the start of the definition."));
+                }
+                lines.expect_num_tokens(&tokens, 2)?;
+                name = Some(tokens[1].to_owned());
+            }
+            "Set" => {
+                if default_pattern.is_some() {
+                    return Err(lines.error("`Set` must come before the solved state."));
+                }
+                lines.expect_num_tokens(&tokens, 4)?;
+                let parse_count = |token: &str| match token.parse::<u8>() {
+                    Ok(count) if count > 0 => Ok(count),
+                    _ => Err(lines.error(format!("Invalid count: {}", token))),
+                };
+                orbits.push(KPuzzleOrbitDefinition {
+                    orbit_name: KPuzzleOrbitName(tokens[1].to_owned()),
+                    num_pieces: parse_count(tokens[2])?,
+                    num_orientations: parse_count(tokens[3])?,
+                });
+            }
+            "Solved" | "StartState" => {
+                if orbits.is_empty() || default_pattern.is_some() {
+                    return Err(lines.error(
+                        "The solved state must come after the sets, and can only be specified once.",
+                    ));
+                }
+                lines.expect_num_tokens(&tokens, 1)?;
+                let zero_indexed = tokens[0] == "StartState";
+                default_pattern = Some(read_pattern(&mut lines, &orbits, zero_indexed, None)?);
+            }
+            "Ignore" => {
+                let Some(default_pattern) = &mut default_pattern else {
+                    return Err(lines.error("`Ignore` must come after the solved state."));
+                };
+                lines.expect_num_tokens(&tokens, 1)?;
+                apply_ignore_block(&mut lines, &orbits, default_pattern)?;
+            }
+            "Move" | "MoveTransformation" => {
+                if default_pattern.is_none() {
+                    return Err(lines.error("Moves must come after the solved state."));
+                }
+                lines.expect_num_tokens(&tokens, 2)?;
+                let r#move = parse_move(&lines, tokens[1])?;
+                if moves.contains_key(&r#move) {
+                    return Err(lines.error(format!("Move defined twice: {}", tokens[1])));
+                }
+                let zero_indexed = tokens[0] == "MoveTransformation";
+                let transformation = read_transformation(&mut lines, &orbits, zero_indexed)?;
+                moves.insert(r#move, transformation);
+            }
+            "MoveAlias" | "MoveSequence" => {
+                if tokens.len() < 3 {
+                    return Err(lines.error(format!("Too few tokens for `{}`.", tokens[0])));
+                }
+                let r#move = parse_move(&lines, tokens[1])?;
+                let alg = tokens[2..]
+                    .join(" ")
+                    .parse::<Alg>()
+                    .map_err(|_| lines.error(format!("Invalid alg for: {}", tokens[1])))?;
+                derived_moves.insert(r#move, alg);
+            }
+            token => {
+                return Err(lines.error(format!("Unsupported block: {}", token)));
+            }
+        }
+    }
+    let Some(default_pattern) = default_pattern else {
+        return Err(ArgumentError {
+            description: "The definition does not have a solved state.".to_owned(),
+        });
+    };
+    Ok(KPuzzleDefinition {
+        name: name.unwrap_or_else(|| "Unnamed".to_owned()),
+        orbits,
+        default_pattern,
+        moves,
+        derived_moves: (!derived_moves.is_empty()).then_some(derived_moves),
+    })
+}
+
+/// A named scramble from a scramble file.
+#[derive(Debug)]
+pub struct KsolveScramble {
+    pub name: String,
+    pub pattern: KPattern,
+}
+
+/// Parses the scrambles in a ksolve scramble file (e.g. `.scr`), using
+/// `Scramble` (one-indexed), `ScrambleState`/`StartState` (zero-indexed), or
+/// `ScrambleAlg` blocks.
+pub fn parse_ksolve_scrambles(
+    kpuzzle: &KPuzzle,
+    input: &str,
+) -> Result<Vec<KsolveScramble>, ArgumentError> {
+    let def = kpuzzle.definition();
+    let mut lines = Lines::new(input);
+    let mut scrambles = vec![];
+    while let Some(tokens) = lines.next_tokens() {
+        let pattern = match tokens[0] {
+            "Scramble" | "ScrambleState" | "StartState" => {
+                lines.expect_num_tokens(&tokens, 2)?;
+                let zero_indexed = tokens[0] != "Scramble";
+                let pattern_data = read_pattern(
+                    &mut lines,
+                    &def.orbits,
+                    zero_indexed,
+                    Some(&def.default_pattern),
+                )?;
+                KPattern::try_from_data(kpuzzle, &pattern_data)
+                    .map_err(|e| lines.error(e.to_string()))?
+            }
+            "ScrambleAlg" => {
+                lines.expect_num_tokens(&tokens, 2)?;
+                let mut pattern = kpuzzle.default_pattern();
+                loop {
+                    let Some(move_tokens) = lines.next_tokens() else {
+                        return Err(lines.error(format!("Missing `{}`.", END)));
+                    };
+                    if move_tokens == [END] {
+                        break;
+                    }
+                    let alg = move_tokens
+                        .join(" ")
+                        .parse::<Alg>()
+                        .map_err(|_| lines.error("Invalid alg."))?;
+                    pattern = pattern
+                        .apply_alg(&alg)
+                        .map_err(|e| lines.error(e.to_string()))?;
+                }
+                pattern
+            }
+            token => {
+                return Err(lines.error(format!("Unsupported block in scramble file: {}", token)));
+            }
+        };
+        scrambles.push(KsolveScramble {
+            name: tokens[1].to_owned(),
+            pattern,
+        });
+    }
+    Ok(scrambles)
+}
+
+fn write_numbers(output: &mut String, numbers: impl Iterator<Item = String>) {
+    let numbers: Vec<String> = numbers.collect();
+    let _ = writeln!(output, "{}", numbers.join(" "));
+}
+
+fn write_pattern_data(
+    output: &mut String,
+    orbits: &[KPuzzleOrbitDefinition],
+    pattern_data: &KPatternData,
+) -> Result<(), ArgumentError> {
+    for orbit in orbits {
+        let orbit_data = &pattern_data[&orbit.orbit_name];
+        let _ = writeln!(output, "{}", orbit.orbit_name);
+        write_numbers(output, orbit_data.pieces.iter().map(u8::to_string));
+        let mut orientation = vec![];
+        for (i, value) in orbit_data.orientation.iter().enumerate() {
+            match orbit_data.orientation_mod.as_ref().map(|mods| mods[i]) {
+                None | Some(0) => orientation.push(value.to_string()),
+                Some(1) => orientation.push(WILDCARD_ORIENTATION.to_owned()),
+                Some(orientation_mod) if orientation_mod == orbit.num_orientations => {
+                    orientation.push(value.to_string())
+                }
+                Some(_) => {
+                    return Err(ArgumentError {
+                        description: format!(
+                            "Orientation mod values other than 0 or 1 cannot be written in the ksolve format (set: {}).",
+                            orbit.orbit_name
+                        ),
+                    })
+                }
+            }
+        }
+        write_numbers(output, orientation.into_iter());
+    }
+    let _ = writeln!(output, "{}", END);
+    Ok(())
+}
+
+/// Writes a definition in the ksolve text format, using zero-indexed
+/// `StartState` and `MoveTransformation` blocks. Derived moves are written as
+/// `MoveSequence` blocks. The output can be read back using
+/// [`parse_ksolve_definition`].
+pub fn ksolve_definition_string(def: &KPuzzleDefinition) -> Result<String, ArgumentError> {
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "Name {}",
+        def.name.split_whitespace().collect::<Vec<_>>().join("_")
+    );
+    let _ = writeln!(output);
+    for orbit in &def.orbits {
+        let _ = writeln!(
+            output,
+            "Set {} {} {}",
+            orbit.orbit_name, orbit.num_pieces, orbit.num_orientations
+        );
+    }
+    let _ = writeln!(output);
+    let _ = writeln!(output, "StartState");
+    write_pattern_data(&mut output, &def.orbits, &def.default_pattern)?;
+
+    let mut moves: Vec<(&Move, &KTransformationData)> = def.moves.iter().collect();
+    moves.sort_by_key(|(r#move, _)| r#move.to_string());
+    for (r#move, transformation_data) in moves {
+        let _ = writeln!(output);
+        let _ = writeln!(output, "MoveTransformation {}", r#move);
+        for orbit in &def.orbits {
+            let orbit_data = &transformation_data[&orbit.orbit_name];
+            let _ = writeln!(output, "{}", orbit.orbit_name);
+            write_numbers(
+                &mut output,
+                orbit_data.permutation.iter().map(u8::to_string),
+            );
+            write_numbers(
+                &mut output,
+                orbit_data.orientation_delta.iter().map(u8::to_string),
+            );
+        }
+        let _ = writeln!(output, "{}", END);
+    }
+
+    if let Some(derived_moves) = &def.derived_moves {
+        let mut derived_moves: Vec<(&Move, &Alg)> = derived_moves.iter().collect();
+        derived_moves.sort_by_key(|(r#move, _)| r#move.to_string());
+        let _ = writeln!(output);
+        for (r#move, alg) in derived_moves {
+            let _ = writeln!(output, "MoveSequence {} {}", r#move, alg);
+        }
+    }
+    Ok(output)
+}
+
+/// Writes a scramble in the ksolve text format, as a zero-indexed `ScrambleState` block.
+pub fn ksolve_scramble_string(
+    kpuzzle: &KPuzzle,
+    name: &str,
+    pattern: &KPattern,
+) -> Result<String, ArgumentError> {
+    let mut output = String::new();
+    let _ = writeln!(output, "ScrambleState {}", name);
+    write_pattern_data(
+        &mut output,
+        &kpuzzle.definition().orbits,
+        &pattern.to_data(),
+    )?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::{
+        alg::{Alg, Move},
+        kpuzzle::{KPattern, KPuzzle, KPuzzleDefinition, KPuzzleOrbitName},
+    };
+
+    use super::{
+        ksolve_definition_string, ksolve_scramble_string, parse_ksolve_definition,
+        parse_ksolve_scrambles,
+    };
+
+    const DEFINITION: &str = "
+Name Test
+
+Set CORNERS 3 3
+Set EDGES 2 2
+
+Solved
+CORNERS
+1 2 3
+0 0 0
+EDGES
+1 2
+End
+
+Move A
+CORNERS
+2 3 1
+1 2 0
+End
+
+MoveTransformation B
+EDGES
+1 0
+1 1
+End
+
+MoveAlias C A
+MoveSequence D A B
+";
+
+    fn orbit(orbit_name: &str) -> KPuzzleOrbitName {
+        KPuzzleOrbitName(orbit_name.to_owned())
+    }
+
+    // `KPuzzleDefinition` does not implement `PartialEq`, so compare field by field.
+    fn assert_definitions_equal(def1: &KPuzzleDefinition, def2: &KPuzzleDefinition) {
+        assert_eq!(def1.name, def2.name);
+        let orbits = |def: &KPuzzleDefinition| {
+            def.orbits
+                .iter()
+                .map(|orbit| {
+                    (
+                        orbit.orbit_name.clone(),
+                        orbit.num_pieces,
+                        orbit.num_orientations,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(orbits(def1), orbits(def2));
+        assert_eq!(def1.default_pattern, def2.default_pattern);
+        assert_eq!(def1.moves, def2.moves);
+        assert_eq!(def1.derived_moves, def2.derived_moves);
+    }
+
+    fn error_description(input: &str) -> String {
+        parse_ksolve_definition(input).unwrap_err().description
+    }
+
+    #[test]
+    fn ksolve_definition_test() {
+        let def = parse_ksolve_definition(DEFINITION).unwrap();
+        assert_eq!(def.name, "Test");
+        assert_eq!(def.orbits.len(), 2);
+        assert_eq!(def.default_pattern[&orbit("CORNERS")].pieces, vec![0, 1, 2]);
+        assert_eq!(def.default_pattern[&orbit("CORNERS")].orientation_mod, None);
+
+        // One-indexed (`Move`): orientation changes are listed by the position that each piece moves from.
+        let a = &def.moves[&"A".parse::<Move>().unwrap()];
+        assert_eq!(a[&orbit("CORNERS")].permutation, vec![1, 2, 0]);
+        assert_eq!(a[&orbit("CORNERS")].orientation_delta, vec![2, 0, 1]);
+        assert_eq!(a[&orbit("EDGES")].permutation, vec![0, 1]);
+        // Zero-indexed (`MoveTransformation`): orientation changes are listed by position.
+        let b = &def.moves[&"B".parse::<Move>().unwrap()];
+        assert_eq!(b[&orbit("EDGES")].permutation, vec![1, 0]);
+        assert_eq!(b[&orbit("EDGES")].orientation_delta, vec![1, 1]);
+
+        let derived_moves = def.derived_moves.as_ref().unwrap();
+        assert_eq!(
+            derived_moves[&"C".parse::<Move>().unwrap()],
+            "A".parse::<Alg>().unwrap()
+        );
+        assert_eq!(
+            derived_moves[&"D".parse::<Move>().unwrap()],
+            "A B".parse::<Alg>().unwrap()
+        );
+        let kpuzzle = KPuzzle::try_from(def).unwrap();
+        assert_eq!(
+            kpuzzle
+                .default_pattern()
+                .apply_alg(&"D".parse::<Alg>().unwrap())
+                .unwrap(),
+            kpuzzle
+                .default_pattern()
+                .apply_alg(&"A B".parse::<Alg>().unwrap())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn ksolve_ignore_and_wildcard_test() {
+        let def = parse_ksolve_definition(&DEFINITION.replace(
+            "\nMove A",
+            "
+Ignore
+CORNERS
+0 1 1
+1 0 0
+End
+
+Move A",
+        ))
+        .unwrap();
+        let corners = &def.default_pattern[&orbit("CORNERS")];
+        // Ignored pieces are made identical, and ignored orientations become wildcards.
+        assert_eq!(corners.pieces, vec![0, 1, 1]);
+        assert_eq!(corners.orientation, vec![0, 0, 0]);
+        assert_eq!(corners.orientation_mod, Some(vec![1, 0, 0]));
+
+        let def =
+            parse_ksolve_definition(&DEFINITION.replace("1 2 3\n0 0 0", "1 2 3\n? 0 2")).unwrap();
+        let corners = &def.default_pattern[&orbit("CORNERS")];
+        assert_eq!(corners.orientation, vec![0, 0, 2]);
+        assert_eq!(corners.orientation_mod, Some(vec![1, 0, 0]));
+
+        assert_eq!(
+            error_description(&DEFINITION.replace("2 3 1\n1 2 0", "2 3 1\n? 2 0")),
+            "Line 18: Moves cannot use `?`."
+        );
+    }
+
+    #[test]
+    fn ksolve_scrambles_test() {
+        let kpuzzle = KPuzzle::try_from(parse_ksolve_definition(DEFINITION).unwrap()).unwrap();
+        let scrambles = parse_ksolve_scrambles(
+            &kpuzzle,
+            "
+# One-indexed
+Scramble one
+CORNERS
+2 3 1
+End
+
+ScrambleState zero
+CORNERS
+1 2 0
+EDGES
+1 0
+1 1
+End
+
+ScrambleAlg alg
+A
+B
+End
+",
+        )
+        .unwrap();
+        let names: Vec<&str> = scrambles
+            .iter()
+            .map(|scramble| scramble.name.as_str())
+            .collect();
+        assert_eq!(names, ["one", "zero", "alg"]);
+
+        // Sets that are not specified keep their solved state.
+        let mut expected_data = kpuzzle.default_pattern().to_data();
+        expected_data.get_mut(&orbit("CORNERS")).unwrap().pieces = vec![1, 2, 0];
+        let expected_pattern = KPattern::try_from_data(&kpuzzle, &expected_data).unwrap();
+        assert_eq!(scrambles[0].pattern, expected_pattern);
+
+        let expected_data = {
+            let mut data = expected_data.clone();
+            let edges = data.get_mut(&orbit("EDGES")).unwrap();
+            edges.pieces = vec![1, 0];
+            edges.orientation = vec![1, 1];
+            data
+        };
+        assert_eq!(
+            scrambles[1].pattern,
+            KPattern::try_from_data(&kpuzzle, &expected_data).unwrap()
+        );
+        assert_eq!(
+            scrambles[2].pattern,
+            kpuzzle
+                .default_pattern()
+                .apply_alg(&"A B".parse::<Alg>().unwrap())
+                .unwrap()
+        );
+
+        assert_eq!(
+            parse_ksolve_scrambles(&kpuzzle, "Scramble one\nCORNERS\n2 3 1\n")
+                .unwrap_err()
+                .description,
+            "Line 3: Missing `End`."
+        );
+    }
+
+    #[test]
+    fn ksolve_definition_round_trip_test() {
+        let with_ignore_and_wildcard = DEFINITION.replace("1 2 3\n0 0 0", "1 2 3\n? 0 2").replace(
+            "\nMove A",
+            "
+Ignore
+EDGES
+1 1
+End
+
+Move A",
+        );
+        for input in [DEFINITION, &with_ignore_and_wildcard] {
+            let def = parse_ksolve_definition(input).unwrap();
+            let written = ksolve_definition_string(&def).unwrap();
+            assert_definitions_equal(&parse_ksolve_definition(&written).unwrap(), &def);
+        }
+
+        let def = parse_ksolve_definition(&with_ignore_and_wildcard).unwrap();
+        assert_eq!(
+            def.default_pattern[&orbit("CORNERS")].orientation_mod,
+            Some(vec![1, 0, 0])
+        );
+        assert_eq!(def.default_pattern[&orbit("EDGES")].pieces, vec![0, 0]);
+        let written = ksolve_definition_string(&def).unwrap();
+        assert!(written.contains("StartState\nCORNERS\n0 1 2\n? 0 2\nEDGES\n0 0\n0 0\nEnd\n"));
+        // The one-indexed `Move A` (`2 3 1` with orientation changes `1 2 0` by
+        // the position each piece moves from) is written zero-indexed, with
+        // orientation changes listed by position.
+        assert!(
+            written.contains("MoveTransformation A\nCORNERS\n1 2 0\n2 0 1\nEDGES\n0 1\n0 0\nEnd\n")
+        );
+        assert!(written.contains("MoveSequence C A\nMoveSequence D A B\n"));
+    }
+
+    #[test]
+    fn ksolve_scramble_round_trip_test() {
+        let kpuzzle = KPuzzle::try_from(parse_ksolve_definition(DEFINITION).unwrap()).unwrap();
+        let pattern = kpuzzle
+            .default_pattern()
+            .apply_alg(&"A B A".parse::<Alg>().unwrap())
+            .unwrap();
+        let written = ksolve_scramble_string(&kpuzzle, "ABA", &pattern).unwrap();
+        let scrambles = parse_ksolve_scrambles(&kpuzzle, &written).unwrap();
+        assert_eq!(scrambles.len(), 1);
+        assert_eq!(scrambles[0].name, "ABA");
+        assert_eq!(scrambles[0].pattern, pattern);
+
+        // Orientation wildcards are written as `?`.
+        let mut data = pattern.to_data();
+        let corners = data.get_mut(&orbit("CORNERS")).unwrap();
+        corners.orientation[1] = 0;
+        corners.orientation_mod = Some(vec![0, 1, 0]);
+        let pattern = KPattern::try_from_data(&kpuzzle, &data).unwrap();
+        let written = ksolve_scramble_string(&kpuzzle, "wildcard", &pattern).unwrap();
+        assert!(written.contains(" ? "));
+        assert_eq!(
+            parse_ksolve_scrambles(&kpuzzle, &written).unwrap()[0]
+                .pattern
+                .to_data(),
+            pattern.to_data()
+        );
+    }
+
+    #[test]
+    fn ksolve_errors_test() {
+        assert_eq!(
+            error_description(&DEFINITION.replace("\nEDGES\n1 2\n", "\nFACES\n1 2\n")),
+            "Line 11: Unknown set name: FACES"
+        );
+        assert_eq!(
+            error_description(&DEFINITION.replace("1 2 3\n0 0 0", "1 2 4\n0 0 0")),
+            "Line 9: Invalid value: 4"
+        );
+        assert_eq!(
+            error_description(&DEFINITION.replace("1 2 3\n0 0 0", "1 2\n0 0 0")),
+            "Line 9: Expected 3 numbers for set `CORNERS` but found 2."
+        );
+        assert_eq!(
+            error_description(&DEFINITION.replace("2 3 1\n1 2 0", "2 2 1\n1 2 0")),
+            "Line 17: Move permutations must contain each position exactly once."
+        );
+        assert_eq!(
+            error_description(&DEFINITION.replace("MoveTransformation B", "MoveTransformation A")),
+            "Line 21: Move defined twice: A"
+        );
+        assert_eq!(
+            error_description("Set CORNERS 3 3\n"),
+            "The definition does not have a solved state."
+        );
+        assert_eq!(
+            error_description("Name Test\nFoo\n"),
+            "Line 2: Unsupported block: Foo"
+        );
+    }
+}
@@ -1,6 +1,12 @@
 pub mod canonical_fsm;
 pub mod cli;
+pub mod cycle_notation;
 pub mod errors;
+pub mod facelets;
 pub mod gods_algorithm;
+pub mod ksolve;
 pub mod puzzle_traits;
+pub mod reachability;
+pub mod schreier_sims;
 pub mod search;
+pub mod timing_test;
@@ -61,6 +61,23 @@ pub trait SemiGroupActionPuzzle: Debug + Clone {
         transformation_to_apply: &Self::Transformation,
         into_pattern: &mut Self::Pattern,
     ) -> bool;
+
+    /********* Functions "defined on the transformation". ********/
+
+    /// Used to find relations between move sequences for the canonical FSM.
+    /// Puzzles that return `None` (the default) only support pruning based on pairs of move classes.
+    fn transformation_apply_transformation(
+        &self,
+        _transformation: &Self::Transformation,
+        _transformation_to_apply: &Self::Transformation,
+    ) -> Option<Self::Transformation> {
+        None
+    }
+
+    /// Must return `Some(…)` if `.transformation_apply_transformation(…)` does.
+    fn transformation_hash_u64(&self, _transformation: &Self::Transformation) -> Option<u64> {
+        None
+    }
 }
 
 pub trait GroupActionPuzzle: SemiGroupActionPuzzle {
@@ -68,6 +68,19 @@ impl SemiGroupActionPuzzle for KPuzzle {
         pattern.apply_transformation_into(transformation_to_apply, into_pattern);
         true
     }
+
+    fn transformation_apply_transformation(
+        &self,
+        transformation: &Self::Transformation,
+        transformation_to_apply: &Self::Transformation,
+    ) -> Option<Self::Transformation> {
+        Some(transformation.apply_transformation(transformation_to_apply))
+    }
+
+    fn transformation_hash_u64(&self, transformation: &Self::Transformation) -> Option<u64> {
+        let h = cityhasher::CityHasher::new();
+        Some(h.hash_one(unsafe { transformation.byte_slice() }))
+    }
 }
 
 impl HashablePatternPuzzle for KPuzzle {
@@ -0,0 +1,382 @@
+use cubing::{
+    alg::Move,
+    kpuzzle::{KPattern, KPuzzle, KPuzzleOrbitInfo, KTransformation},
+};
+
+use super::{
+    cli::args::EnableAutoAlwaysNeverValueEnum,
+    cycle_notation::gcd,
+    errors::{ArgumentError, SearchError},
+    schreier_sims::stabilizer_chain::StabilizerChain,
+};
+
+// Returns `None` if the orbit has an orientation wildcard at any position.
+fn orientation_sum(pattern: &KPattern, orbit_info: &KPuzzleOrbitInfo) -> Option<u32> {
+    let mut sum = 0;
+    for i in 0..orbit_info.num_pieces {
+        let orientation_with_mod = pattern.get_orientation_with_mod(orbit_info, i);
+        if orientation_with_mod.orientation_mod != 0
+            && orientation_with_mod.orientation_mod != orbit_info.num_orientations
+        {
+            return None;
+        }
+        sum += orientation_with_mod.orientation as u32;
+    }
+    Some(sum % orbit_info.num_orientations as u32)
+}
+
+fn has_distinct_pieces(pattern: &KPattern, orbit_info: &KPuzzleOrbitInfo) -> bool {
+    let mut seen = vec![false; orbit_info.num_pieces as usize];
+    (0..orbit_info.num_pieces).all(|i| {
+        let piece = pattern.get_piece(orbit_info, i) as usize;
+        piece < seen.len() && !std::mem::replace(&mut seen[piece], true)
+    })
+}
+
+// Returns `true` if the permutation (where `perm[i]` is the image of `i`) is odd.
+pub(crate) fn is_odd_permutation(perm: &[usize]) -> bool {
+    let mut visited = vec![false; perm.len()];
+    let mut num_even_length_cycles = 0;
+    for start in 0..perm.len() {
+        let mut length = 0;
+        let mut i = start;
+        while !visited[i] {
+            visited[i] = true;
+            i = perm[i];
+            length += 1;
+        }
+        if length > 0 && length % 2 == 0 {
+            num_even_length_cycles += 1;
+        }
+    }
+    num_even_length_cycles % 2 == 1
+}
+
+fn join_orbit_names(names: &[&str]) -> String {
+    match names {
+        [] => "".to_owned(),
+        [name] => name.to_string(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
+
+// Finds a vector `w` with `row · w = 0` for every row, but `v · w = 1`. Such a
+// vector exists exactly when `v` is not in the span of the rows (over GF(2)).
+fn find_violated_relation(rows: &[Vec<bool>], v: &[bool]) -> Option<Vec<bool>> {
+    let num_columns = v.len();
+    // Reduced row echelon form.
+    let mut rows: Vec<Vec<bool>> = rows.to_vec();
+    let mut pivot_columns = vec![];
+    let mut rank = 0;
+    for column in 0..num_columns {
+        let Some(pivot) = (rank..rows.len()).find(|&r| rows[r][column]) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        let pivot_row = rows[rank].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != rank && row[column] {
+                for (entry, pivot_entry) in row.iter_mut().zip(&pivot_row) {
+                    *entry ^= pivot_entry;
+                }
+            }
+        }
+        pivot_columns.push(column);
+        rank += 1;
+    }
+    // Each free column gives a basis vector of the null space.
+    for free_column in (0..num_columns).filter(|column| !pivot_columns.contains(column)) {
+        let mut w = vec![false; num_columns];
+        w[free_column] = true;
+        for (r, &pivot_column) in pivot_columns.iter().enumerate() {
+            w[pivot_column] = rows[r][free_column];
+        }
+        if v.iter().zip(&w).filter(|(a, b)| **a && **b).count() % 2 == 1 {
+            return Some(w);
+        }
+    }
+    None
+}
+
+/// Checks whether a pattern can be solved before searching for it, so that
+/// unsolvable patterns result in an error instead of a search that never finishes.
+///
+/// The orbit invariants (matching pieces, orientation sums, and permutation
+/// parities) are cheap to check and catch most invalid patterns. Optionally,
+/// a stabilizer chain can be used to check membership in the group generated
+/// by the moves. This only considers orbits that have distinct pieces and no
+/// orientation wildcards in the default pattern (e.g. it ignores the centers
+/// of a 4x4x4).
+pub struct ReachabilityChecker {
+    kpuzzle: KPuzzle,
+    // Indexed by generator, then by orbit.
+    generator_parities: Vec<Vec<bool>>,
+    // The orientation sums that the generators can change each orbit by are the multiples of this.
+    orientation_sum_gcds: Vec<u32>,
+    // The chain only includes the orbits (by index) in `stabilizer_chain_orbit_indices`.
+    stabilizer_chain: Option<StabilizerChain>,
+    stabilizer_chain_orbit_indices: Vec<usize>,
+}
+
+impl ReachabilityChecker {
+    pub fn new(
+        kpuzzle: &KPuzzle,
+        generators: &[KTransformation],
+        use_stabilizer_chain: bool,
+    ) -> Self {
+        let orbit_infos = &kpuzzle.data.ordered_orbit_info;
+        let generator_parities = generators
+            .iter()
+            .map(|generator| {
+                orbit_infos
+                    .iter()
+                    .map(|orbit_info| {
+                        let perm: Vec<usize> = (0..orbit_info.num_pieces)
+                            .map(|i| generator.get_permutation_idx(orbit_info, i) as usize)
+                            .collect();
+                        is_odd_permutation(&perm)
+                    })
+                    .collect()
+            })
+            .collect();
+        let orientation_sum_gcds = orbit_infos
+            .iter()
+            .map(|orbit_info| {
+                let num_orientations = orbit_info.num_orientations as u32;
+                generators.iter().fold(num_orientations, |acc, generator| {
+                    let sum: u32 = (0..orbit_info.num_pieces)
+                        .map(|i| generator.get_orientation_delta(orbit_info, i) as u32)
+                        .sum();
+                    gcd(acc as u64, (sum % num_orientations) as u64) as u32
+                })
+            })
+            .collect();
+        let default_pattern = kpuzzle.default_pattern();
+        let stabilizer_chain_orbit_indices: Vec<usize> = orbit_infos
+            .iter()
+            .enumerate()
+            .filter(|(_, orbit_info)| {
+                has_distinct_pieces(&default_pattern, orbit_info)
+                    && orientation_sum(&default_pattern, orbit_info).is_some()
+            })
+            .map(|(orbit_index, _)| orbit_index)
+            .collect();
+        Self {
+            kpuzzle: kpuzzle.clone(),
+            generator_parities,
+            orientation_sum_gcds,
+            stabilizer_chain: (use_stabilizer_chain && !stabilizer_chain_orbit_indices.is_empty())
+                .then(|| {
+                    StabilizerChain::new_for_orbits(
+                        kpuzzle,
+                        generators,
+                        &stabilizer_chain_orbit_indices,
+                    )
+                }),
+            stabilizer_chain_orbit_indices,
+        }
+    }
+
+    /// Constructs a checker for the `--check-before-solve` option (`None` means
+    /// `auto`). The orbit invariants are checked unless the option is `never`,
+    /// and the (potentially expensive) stabilizer chain is only used for `always`.
+    pub fn try_from_check_before_solve(
+        kpuzzle: &KPuzzle,
+        generator_moves: &[Move],
+        check_before_solve: Option<&EnableAutoAlwaysNeverValueEnum>,
+    ) -> Result<Option<Self>, ArgumentError> {
+        let use_stabilizer_chain = match check_before_solve {
+            Some(EnableAutoAlwaysNeverValueEnum::Never) => return Ok(None),
+            None | Some(EnableAutoAlwaysNeverValueEnum::Auto) => false,
+            Some(EnableAutoAlwaysNeverValueEnum::Always) => true,
+        };
+        let generators = generator_moves
+            .iter()
+            .map(|r#move| kpuzzle.transformation_from_move(r#move))
+            .collect::<Result<Vec<KTransformation>, _>>()
+            .map_err(|e| ArgumentError {
+                description: e.to_string(),
+            })?;
+        Ok(Some(Self::new(kpuzzle, &generators, use_stabilizer_chain)))
+    }
+
+    /// Whether [`ReachabilityChecker::check`] is exact (i.e. never accepts an
+    /// unreachable pattern) for target patterns with distinct pieces and no
+    /// orientation wildcards. This requires the stabilizer chain to cover every orbit.
+    pub fn is_exact(&self) -> bool {
+        self.stabilizer_chain.is_some()
+            && self.stabilizer_chain_orbit_indices.len()
+                == self.kpuzzle.data.ordered_orbit_info.len()
+    }
+
+    /// Returns an error describing the first violated invariant if `pattern` cannot reach `target_pattern`.
+    pub fn check(&self, pattern: &KPattern, target_pattern: &KPattern) -> Result<(), SearchError> {
+        let orbit_infos = &self.kpuzzle.data.ordered_orbit_info;
+
+        for orbit_info in orbit_infos {
+            let mut piece_counts = vec![0i32; orbit_info.num_pieces as usize];
+            for i in 0..orbit_info.num_pieces {
+                piece_counts[pattern.get_piece(orbit_info, i) as usize] += 1;
+                piece_counts[target_pattern.get_piece(orbit_info, i) as usize] -= 1;
+            }
+            if let Some(piece) = piece_counts.iter().position(|count| *count != 0) {
+                return Err(SearchError {
+                    description: format!(
+                        "The pieces of orbit {} do not match the target pattern: piece {} appears {} times instead of {}.",
+                        orbit_info.name,
+                        piece,
+                        (0..orbit_info.num_pieces)
+                            .filter(|&i| pattern.get_piece(orbit_info, i) as usize == piece)
+                            .count(),
+                        (0..orbit_info.num_pieces)
+                            .filter(|&i| target_pattern.get_piece(orbit_info, i) as usize == piece)
+                            .count(),
+                    ),
+                });
+            }
+        }
+
+        for (orbit_info, orientation_sum_gcd) in orbit_infos.iter().zip(&self.orientation_sum_gcds)
+        {
+            if *orientation_sum_gcd == 1 {
+                continue;
+            }
+            let (Some(sum), Some(target_sum)) = (
+                orientation_sum(pattern, orbit_info),
+                orientation_sum(target_pattern, orbit_info),
+            ) else {
+                continue;
+            };
+            if sum % orientation_sum_gcd != target_sum % orientation_sum_gcd {
+                return Err(SearchError {
+                    description: format!(
+                        "The orientation sum of orbit {} is {} mod {} (expected {} mod {}).",
+                        orbit_info.name,
+                        sum % orientation_sum_gcd,
+                        orientation_sum_gcd,
+                        target_sum % orientation_sum_gcd,
+                        orientation_sum_gcd
+                    ),
+                });
+            }
+        }
+
+        // Parity is only observable for orbits with distinct pieces.
+        let parity_orbits: Vec<(usize, &KPuzzleOrbitInfo)> = orbit_infos
+            .iter()
+            .enumerate()
+            .filter(|(_, orbit_info)| has_distinct_pieces(target_pattern, orbit_info))
+            .collect();
+        let relative_parities: Vec<bool> = parity_orbits
+            .iter()
+            .map(|(_, orbit_info)| {
+                let mut target_positions = vec![0; orbit_info.num_pieces as usize];
+                for i in 0..orbit_info.num_pieces {
+                    target_positions[target_pattern.get_piece(orbit_info, i) as usize] = i as usize;
+                }
+                let perm: Vec<usize> = (0..orbit_info.num_pieces)
+                    .map(|i| target_positions[pattern.get_piece(orbit_info, i) as usize])
+                    .collect();
+                is_odd_permutation(&perm)
+            })
+            .collect();
+        let generator_rows: Vec<Vec<bool>> = self
+            .generator_parities
+            .iter()
+            .map(|parities| {
+                parity_orbits
+                    .iter()
+                    .map(|(orbit_index, _)| parities[*orbit_index])
+                    .collect()
+            })
+            .collect();
+        if let Some(relation) = find_violated_relation(&generator_rows, &relative_parities) {
+            let names: Vec<&str> = parity_orbits
+                .iter()
+                .zip(&relation)
+                .filter(|(_, in_relation)| **in_relation)
+                .map(|((_, orbit_info), _)| orbit_info.name.0.as_str())
+                .collect();
+            let parity_name = |odd: bool| if odd { "odd" } else { "even" };
+            let description = match names.as_slice() {
+                [name] => format!(
+                    "The permutation of orbit {} is odd (relative to the target pattern), but every move is an even permutation of it.",
+                    name
+                ),
+                [first, second] => {
+                    let parity_of = |name: &str| {
+                        let index = parity_orbits
+                            .iter()
+                            .position(|(_, orbit_info)| orbit_info.name.0 == name)
+                            .unwrap();
+                        parity_name(relative_parities[index])
+                    };
+                    format!(
+                        "The permutation parities of orbits {} and {} must match (relative to the target pattern), but {} is {} and {} is {}.",
+                        first,
+                        second,
+                        first,
+                        parity_of(first),
+                        second,
+                        parity_of(second)
+                    )
+                }
+                _ => format!(
+                    "The combined permutation parity of orbits {} is odd (relative to the target pattern), but every move changes it by an even amount.",
+                    join_orbit_names(&names)
+                ),
+            };
+            return Err(SearchError { description });
+        }
+
+        if let Some(stabilizer_chain) = &self.stabilizer_chain {
+            if let Some(transformation) = self.transformation_to_target(pattern, target_pattern) {
+                if !stabilizer_chain.contains(&transformation) {
+                    return Err(
+                        "The pattern cannot reach the target pattern using the search moves (checked using a stabilizer chain).".into(),
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // The transformation `t` with `pattern · t = target_pattern` (restricted to
+    // the orbits of the stabilizer chain), if it is unique (i.e. those orbits have
+    // distinct pieces and no orientation wildcards in both patterns).
+    fn transformation_to_target(
+        &self,
+        pattern: &KPattern,
+        target_pattern: &KPattern,
+    ) -> Option<KTransformation> {
+        let mut transformation = self.kpuzzle.identity_transformation();
+        for &orbit_index in &self.stabilizer_chain_orbit_indices {
+            let orbit_info = &self.kpuzzle.data.ordered_orbit_info[orbit_index];
+            if !has_distinct_pieces(target_pattern, orbit_info) {
+                return None;
+            }
+            orientation_sum(pattern, orbit_info)?;
+            orientation_sum(target_pattern, orbit_info)?;
+            let mut positions = vec![0; orbit_info.num_pieces as usize];
+            for i in 0..orbit_info.num_pieces {
+                positions[pattern.get_piece(orbit_info, i) as usize] = i;
+            }
+            for i in 0..orbit_info.num_pieces {
+                let from = positions[target_pattern.get_piece(orbit_info, i) as usize];
+                transformation.set_permutation_idx(orbit_info, i, from);
+                let orientation = |pattern: &KPattern, i| {
+                    pattern.get_orientation_with_mod(orbit_info, i).orientation
+                };
+                transformation.set_orientation_delta(
+                    orbit_info,
+                    i,
+                    (orientation(target_pattern, i) + orbit_info.num_orientations
+                        - orientation(pattern, from))
+                        % orbit_info.num_orientations,
+                );
+            }
+        }
+        Some(transformation)
+    }
+}
@@ -0,0 +1,102 @@
+use cubing::kpuzzle::{KPattern, KPuzzle, KTransformation};
+use thousands::Separable;
+
+use crate::_internal::gods_algorithm::factor_number::Factorization;
+
+use super::stabilizer_chain::StabilizerChain;
+
+pub struct OrbitGroupOrder {
+    pub orbit_name: String,
+    pub order: Factorization,
+    pub num_patterns: Factorization,
+}
+
+/// The order of the group generated by a set of transformations, in total and restricted to each orbit.
+///
+/// If the start pattern has identical pieces or orientation wildcards, some
+/// group elements result in the same pattern. `num_patterns` accounts for
+/// this by dividing the group order by the order of the stabilizer of the
+/// start pattern.
+pub struct GroupOrder {
+    pub total: Factorization,
+    pub num_patterns: Factorization,
+    pub orbits: Vec<OrbitGroupOrder>,
+}
+
+fn order_and_num_patterns(
+    stabilizer_chain: StabilizerChain,
+    start_pattern: &KPattern,
+) -> (Factorization, Factorization) {
+    let order = stabilizer_chain.order();
+    let mut num_patterns = order.clone();
+    num_patterns.divide_by(&stabilizer_chain.pattern_stabilizer_order(start_pattern));
+    (order, num_patterns)
+}
+
+fn format_order(order: &Factorization) -> String {
+    format!(
+        "{} ({})",
+        order.to_decimal_string().separate_with_underscores(),
+        order
+    )
+}
+
+fn format_line(name: &str, order: &Factorization, num_patterns: &Factorization) -> String {
+    if order == num_patterns {
+        format!("{}: {}", name, format_order(order))
+    } else {
+        format!(
+            "{}: {} patterns (group order: {})",
+            name,
+            format_order(num_patterns),
+            format_order(order)
+        )
+    }
+}
+
+impl GroupOrder {
+    pub fn new(
+        kpuzzle: &KPuzzle,
+        generators: &[KTransformation],
+        start_pattern: &KPattern,
+    ) -> Self {
+        let orbits = kpuzzle
+            .orbit_info_iter()
+            .enumerate()
+            .map(|(orbit_index, orbit_info)| {
+                let (order, num_patterns) = order_and_num_patterns(
+                    StabilizerChain::new_for_orbits(kpuzzle, generators, &[orbit_index]),
+                    start_pattern,
+                );
+                OrbitGroupOrder {
+                    orbit_name: orbit_info.name.to_string(),
+                    order,
+                    num_patterns,
+                }
+            })
+            .collect();
+        let (total, num_patterns) =
+            order_and_num_patterns(StabilizerChain::new(kpuzzle, generators), start_pattern);
+        Self {
+            total,
+            num_patterns,
+            orbits,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let mut lines: Vec<String> = self
+            .orbits
+            .iter()
+            .map(|orbit_group_order| {
+                format_line(
+                    &orbit_group_order.orbit_name,
+                    &orbit_group_order.order,
+                    &orbit_group_order.num_patterns,
+                )
+            })
+            .collect();
+        lines.push(format_line("Total", &self.total, &self.num_patterns));
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,2 @@
+pub mod group_order;
+pub mod stabilizer_chain;
@@ -0,0 +1,585 @@
+use std::collections::{BTreeMap, HashMap};
+
+use cubing::kpuzzle::{KPattern, KPuzzle, KTransformation};
+use rand::{seq::IteratorRandom, Rng};
+
+use crate::_internal::gods_algorithm::factor_number::Factorization;
+
+/// A permutation of points, where `perm[x]` is the image of `x`.
+type Perm = Vec<u32>;
+
+fn compose(first: &Perm, second: &Perm) -> Perm {
+    first.iter().map(|&x| second[x as usize]).collect()
+}
+
+fn invert(perm: &Perm) -> Perm {
+    let mut inverse = vec![0; perm.len()];
+    for (i, &x) in perm.iter().enumerate() {
+        inverse[x as usize] = i as u32;
+    }
+    inverse
+}
+
+fn is_identity(perm: &Perm) -> bool {
+    perm.iter().enumerate().all(|(i, &x)| i as u32 == x)
+}
+
+#[derive(Clone)]
+struct OrbitPoints {
+    orbit_index: usize,
+    offset: usize,
+}
+
+/// Maps each (position, orientation) pair of the included orbits to a point,
+/// so that transformations act on points as plain permutations.
+#[derive(Clone)]
+struct PointLayout {
+    kpuzzle: KPuzzle,
+    orbits: Vec<OrbitPoints>,
+    num_points: usize,
+}
+
+impl PointLayout {
+    fn new(kpuzzle: &KPuzzle, orbit_indices: &[usize]) -> Self {
+        let mut num_points = 0;
+        let mut orbits = vec![];
+        for &orbit_index in orbit_indices {
+            let orbit_info = &kpuzzle.data.ordered_orbit_info[orbit_index];
+            orbits.push(OrbitPoints {
+                orbit_index,
+                offset: num_points,
+            });
+            num_points += orbit_info.num_pieces as usize * orbit_info.num_orientations as usize;
+        }
+        Self {
+            kpuzzle: kpuzzle.clone(),
+            orbits,
+            num_points,
+        }
+    }
+
+    // The piece at position `perm[i]` moves to position `i` (with its orientation increased by `delta[i]`).
+    fn perm_from_transformation(&self, transformation: &KTransformation) -> Perm {
+        let mut perm = vec![0; self.num_points];
+        for orbit_points in &self.orbits {
+            let orbit_info = &self.kpuzzle.data.ordered_orbit_info[orbit_points.orbit_index];
+            let num_orientations = orbit_info.num_orientations as usize;
+            for i in 0..orbit_info.num_pieces {
+                let from = transformation.get_permutation_idx(orbit_info, i) as usize;
+                let delta = transformation.get_orientation_delta(orbit_info, i) as usize;
+                for orientation in 0..num_orientations {
+                    perm[orbit_points.offset + from * num_orientations + orientation] =
+                        (orbit_points.offset
+                            + i as usize * num_orientations
+                            + (orientation + delta) % num_orientations)
+                            as u32;
+                }
+            }
+        }
+        perm
+    }
+
+    // Inverse of `perm_from_transformation()`. Orbits that are not included are left unchanged.
+    fn transformation_from_perm(&self, perm: &Perm) -> KTransformation {
+        let mut transformation = self.kpuzzle.identity_transformation();
+        for orbit_points in &self.orbits {
+            let orbit_info = &self.kpuzzle.data.ordered_orbit_info[orbit_points.orbit_index];
+            let num_orientations = orbit_info.num_orientations as usize;
+            for from in 0..orbit_info.num_pieces {
+                let image = perm[orbit_points.offset + from as usize * num_orientations] as usize
+                    - orbit_points.offset;
+                let i = (image / num_orientations) as u8;
+                transformation.set_permutation_idx(orbit_info, i, from);
+                transformation.set_orientation_delta(
+                    orbit_info,
+                    i,
+                    (image % num_orientations) as u8,
+                );
+            }
+        }
+        transformation
+    }
+
+    // Two points have the same color if the pattern cannot distinguish a piece
+    // on one from a piece on the other. A transformation leaves the pattern unchanged
+    // exactly when it maps every point to a point of the same color.
+    fn point_colors(&self, pattern: &KPattern) -> Vec<u32> {
+        let mut colors = vec![0; self.num_points];
+        for orbit_points in &self.orbits {
+            let orbit_info = &self.kpuzzle.data.ordered_orbit_info[orbit_points.orbit_index];
+            let num_orientations = orbit_info.num_orientations as usize;
+            for i in 0..orbit_info.num_pieces {
+                let piece = pattern.get_piece(orbit_info, i) as u32;
+                let orientation_with_mod = pattern.get_orientation_with_mod(orbit_info, i);
+                let orientation_mod = match orientation_with_mod.orientation_mod {
+                    0 => orbit_info.num_orientations,
+                    orientation_mod => orientation_mod,
+                } as usize;
+                for orientation in 0..num_orientations {
+                    // The default pattern has each piece at orientation 0, so the color
+                    // only depends on the orientation relative to that.
+                    let relative_orientation = (orientation + num_orientations
+                        - orientation_with_mod.orientation as usize)
+                        % orientation_mod;
+                    colors[orbit_points.offset + i as usize * num_orientations + orientation] =
+                        ((orbit_points.orbit_index as u32) << 24)
+                            | (piece << 16)
+                            | ((orientation_mod as u32) << 8)
+                            | relative_orientation as u32;
+                }
+            }
+        }
+        colors
+    }
+}
+
+struct Level {
+    base_point: usize,
+    generators: Vec<Perm>,
+    // Indexed by the image of the base point. Each entry stores a coset representative and its inverse.
+    transversal: BTreeMap<usize, (Perm, Perm)>,
+}
+
+// Calls `f` with `perm` composed with each product of one coset representative per level.
+fn for_each_perm(levels: &[Level], perm: Perm, f: &mut dyn FnMut(&Perm)) {
+    match levels.split_last() {
+        None => f(&perm),
+        Some((level, remaining_levels)) => {
+            for (representative, _) in level.transversal.values() {
+                for_each_perm(remaining_levels, compose(&perm, representative), f);
+            }
+        }
+    }
+}
+
+/// A stabilizer chain for the group generated by a set of transformations,
+/// built using Knuth's variant of the Schreier–Sims algorithm.
+///
+/// Level `k` represents the subgroup that fixes the base points of the
+/// levels before it, and the transversal at level `k` contains one
+/// representative for each image of its own base point under that subgroup.
+/// The group order is the product of the transversal sizes.
+///
+/// The base is chosen adaptively: a level is only added when an element
+/// that fixes all current base points is found, using the first point (in a
+/// preferred order) that the element moves. This means that every level has
+/// a non-trivial transversal, and that each level fixes all points that come
+/// before its base point in the preferred order.
+pub struct StabilizerChain {
+    layout: PointLayout,
+    // The position of each point in the preferred base order.
+    base_order_positions: Vec<usize>,
+    levels: Vec<Level>,
+}
+
+impl StabilizerChain {
+    /// Builds the chain for the group generated by `generators`, acting on all orbits of the puzzle.
+    pub fn new(kpuzzle: &KPuzzle, generators: &[KTransformation]) -> Self {
+        let orbit_indices: Vec<usize> = (0..kpuzzle.data.ordered_orbit_info.len()).collect();
+        Self::new_for_orbits(kpuzzle, generators, &orbit_indices)
+    }
+
+    /// Builds the chain for the group generated by `generators`, restricted to the given orbits (by index).
+    pub fn new_for_orbits(
+        kpuzzle: &KPuzzle,
+        generators: &[KTransformation],
+        orbit_indices: &[usize],
+    ) -> Self {
+        let layout = PointLayout::new(kpuzzle, orbit_indices);
+        let base_order: Vec<usize> = (0..layout.num_points).collect();
+        let perms: Vec<Perm> = generators
+            .iter()
+            .map(|generator| layout.perm_from_transformation(generator))
+            .collect();
+        Self::with_base_order(layout, &base_order, perms)
+    }
+
+    fn with_base_order(layout: PointLayout, base_order: &[usize], generators: Vec<Perm>) -> Self {
+        let mut base_order_positions = vec![0; layout.num_points];
+        for (position, &point) in base_order.iter().enumerate() {
+            base_order_positions[point] = position;
+        }
+        let mut stabilizer_chain = Self {
+            layout,
+            base_order_positions,
+            levels: vec![],
+        };
+        for perm in generators {
+            stabilizer_chain.add_generator(0, perm);
+        }
+        stabilizer_chain
+    }
+
+    /// Checks whether the transformation is an element of the group (restricted to the orbits of the chain).
+    pub fn contains(&self, transformation: &KTransformation) -> bool {
+        self.contains_from_level(0, &self.layout.perm_from_transformation(transformation))
+    }
+
+    fn contains_from_level(&self, level: usize, perm: &Perm) -> bool {
+        let mut perm = perm.clone();
+        for level in self.levels.iter().skip(level) {
+            if is_identity(&perm) {
+                return true;
+            }
+            match level.transversal.get(&(perm[level.base_point] as usize)) {
+                Some((_, representative_inverse)) => perm = compose(&perm, representative_inverse),
+                None => return false,
+            }
+        }
+        is_identity(&perm)
+    }
+
+    fn add_generator(&mut self, level: usize, perm: Perm) {
+        if self.contains_from_level(level, &perm) {
+            return;
+        }
+        let first_moved_point = (0..self.layout.num_points)
+            .filter(|&point| perm[point] as usize != point)
+            .min_by_key(|&point| self.base_order_positions[point])
+            .expect("Internal error: a non-identity element moves no points.");
+        if self.levels.get(level).is_none_or(|existing_level| {
+            self.base_order_positions[first_moved_point]
+                < self.base_order_positions[existing_level.base_point]
+        }) {
+            self.insert_level(level, first_moved_point);
+        }
+        self.levels[level].generators.push(perm.clone());
+        let representatives: Vec<Perm> = self.levels[level]
+            .transversal
+            .values()
+            .map(|(representative, _)| representative.clone())
+            .collect();
+        for representative in representatives {
+            self.extend_level(level, compose(&representative, &perm));
+        }
+    }
+
+    // Inserts a level in front of `level`. The existing level fixes the new base
+    // point, so its generators remain valid (and form the stabilizer of the new
+    // base point until more generators are added).
+    fn insert_level(&mut self, level: usize, base_point: usize) {
+        let identity: Perm = (0..self.layout.num_points as u32).collect();
+        let generators = match self.levels.get(level) {
+            Some(existing_level) => existing_level.generators.clone(),
+            None => vec![],
+        };
+        self.levels.insert(
+            level,
+            Level {
+                base_point,
+                generators,
+                transversal: BTreeMap::from([(base_point, (identity.clone(), identity))]),
+            },
+        );
+    }
+
+    fn extend_level(&mut self, level: usize, perm: Perm) {
+        let mut pending = vec![perm];
+        while let Some(perm) = pending.pop() {
+            let image = perm[self.levels[level].base_point] as usize;
+            match self.levels[level].transversal.get(&image) {
+                Some((_, representative_inverse)) => {
+                    // A Schreier generator for the next level.
+                    let schreier_generator = compose(&perm, representative_inverse);
+                    self.add_generator(level + 1, schreier_generator);
+                }
+                None => {
+                    for generator in &self.levels[level].generators {
+                        pending.push(compose(&perm, generator));
+                    }
+                    let inverse = invert(&perm);
+                    self.levels[level]
+                        .transversal
+                        .insert(image, (perm, inverse));
+                }
+            }
+        }
+    }
+
+    /// The sizes of the transversals, from the first level to the last.
+    pub fn transversal_sizes(&self) -> Vec<usize> {
+        self.levels
+            .iter()
+            .map(|level| level.transversal.len())
+            .collect()
+    }
+
+    pub fn order(&self) -> Factorization {
+        let mut order = Factorization::one();
+        for transversal_size in self.transversal_sizes() {
+            order.multiply_by(transversal_size as u64);
+        }
+        order
+    }
+
+    /// Returns a uniformly random element of the group, by choosing a random
+    /// coset representative at each level.
+    pub fn random_transformation<R: Rng>(&self, rng: &mut R) -> KTransformation {
+        let mut perm: Perm = (0..self.layout.num_points as u32).collect();
+        for level in self.levels.iter().rev() {
+            let (representative, _) = level
+                .transversal
+                .values()
+                .choose(rng)
+                .expect("Internal error: empty transversal.");
+            perm = compose(&perm, representative);
+        }
+        self.layout.transformation_from_perm(&perm)
+    }
+
+    /// Calls `f` with each element of the group, by combining one coset
+    /// representative from each level (so that nothing is stored per element).
+    pub fn for_each_transformation(&self, mut f: impl FnMut(&KTransformation)) {
+        let identity: Perm = (0..self.layout.num_points as u32).collect();
+        for_each_perm(&self.levels, identity, &mut |perm| {
+            f(&self.layout.transformation_from_perm(perm))
+        });
+    }
+
+    /// Calculates the order of the subgroup of transformations that leave the
+    /// given pattern unchanged (which is non-trivial if the pattern has
+    /// identical pieces or orientation wildcards).
+    ///
+    /// The subgroup is the intersection of the group with the permutations
+    /// that map each point to a point of the same color. To search it, the
+    /// chain is rebuilt with a base order that lists the points with a unique color
+    /// first (the subgroup fixes them), followed by one color class at a time.
+    pub fn pattern_stabilizer_order(&self, pattern: &KPattern) -> Factorization {
+        let colors = self.layout.point_colors(pattern);
+        let mut color_class_sizes = HashMap::<u32, usize>::new();
+        for color in &colors {
+            *color_class_sizes.entry(*color).or_default() += 1;
+        }
+        let mut base_order: Vec<usize> = (0..self.layout.num_points).collect();
+        base_order.sort_by_key(|&point| (color_class_sizes[&colors[point]], colors[point], point));
+        let generators = match self.levels.first() {
+            Some(level) => level.generators.clone(),
+            None => vec![],
+        };
+        Self::with_base_order(self.layout.clone(), &base_order, generators)
+            .color_preserving_order(&colors)
+    }
+
+    // Finds generators for the color-preserving subgroup level by level,
+    // starting at the last level. At each level, this searches for an element
+    // that maps the base point to each point of the same color that is not yet
+    // reachable using the generators found so far.
+    fn color_preserving_order(&self, colors: &[u32]) -> Factorization {
+        let color_refinement = ColorRefinement::new(self, colors);
+        let mut stabilizer_generators: Vec<Perm> = vec![];
+        let mut order = Factorization::one();
+        for (level_index, level) in self.levels.iter().enumerate().rev() {
+            let mut stabilizer_orbit = orbit_of_point(
+                level.base_point,
+                &stabilizer_generators,
+                self.layout.num_points,
+            );
+            for (&image, (representative, _)) in &level.transversal {
+                if stabilizer_orbit[image] || colors[image] != colors[level.base_point] {
+                    continue;
+                }
+                if let Some(perm) =
+                    self.search_color_preserving(level_index + 1, representative, &color_refinement)
+                {
+                    stabilizer_generators.push(perm);
+                    stabilizer_orbit = orbit_of_point(
+                        level.base_point,
+                        &stabilizer_generators,
+                        self.layout.num_points,
+                    );
+                }
+            }
+            order.multiply_by(
+                stabilizer_orbit
+                    .iter()
+                    .filter(|in_orbit| **in_orbit)
+                    .count() as u64,
+            );
+        }
+        order
+    }
+
+    // Searches for a color-preserving element of the form `(element of level `level`) · outer`.
+    fn search_color_preserving(
+        &self,
+        level: usize,
+        outer: &Perm,
+        color_refinement: &ColorRefinement,
+    ) -> Option<Perm> {
+        if !color_refinement.is_consistent(level, outer) {
+            return None;
+        }
+        if level == self.levels.len() {
+            return Some(outer.clone());
+        }
+        let base_point = self.levels[level].base_point;
+        for (representative, _) in self.levels[level].transversal.values() {
+            let candidate = compose(representative, outer);
+            if color_refinement.colors[candidate[base_point] as usize]
+                != color_refinement.colors[base_point]
+            {
+                continue;
+            }
+            if let Some(perm) =
+                self.search_color_preserving(level + 1, &candidate, color_refinement)
+            {
+                return Some(perm);
+            }
+        }
+        None
+    }
+}
+
+/// Prunes the search for color-preserving elements.
+///
+/// Any element of level `k` permutes each of its orbits, so an element of
+/// the form `(element of level k) · outer` can only preserve colors if
+/// `outer` maps each orbit to points with the same colors (counted with
+/// multiplicity). For the (trivial) group after the last level, every orbit
+/// is a single point and this is an exact check.
+struct ColorRefinement<'a> {
+    colors: &'a [u32],
+    // For each level (and one past the last level): the orbit index of each point.
+    orbit_indices: Vec<Vec<u32>>,
+    // For each level: the sorted `(orbit index, color)` pairs of all points.
+    orbit_colors: Vec<Vec<(u32, u32)>>,
+}
+
+impl<'a> ColorRefinement<'a> {
+    fn new(stabilizer_chain: &StabilizerChain, colors: &'a [u32]) -> Self {
+        let num_points = stabilizer_chain.layout.num_points;
+        let mut orbit_indices: Vec<Vec<u32>> = stabilizer_chain
+            .levels
+            .iter()
+            .map(|level| orbit_indices(&level.generators, num_points))
+            .collect();
+        orbit_indices.push((0..num_points as u32).collect());
+        let orbit_colors = orbit_indices
+            .iter()
+            .map(|orbit_indices| {
+                let mut orbit_colors: Vec<(u32, u32)> = orbit_indices
+                    .iter()
+                    .zip(colors)
+                    .map(|(orbit_index, color)| (*orbit_index, *color))
+                    .collect();
+                orbit_colors.sort();
+                orbit_colors
+            })
+            .collect();
+        Self {
+            colors,
+            orbit_indices,
+            orbit_colors,
+        }
+    }
+
+    fn is_consistent(&self, level: usize, outer: &Perm) -> bool {
+        let mut orbit_colors: Vec<(u32, u32)> = self.orbit_indices[level]
+            .iter()
+            .zip(outer)
+            .map(|(orbit_index, image)| (*orbit_index, self.colors[*image as usize]))
+            .collect();
+        orbit_colors.sort();
+        orbit_colors == self.orbit_colors[level]
+    }
+}
+
+fn orbit_of_point(point: usize, generators: &[Perm], num_points: usize) -> Vec<bool> {
+    let mut in_orbit = vec![false; num_points];
+    in_orbit[point] = true;
+    let mut pending = vec![point];
+    while let Some(point) = pending.pop() {
+        for generator in generators {
+            let image = generator[point] as usize;
+            if !in_orbit[image] {
+                in_orbit[image] = true;
+                pending.push(image);
+            }
+        }
+    }
+    in_orbit
+}
+
+// Labels each point with the index of its orbit under the group generated by `generators`.
+fn orbit_indices(generators: &[Perm], num_points: usize) -> Vec<u32> {
+    let mut orbit_indices = vec![u32::MAX; num_points];
+    let mut num_orbits = 0;
+    for start in 0..num_points {
+        if orbit_indices[start] != u32::MAX {
+            continue;
+        }
+        orbit_indices[start] = num_orbits;
+        let mut pending = vec![start];
+        while let Some(point) = pending.pop() {
+            for generator in generators {
+                let image = generator[point] as usize;
+                if orbit_indices[image] == u32::MAX {
+                    orbit_indices[image] = num_orbits;
+                    pending.push(image);
+                }
+            }
+        }
+        num_orbits += 1;
+    }
+    orbit_indices
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use cubing::{
+        kpuzzle::KTransformation,
+        puzzles::{cube2x2x2_kpuzzle, cube3x3x3_kpuzzle},
+    };
+
+    use super::StabilizerChain;
+
+    #[test]
+    fn adaptive_base_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let generators: Vec<KTransformation> = ["U", "L", "F", "R", "B", "D"]
+            .iter()
+            .map(|r#move| {
+                kpuzzle
+                    .transformation_from_move(&r#move.parse().unwrap())
+                    .unwrap()
+            })
+            .collect();
+        let stabilizer_chain = StabilizerChain::new(kpuzzle, &generators);
+        let transversal_sizes = stabilizer_chain.transversal_sizes();
+        // Levels with a trivial transversal are skipped. In particular, the
+        // orientation of the last edge and the last corner are determined by the
+        // rest.
+        assert!(transversal_sizes.iter().all(|size| *size > 1));
+        assert!(transversal_sizes.len() < stabilizer_chain.layout.num_points);
+        assert_eq!(
+            stabilizer_chain.order().to_decimal_string(),
+            // The definition includes center orientation: 43_252_003_274_489_856_000 × 4⁶ / 2
+            "88580102706155225088000"
+        );
+    }
+
+    #[test]
+    fn for_each_transformation_test() {
+        let kpuzzle = cube2x2x2_kpuzzle();
+        let generators: Vec<KTransformation> = ["R2", "U2"]
+            .iter()
+            .map(|r#move| {
+                kpuzzle
+                    .transformation_from_move(&r#move.parse().unwrap())
+                    .unwrap()
+            })
+            .collect();
+        let stabilizer_chain = StabilizerChain::new(kpuzzle, &generators);
+        let mut patterns = HashSet::new();
+        stabilizer_chain.for_each_transformation(|transformation| {
+            patterns.insert(
+                kpuzzle
+                    .default_pattern()
+                    .apply_transformation(transformation),
+            );
+        });
+        assert_eq!(patterns.len(), 6);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     collections::{HashMap, VecDeque},
-    fmt::Debug,
+    fmt::{Debug, Display},
     hash::Hash,
     marker::PhantomData,
     sync::Arc,
@@ -102,6 +102,22 @@ pub enum PhaseCoordinateConversionError {
     InvalidPhaseCoordinate,
 }
 
+impl Display for PhaseCoordinateConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhaseCoordinateConversionError::InvalidSemanticCoordinate => write!(
+                f,
+                "The pattern does not correspond to a valid semantic coordinate for this phase."
+            ),
+            PhaseCoordinateConversionError::InvalidPhaseCoordinate => {
+                write!(f, "The pattern is not in the phase coordinate table.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PhaseCoordinateConversionError {}
+
 impl<TPuzzle: SemiGroupActionPuzzle, TSemanticCoordinate: SemanticCoordinate<TPuzzle>>
     PhaseCoordinatePuzzle<TPuzzle, TSemanticCoordinate>
 where
@@ -373,6 +389,14 @@ impl<TPuzzle: SemiGroupActionPuzzle, TSemanticCoordinate: SemanticCoordinate<TPu
         *self.tpuzzle.data.exact_prune_table.at(*pattern)
     }
 
+    fn needs_extension_for_search_depth(
+        &self,
+        _search_depth: Depth,
+        _approximate_num_entries: usize,
+    ) -> bool {
+        false
+    }
+
     fn extend_for_search_depth(&mut self, _search_depth: Depth, _approximate_num_entries: usize) {
         // no-op
     }
@@ -420,6 +420,14 @@ impl<
         max(max(depth1, depth2), depth3)
     }
 
+    fn needs_extension_for_search_depth(
+        &self,
+        _search_depth: Depth,
+        _approximate_num_entries: usize,
+    ) -> bool {
+        false
+    }
+
     fn extend_for_search_depth(&mut self, _search_depth: Depth, _approximate_num_entries: usize) {
         // no-op
     }
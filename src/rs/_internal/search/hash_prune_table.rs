@@ -20,6 +20,7 @@ use super::search_logger::SearchLogger;
 whole_number_newtype!(DepthU8, u8);
 
 type PruneTableEntryType = DepthU8;
+pub(crate) const PRUNE_TABLE_ENTRY_NUM_BYTES: usize = std::mem::size_of::<PruneTableEntryType>();
 // 0 is uninitialized, all other values are stored as 1+depth.
 // This allows us to save initialization time by allowing table memory pages to start as "blank" (all 0).
 const UNINITIALIZED_SENTINEL: PruneTableEntryType = DepthU8(0);
@@ -90,6 +91,27 @@ impl<
         TPatternTraversalFilter: PatternTraversalFilter<TPuzzle>,
     > HashPruneTable<TPuzzle, TPatternTraversalFilter>
 {
+    /// Returns the pruning depth for the given search depth, and whether it had to be
+    /// limited to `MAX_PRUNE_TABLE_DEPTH`.
+    fn pruning_depth_for_search_depth(search_depth: Depth) -> (DepthU8, bool) {
+        let pruning_depth = DepthU8(
+            std::convert::TryInto::<u8>::try_into(search_depth.0 / 2)
+                .expect("Prune table depth exceeded available size"),
+        );
+        if pruning_depth > MAX_PRUNE_TABLE_DEPTH {
+            (MAX_PRUNE_TABLE_DEPTH, true)
+        } else {
+            (pruning_depth, false)
+        }
+    }
+
+    fn prune_table_size_for_num_entries(&self, approximate_num_entries: usize) -> usize {
+        usize::max(
+            usize::next_power_of_two(approximate_num_entries),
+            self.mutable.min_size,
+        )
+    }
+
     // TODO: dedup with IterativeDeepeningSearch?
     // TODO: Store a reference to `search_api_data` so that you can't accidentally pass in the wrong `search_api_data`?
     fn recurse(
@@ -187,25 +209,32 @@ impl<
         self.mutable.lookup(pattern)
     }
 
+    fn needs_extension_for_search_depth(
+        &self,
+        search_depth: Depth,
+        approximate_num_entries: usize,
+    ) -> bool {
+        let (new_pruning_depth, _) = Self::pruning_depth_for_search_depth(search_depth);
+        let new_prune_table_size = self.prune_table_size_for_num_entries(approximate_num_entries);
+        match new_prune_table_size.cmp(&self.mutable.prune_table_size) {
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal => new_pruning_depth > self.mutable.current_pruning_depth,
+            std::cmp::Ordering::Greater => true,
+        }
+    }
+
     // TODO: dedup with IterativeDeepeningSearch?
     // TODO: Store a reference to `search_api_data` so that you can't accidentally pass in the wrong `search_api_data`?
     fn extend_for_search_depth(&mut self, search_depth: Depth, approximate_num_entries: usize) {
-        let mut new_pruning_depth = DepthU8(
-            std::convert::TryInto::<u8>::try_into(search_depth.0 / 2)
-                .expect("Prune table depth exceeded available size"),
-        );
-        if new_pruning_depth > MAX_PRUNE_TABLE_DEPTH {
+        let (new_pruning_depth, limited) = Self::pruning_depth_for_search_depth(search_depth);
+        if limited {
             self.mutable.search_logger.write_warning(&format!(
                 "[Prune table] Exceeded max depth, limiting to {:?}.",
                 MAX_PRUNE_TABLE_DEPTH
             ));
-            new_pruning_depth = MAX_PRUNE_TABLE_DEPTH;
         }
 
-        let new_prune_table_size = usize::max(
-            usize::next_power_of_two(approximate_num_entries),
-            self.mutable.min_size,
-        );
+        let new_prune_table_size = self.prune_table_size_for_num_entries(approximate_num_entries);
         match new_prune_table_size.cmp(&self.mutable.prune_table_size) {
             std::cmp::Ordering::Less => {
                 // Don't shrink the prune table.
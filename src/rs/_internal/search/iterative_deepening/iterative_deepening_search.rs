@@ -2,8 +2,9 @@ use std::{
     fmt::Debug,
     sync::{
         mpsc::{channel, Receiver, Sender},
-        Arc,
+        Arc, RwLock,
     },
+    time::Duration,
 };
 
 use cubing::{
@@ -79,9 +80,19 @@ impl SolutionMoves<'_> {
     }
 }
 
+/// Statistics about the work done by a single search.
+#[derive(Clone, Debug, Default)]
+pub struct SearchStats {
+    pub num_recursive_calls: usize,
+    /// The deepest depth that was searched.
+    pub max_depth_searched: Option<usize>,
+    pub duration: Duration,
+}
+
 pub struct SearchSolutions {
     receiver: Receiver<Option<Alg>>,
     done: bool,
+    stats: SearchStats,
 }
 
 impl SearchSolutions {
@@ -93,9 +104,14 @@ impl SearchSolutions {
             Self {
                 receiver,
                 done: false,
+                stats: Default::default(),
             },
         )
     }
+
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
 }
 
 impl Iterator for SearchSolutions {
@@ -169,7 +185,8 @@ pub struct IterativeDeepeningSearch<
     >>::Adaptations,
 > {
     pub api_data: Arc<IterativeDeepeningSearchAPIData<TPuzzle>>,
-    pub prune_table: Adaptations::PruneTable, // TODO: push this into the associated data for the adaptations.
+    /// Shared between searches (including concurrent ones), and only extended when a search needs a deeper table.
+    pub prune_table: RwLock<Adaptations::PruneTable>, // TODO: push this into the associated data for the adaptations.
 }
 
 pub struct IterativeDeepeningSearchConstructionOptions {
@@ -227,15 +244,15 @@ impl<
             api_data.clone(),
             options.search_logger,
             options.min_prune_table_size,
-        ); // TODO: make the prune table reusable across searches.
+        );
         Ok(Self {
             api_data,
-            prune_table,
+            prune_table: RwLock::new(prune_table),
         })
     }
 
     pub fn search(
-        &mut self,
+        &self,
         search_pattern: &TPuzzle::Pattern,
         mut individual_search_options: IndividualSearchOptions,
     ) -> SearchSolutions {
@@ -257,7 +274,8 @@ impl<
             }
         }
 
-        let (solution_sender, search_solutions) = SearchSolutions::construct();
+        let search_start_time = instant::Instant::now();
+        let (solution_sender, mut search_solutions) = SearchSolutions::construct();
         let mut individual_search_data = IndividualSearchData {
             individual_search_options,
             recursive_work_tracker: RecursiveWorkTracker::new(
@@ -279,14 +297,30 @@ impl<
                 .individual_search_options
                 .get_max_depth()
         {
+            search_solutions.stats.max_depth_searched = Some(remaining_depth);
             let remaining_depth = Depth(remaining_depth);
             self.api_data.search_logger.write_info("----------------");
-            self.prune_table.extend_for_search_depth(
-                remaining_depth,
-                individual_search_data
-                    .recursive_work_tracker
-                    .estimate_next_level_num_recursive_calls(),
-            );
+            let approximate_num_entries = individual_search_data
+                .recursive_work_tracker
+                .estimate_next_level_num_recursive_calls();
+            // Only take the write lock if the table needs to grow, so that concurrent
+            // searches over an already-extended table don't block each other.
+            let needs_extension = self
+                .prune_table
+                .read()
+                .expect("Prune table lock was poisoned.")
+                .needs_extension_for_search_depth(remaining_depth, approximate_num_entries);
+            if needs_extension {
+                self.prune_table
+                    .write()
+                    .expect("Prune table lock was poisoned.")
+                    .extend_for_search_depth(remaining_depth, approximate_num_entries);
+            }
+            // Other searches can read (but not extend) the prune table while we hold this.
+            let prune_table = self
+                .prune_table
+                .read()
+                .expect("Prune table lock was poisoned.");
             individual_search_data
                 .recursive_work_tracker
                 .start_depth(remaining_depth, Some("Starting search…"));
@@ -299,6 +333,7 @@ impl<
                 )
                 .expect("TODO: invalid canonical FSM pre-moves.");
             let recursion_result = self.recurse(
+                &prune_table,
                 &mut individual_search_data,
                 &mut pattern_stack,
                 initial_state,
@@ -312,11 +347,16 @@ impl<
                 break;
             }
         }
+        search_solutions.stats.num_recursive_calls = individual_search_data
+            .recursive_work_tracker
+            .total_num_recursive_calls();
+        search_solutions.stats.duration = instant::Instant::now() - search_start_time;
         search_solutions
     }
 
     fn recurse(
         &self,
+        prune_table: &Adaptations::PruneTable,
         individual_search_data: &mut IndividualSearchData,
         pattern_stack: &mut PatternStack<TPuzzle>,
         current_state: CanonicalFSMState,
@@ -340,7 +380,7 @@ impl<
                 solution_moves,
             );
         }
-        let prune_table_depth = self.prune_table.lookup(current_pattern);
+        let prune_table_depth = prune_table.lookup(current_pattern);
         if prune_table_depth > remaining_depth + Depth(1) {
             return SearchRecursionResult::ContinueSearchingExcludingCurrentMoveClass();
         }
@@ -372,6 +412,7 @@ impl<
                 }
 
                 let recursive_result = self.recurse(
+                    prune_table,
                     individual_search_data,
                     pattern_stack,
                     next_state,
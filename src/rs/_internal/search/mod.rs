@@ -9,5 +9,6 @@ pub mod pattern_traversal_filter_trait;
 pub(crate) mod prune_table_trait;
 pub(crate) mod recursive_work_tracker;
 pub mod search_logger;
+pub mod search_report;
 pub(crate) mod transformation_traversal_filter_trait;
 pub mod whole_number_newtype;
@@ -20,6 +20,17 @@ pub trait PruneTable<TPuzzle: SemiGroupActionPuzzle> {
 
     fn lookup(&self, pattern: &TPuzzle::Pattern) -> Depth;
 
+    /// Returns whether `extend_for_search_depth` would do any work for these arguments.
+    /// This lets callers avoid taking exclusive access to a shared prune table when it
+    /// is already large enough.
+    fn needs_extension_for_search_depth(
+        &self,
+        _search_depth: Depth,
+        _approximate_num_entries: usize,
+    ) -> bool {
+        true
+    }
+
     // TODO
     fn extend_for_search_depth(&mut self, search_depth: Depth, approximate_num_entries: usize);
 }
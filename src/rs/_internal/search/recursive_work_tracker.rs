@@ -14,6 +14,7 @@ pub(crate) struct RecursiveWorkTracker {
     latest_depth_finished: bool,
 
     previous_depth_num_recursive_calls: usize,
+    total_num_recursive_calls: usize,
 
     search_logger: Arc<SearchLogger>,
 }
@@ -24,6 +25,7 @@ impl RecursiveWorkTracker {
             work_name,
             latest_depth: Depth(0),
             previous_depth_num_recursive_calls: 0,
+            total_num_recursive_calls: 0,
             latest_depth_start_time: instant::Instant::now(),
             latest_depth_duration: Duration::ZERO,
             latest_depth_finished: true,
@@ -80,6 +82,12 @@ impl RecursiveWorkTracker {
 
     pub fn record_recursive_call(&mut self) {
         self.latest_depth_num_recursive_calls += 1;
+        self.total_num_recursive_calls += 1;
+    }
+
+    /// The number of recursive calls across all depths.
+    pub fn total_num_recursive_calls(&self) -> usize {
+        self.total_num_recursive_calls
     }
 
     pub fn estimate_next_level_num_recursive_calls(&self) -> usize {
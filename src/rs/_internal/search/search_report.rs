@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use cubing::alg::{Alg, AlgNode};
+use serde::Serialize;
+
+use crate::_internal::cli::args::MetricEnum;
+
+use super::iterative_deepening::iterative_deepening_search::SearchStats;
+
+/// The length of an alg in each supported metric.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgLengths {
+    /// Every move counts as 1.
+    pub hand: usize,
+    /// Every move counts as the absolute value of its amount (e.g. `R2` counts as 2).
+    pub quantum: usize,
+}
+
+impl AlgLengths {
+    pub fn from_alg(alg: &Alg) -> Self {
+        let mut lengths = Self {
+            hand: 0,
+            quantum: 0,
+        };
+        for node in &alg.nodes {
+            match node {
+                AlgNode::MoveNode(r#move) => {
+                    lengths.hand += 1;
+                    lengths.quantum += r#move.amount.unsigned_abs() as usize;
+                }
+                AlgNode::PauseNode(_) | AlgNode::NewlineNode(_) | AlgNode::LineCommentNode(_) => {}
+                _ => {
+                    // Search solutions are always flat sequences of moves.
+                    lengths.hand += 1;
+                    lengths.quantum += 1;
+                }
+            }
+        }
+        lengths
+    }
+
+    pub fn in_metric(&self, metric: &MetricEnum) -> usize {
+        match metric {
+            MetricEnum::Hand => self.hand,
+            MetricEnum::Quantum => self.quantum,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchReportSolution {
+    pub alg: String,
+    pub lengths: AlgLengths,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchReportStats {
+    pub num_recursive_calls: usize,
+    pub max_depth_searched: Option<usize>,
+    pub search_seconds: f64,
+}
+
+/// A machine-readable summary of the search for a single scramble.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchReport {
+    /// The scramble, as it was passed to the search (e.g. an alg, facelets, or the name of a block in a scramble file).
+    pub input: String,
+    pub solutions: Vec<SearchReportSolution>,
+    /// The metric used for the search.
+    pub metric: String,
+    /// The length of the shortest solution in the metric used for the search.
+    pub depth: Option<usize>,
+    /// The time since the command started, including setup (e.g. parsing the definition and building the prune table) and any earlier scrambles.
+    pub total_seconds: f64,
+    pub stats: SearchReportStats,
+    /// Set if the pattern was not searched (e.g. because it is unsolvable).
+    pub error: Option<String>,
+}
+
+impl SearchReport {
+    pub fn new(
+        input: String,
+        solutions: &[Alg],
+        metric: &MetricEnum,
+        total_duration: Duration,
+        stats: &SearchStats,
+    ) -> Self {
+        let solutions: Vec<SearchReportSolution> = solutions
+            .iter()
+            .map(|alg| SearchReportSolution {
+                alg: alg.to_string(),
+                lengths: AlgLengths::from_alg(alg),
+            })
+            .collect();
+        let depth = solutions
+            .iter()
+            .map(|solution| solution.lengths.in_metric(metric))
+            .min();
+        Self {
+            input,
+            solutions,
+            metric: metric.to_string(),
+            depth,
+            total_seconds: total_duration.as_secs_f64(),
+            stats: SearchReportStats {
+                num_recursive_calls: stats.num_recursive_calls,
+                max_depth_searched: stats.max_depth_searched,
+                search_seconds: stats.duration.as_secs_f64(),
+            },
+            error: None,
+        }
+    }
+
+    pub fn from_error(
+        input: String,
+        error: String,
+        metric: &MetricEnum,
+        total_duration: Duration,
+    ) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(input, &[], metric, total_duration, &Default::default())
+        }
+    }
+
+    /// Serializes the report as a single line, so that reports for multiple scrambles can be printed as JSON lines.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("Could not serialize report.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cubing::alg::Alg;
+
+    use crate::_internal::{
+        cli::args::MetricEnum, search::iterative_deepening::iterative_deepening_search::SearchStats,
+    };
+
+    use super::SearchReport;
+
+    #[test]
+    fn search_report_json_line_test() {
+        let report = SearchReport::new(
+            "R U".to_owned(),
+            &[
+                "U' R'".parse::<Alg>().unwrap(),
+                "R2 U2".parse::<Alg>().unwrap(),
+            ],
+            &MetricEnum::Quantum,
+            Duration::from_millis(1500),
+            &SearchStats {
+                num_recursive_calls: 42,
+                max_depth_searched: Some(2),
+                duration: Duration::from_millis(500),
+            },
+        );
+        let json_line = report.to_json_line();
+        assert!(!json_line.contains('\n'));
+        let json: serde_json::Value = serde_json::from_str(&json_line).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "input": "R U",
+                "solutions": [
+                    { "alg": "U' R'", "lengths": { "hand": 2, "quantum": 2 } },
+                    { "alg": "R2 U2", "lengths": { "hand": 2, "quantum": 4 } },
+                ],
+                "metric": "quantum",
+                "depth": 2,
+                "totalSeconds": 1.5,
+                "stats": {
+                    "numRecursiveCalls": 42,
+                    "maxDepthSearched": 2,
+                    "searchSeconds": 0.5,
+                },
+                "error": null,
+            })
+        );
+    }
+
+    #[test]
+    fn search_report_error_test() {
+        let report = SearchReport::from_error(
+            "scramble".to_owned(),
+            "The pattern is unsolvable.".to_owned(),
+            &MetricEnum::Hand,
+            Duration::ZERO,
+        );
+        let json: serde_json::Value = serde_json::from_str(&report.to_json_line()).unwrap();
+        assert_eq!(json["error"], "The pattern is unsolvable.");
+        assert_eq!(json["solutions"], serde_json::json!([]));
+        assert!(json["depth"].is_null());
+        assert_eq!(json["stats"]["numRecursiveCalls"], 0);
+        assert!(json["stats"]["maxDepthSearched"].is_null());
+    }
+}
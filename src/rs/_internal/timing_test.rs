@@ -0,0 +1,392 @@
+use std::{fmt::Write, hint::black_box, sync::Arc};
+
+use cubing::kpuzzle::{KPattern, KPatternBuffer, KPuzzle, KTransformation};
+use instant::Instant;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::Serialize;
+use thousands::Separable;
+
+use super::{
+    canonical_fsm::{
+        canonical_algs::count_canonical_algs,
+        canonical_fsm::{CanonicalFSM, CanonicalFSMState, CANONICAL_FSM_START_STATE},
+        search_generators::SearchGenerators,
+    },
+    cli::args::{Generators, MetricEnum, VerbosityLevel},
+    errors::SearchError,
+    search::{
+        hash_prune_table::PRUNE_TABLE_ENTRY_NUM_BYTES,
+        iterative_deepening::iterative_deepening_search::{
+            IterativeDeepeningSearch, IterativeDeepeningSearchConstructionOptions,
+        },
+        prune_table_trait::{Depth, PruneTable},
+        search_logger::SearchLogger,
+    },
+};
+
+// The move sequence and iteration counts are fixed so that numbers are comparable across runs and machines.
+const RANDOM_MOVES_SEED: u64 = 0;
+const NUM_RANDOM_MOVES: usize = 65536;
+const NUM_MOVE_APPLICATIONS: usize = 10_000_000;
+const NUM_MEASUREMENT_RUNS: usize = 3;
+
+const MAX_PRUNE_TABLE_DEPTH: usize = 20;
+// Filling the prune table to a given depth visits every canonical alg up to that depth.
+// The deepest depth is chosen so that the total number of canonical algs stays within this budget.
+const MAX_PRUNE_TABLE_NODES: u128 = 20_000_000;
+
+const MAX_FSM_ENUMERATION_NODES: usize = 5_000_000;
+// Keeps the recursion shallow for puzzles with very few moves.
+const MAX_FSM_ENUMERATION_DEPTH: usize = 20;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveApplicationTiming {
+    pub num_moves: usize,
+    /// Best of several runs.
+    pub seconds: f64,
+    pub moves_per_second: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneTableDepthTiming {
+    pub depth: usize,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneTableTiming {
+    pub num_entries: usize,
+    pub depths: Vec<PruneTableDepthTiming>,
+}
+
+/// Compares a full enumeration of move sequences up to `depth` with and without the canonical FSM.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanonicalFSMTiming {
+    pub num_states: usize,
+    pub construction_seconds: f64,
+    pub depth: usize,
+    pub num_nodes_without_fsm: usize,
+    pub seconds_without_fsm: f64,
+    pub num_nodes_with_fsm: usize,
+    pub seconds_with_fsm: f64,
+}
+
+impl CanonicalFSMTiming {
+    pub fn nodes_per_second_without_fsm(&self) -> f64 {
+        self.num_nodes_without_fsm as f64 / self.seconds_without_fsm
+    }
+
+    pub fn nodes_per_second_with_fsm(&self) -> f64 {
+        self.num_nodes_with_fsm as f64 / self.seconds_with_fsm
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingTestReport {
+    pub puzzle_name: String,
+    pub metric: String,
+    pub num_moves: usize,
+    pub apply_transformation: MoveApplicationTiming,
+    pub apply_transformation_into: MoveApplicationTiming,
+    pub prune_table: PruneTableTiming,
+    pub canonical_fsm: CanonicalFSMTiming,
+}
+
+fn format_rate(per_second: f64) -> String {
+    format!("{:.2}M/s", per_second / 1_000_000.0)
+}
+
+fn format_move_application(name: &str, timing: &MoveApplicationTiming) -> String {
+    format!(
+        "{}: {} moves in {:.3}s ({})",
+        name,
+        timing.num_moves.separate_with_underscores(),
+        timing.seconds,
+        format_rate(timing.moves_per_second)
+    )
+}
+
+impl TimingTestReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Could not serialize report.")
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        let _ = writeln!(
+            summary,
+            "{} ({} metric, {} moves)",
+            self.puzzle_name, self.metric, self.num_moves
+        );
+        let _ = writeln!(
+            summary,
+            "{}",
+            format_move_application("apply_transformation", &self.apply_transformation)
+        );
+        let _ = writeln!(
+            summary,
+            "{}",
+            format_move_application("apply_transformation_into", &self.apply_transformation_into)
+        );
+        let _ = writeln!(
+            summary,
+            "Prune table ({} entries):",
+            self.prune_table.num_entries.separate_with_underscores()
+        );
+        for depth_timing in &self.prune_table.depths {
+            let _ = writeln!(
+                summary,
+                "  Depth {}: {:.6}s",
+                depth_timing.depth, depth_timing.seconds
+            );
+        }
+        let canonical_fsm = &self.canonical_fsm;
+        let _ = writeln!(
+            summary,
+            "Canonical FSM: {} states (constructed in {:.6}s)",
+            canonical_fsm.num_states, canonical_fsm.construction_seconds
+        );
+        let _ = writeln!(
+            summary,
+            "  Depth {} without FSM: {} nodes in {:.3}s ({})",
+            canonical_fsm.depth,
+            canonical_fsm
+                .num_nodes_without_fsm
+                .separate_with_underscores(),
+            canonical_fsm.seconds_without_fsm,
+            format_rate(canonical_fsm.nodes_per_second_without_fsm())
+        );
+        let _ = writeln!(
+            summary,
+            "  Depth {} with FSM: {} nodes in {:.3}s ({})",
+            canonical_fsm.depth,
+            canonical_fsm.num_nodes_with_fsm.separate_with_underscores(),
+            canonical_fsm.seconds_with_fsm,
+            format_rate(canonical_fsm.nodes_per_second_with_fsm())
+        );
+        let _ = write!(
+            summary,
+            "  Per-node overhead: {:+.1}%",
+            (canonical_fsm.nodes_per_second_without_fsm()
+                / canonical_fsm.nodes_per_second_with_fsm()
+                - 1.0)
+                * 100.0
+        );
+        summary
+    }
+}
+
+fn time_move_application(mut run: impl FnMut()) -> MoveApplicationTiming {
+    let mut best_seconds = f64::INFINITY;
+    for _ in 0..NUM_MEASUREMENT_RUNS {
+        let start_time = Instant::now();
+        run();
+        best_seconds = best_seconds.min(Instant::now().duration_since(start_time).as_secs_f64());
+    }
+    MoveApplicationTiming {
+        num_moves: NUM_MOVE_APPLICATIONS,
+        seconds: best_seconds,
+        moves_per_second: NUM_MOVE_APPLICATIONS as f64 / best_seconds,
+    }
+}
+
+// `patterns[0]` is the current pattern, and the rest are used as buffers for deeper levels.
+fn count_nodes_without_fsm(
+    patterns: &mut [KPattern],
+    transformations: &[&KTransformation],
+) -> usize {
+    let (current_pattern, deeper_patterns) = patterns.split_first_mut().unwrap();
+    if deeper_patterns.is_empty() {
+        return 1;
+    }
+    let mut num_nodes = 1;
+    for transformation in transformations {
+        current_pattern.apply_transformation_into(transformation, &mut deeper_patterns[0]);
+        black_box(&deeper_patterns[0]);
+        num_nodes += count_nodes_without_fsm(deeper_patterns, transformations);
+    }
+    num_nodes
+}
+
+fn count_nodes_with_fsm(
+    patterns: &mut [KPattern],
+    search_generators: &SearchGenerators<KPuzzle>,
+    canonical_fsm: &CanonicalFSM<KPuzzle>,
+    current_state: CanonicalFSMState,
+) -> usize {
+    let (current_pattern, deeper_patterns) = patterns.split_first_mut().unwrap();
+    if deeper_patterns.is_empty() {
+        return 1;
+    }
+    let mut num_nodes = 1;
+    for (move_class_index, move_transformation_multiples) in search_generators.by_move_class.iter()
+    {
+        let Some(next_state) = canonical_fsm.next_state(current_state, move_class_index) else {
+            continue;
+        };
+        for move_transformation_info in move_transformation_multiples {
+            current_pattern.apply_transformation_into(
+                &move_transformation_info.transformation,
+                &mut deeper_patterns[0],
+            );
+            black_box(&deeper_patterns[0]);
+            num_nodes += count_nodes_with_fsm(
+                deeper_patterns,
+                search_generators,
+                canonical_fsm,
+                next_state,
+            );
+        }
+    }
+    num_nodes
+}
+
+/// Measures move application throughput, prune table generation speed, and canonical FSM overhead for all moves of the given puzzle.
+pub fn timing_test(
+    kpuzzle: &KPuzzle,
+    metric: &MetricEnum,
+    memory_mebibytes: Option<usize>,
+) -> Result<TimingTestReport, SearchError> {
+    let generator_moves = Generators::Default.enumerate_moves_for_kpuzzle(kpuzzle);
+    let search_generators =
+        SearchGenerators::try_new(kpuzzle, generator_moves.clone(), metric, false)?;
+    let num_moves = search_generators.flat.len();
+    if num_moves == 0 {
+        return Err(SearchError {
+            description: "The puzzle has no moves to time.".to_owned(),
+        });
+    }
+
+    // Move application
+    let mut rng = StdRng::seed_from_u64(RANDOM_MOVES_SEED);
+    let random_move_list: Vec<&KTransformation> = (0..NUM_RANDOM_MOVES)
+        .map(|_| {
+            &search_generators
+                .flat
+                .0
+                .choose(&mut rng)
+                .unwrap()
+                .transformation
+        })
+        .collect();
+    let apply_transformation = time_move_application(|| {
+        let mut pattern = kpuzzle.default_pattern();
+        for i in 0..NUM_MOVE_APPLICATIONS {
+            pattern = pattern.apply_transformation(random_move_list[i % NUM_RANDOM_MOVES]);
+        }
+        black_box(pattern);
+    });
+    let apply_transformation_into = time_move_application(|| {
+        let mut pattern_buffer = KPatternBuffer::from(kpuzzle.default_pattern());
+        for i in 0..NUM_MOVE_APPLICATIONS {
+            pattern_buffer.apply_transformation(random_move_list[i % NUM_RANDOM_MOVES]);
+        }
+        black_box(pattern_buffer);
+    });
+
+    // Canonical FSM construction
+    let start_time = Instant::now();
+    let canonical_fsm = CanonicalFSM::try_new(
+        kpuzzle.clone(),
+        search_generators.clone(),
+        Default::default(),
+    )?;
+    let construction_seconds = Instant::now().duration_since(start_time).as_secs_f64();
+
+    // Prune table
+    let num_entries = memory_mebibytes
+        .map(|memory_mebibytes| (memory_mebibytes << 20) / PRUNE_TABLE_ENTRY_NUM_BYTES);
+    let mut iterative_deepening_search = IterativeDeepeningSearch::<KPuzzle>::try_new(
+        kpuzzle.clone(),
+        generator_moves,
+        vec![kpuzzle.default_pattern()],
+        IterativeDeepeningSearchConstructionOptions {
+            search_logger: Arc::new(SearchLogger {
+                verbosity: VerbosityLevel::Silent,
+            }),
+            metric: metric.clone(),
+            min_prune_table_size: num_entries,
+            ..Default::default()
+        },
+    )?;
+    let num_entries = num_entries.unwrap_or(0).next_power_of_two().max(1 << 20);
+    let mut num_prune_table_nodes: u128 = 0;
+    let prune_table_max_depth =
+        count_canonical_algs(&canonical_fsm, &search_generators, MAX_PRUNE_TABLE_DEPTH)
+            .iter()
+            .skip(1)
+            .take_while(|depth_count| {
+                num_prune_table_nodes =
+                    num_prune_table_nodes.saturating_add(depth_count.num_canonical_algs);
+                num_prune_table_nodes <= MAX_PRUNE_TABLE_NODES
+            })
+            .count()
+            // Always time at least one depth.
+            .max(1);
+    let mut prune_table_depths = vec![];
+    for depth in 1..=prune_table_max_depth {
+        let start_time = Instant::now();
+        iterative_deepening_search
+            .prune_table
+            .get_mut()
+            .expect("Prune table lock was poisoned.")
+            .extend_for_search_depth(Depth(depth * 2), num_entries);
+        let seconds = Instant::now().duration_since(start_time).as_secs_f64();
+        prune_table_depths.push(PruneTableDepthTiming { depth, seconds });
+    }
+
+    // Canonical FSM enumeration
+    let mut depth = 1;
+    while depth < MAX_FSM_ENUMERATION_DEPTH
+        && (0..=depth + 1)
+            .map(|i| num_moves.saturating_pow(i as u32))
+            .fold(0, usize::saturating_add)
+            <= MAX_FSM_ENUMERATION_NODES
+    {
+        depth += 1;
+    }
+    let flat_transformations: Vec<&KTransformation> = search_generators
+        .flat
+        .0
+        .iter()
+        .map(|move_transformation_info| &move_transformation_info.transformation)
+        .collect();
+    let mut patterns = vec![kpuzzle.default_pattern(); depth + 1];
+    let start_time = Instant::now();
+    let num_nodes_without_fsm = count_nodes_without_fsm(&mut patterns, &flat_transformations);
+    let seconds_without_fsm = Instant::now().duration_since(start_time).as_secs_f64();
+    let start_time = Instant::now();
+    let num_nodes_with_fsm = count_nodes_with_fsm(
+        &mut patterns,
+        &search_generators,
+        &canonical_fsm,
+        CANONICAL_FSM_START_STATE,
+    );
+    let seconds_with_fsm = Instant::now().duration_since(start_time).as_secs_f64();
+
+    Ok(TimingTestReport {
+        puzzle_name: kpuzzle.definition().name.to_string(),
+        metric: metric.to_string(),
+        num_moves,
+        apply_transformation,
+        apply_transformation_into,
+        prune_table: PruneTableTiming {
+            num_entries,
+            depths: prune_table_depths,
+        },
+        canonical_fsm: CanonicalFSMTiming {
+            num_states: canonical_fsm.next_state_lookup.len(),
+            construction_seconds,
+            depth,
+            num_nodes_without_fsm,
+            seconds_without_fsm,
+            num_nodes_with_fsm,
+            seconds_with_fsm,
+        },
+    })
+}
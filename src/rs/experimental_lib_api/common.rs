@@ -1,25 +1,39 @@
 use derive_more::From;
 
-use std::{path::PathBuf, process::exit};
+use std::{
+    io::{stdin, Read},
+    path::PathBuf,
+};
 
 use crate::_internal::{
     cli::{
         args::{DefOnlyArgs, ScrambleAndTargetPatternOptionalArgs},
-        io::read_to_json,
+        io::{parse_json, read_input_file},
     },
+    cycle_notation::parse_pattern_cycle_notation,
     errors::{ArgumentError, CommandError},
+    facelets::pattern_from_facelets,
+    ksolve::{
+        is_probably_json, is_probably_ksolve_scrambles, parse_ksolve_definition,
+        parse_ksolve_scrambles,
+    },
 };
+use crate::scramble::{embedded_facelet_mapping, embedded_kpuzzle};
 use cubing::{
     alg::Alg,
     kpuzzle::{KPattern, KPatternData, KPuzzle, KPuzzleDefinition},
 };
 
 // TODO: can we afford to make these fields into references?
+/// A `FilePath` can be a JSON `KPuzzleDefinition` or a ksolve definition (e.g. `.tws`). The format is detected automatically.
 #[derive(Debug, From)]
 pub enum KPuzzleSource {
     KPuzzle(KPuzzle),
     KPuzzleDefinition(KPuzzleDefinition),
     FilePath(PathBuf),
+    /// The ID of a built-in puzzle definition (see [`embedded_puzzles`](crate::scramble::embedded_puzzles)).
+    #[from(ignore)]
+    EmbeddedPuzzleId(String),
 }
 
 impl KPuzzleSource {
@@ -27,7 +41,21 @@ impl KPuzzleSource {
         let def = match self {
             KPuzzleSource::KPuzzle(kpuzzle) => return Ok(kpuzzle),
             KPuzzleSource::KPuzzleDefinition(kpuzzle_definition) => kpuzzle_definition,
-            KPuzzleSource::FilePath(path_buf) => read_to_json(&path_buf)?,
+            KPuzzleSource::FilePath(path_buf) => {
+                let input_str = read_input_file(&path_buf)?;
+                if is_probably_json(&input_str) {
+                    parse_json(&input_str)?
+                } else {
+                    parse_ksolve_definition(&input_str)?
+                }
+            }
+            KPuzzleSource::EmbeddedPuzzleId(puzzle_id) => {
+                return embedded_kpuzzle(&puzzle_id)
+                    .cloned()
+                    .map_err(|e| ArgumentError {
+                        description: e.description,
+                    })
+            }
         };
 
         KPuzzle::try_from(def).map_err(|e| ArgumentError {
@@ -38,27 +66,110 @@ impl KPuzzleSource {
 
 impl KPuzzleSource {
     // TODO
-    pub fn from_clap_args(def_args: &DefOnlyArgs) -> Self {
-        Self::FilePath(def_args.def_file.clone())
+    pub fn from_clap_args(def_args: &DefOnlyArgs) -> Result<Self, ArgumentError> {
+        match (&def_args.def_file, &def_args.puzzle) {
+            (Some(def_file), None) => Ok(Self::FilePath(def_file.clone())),
+            (None, Some(puzzle_id)) => Ok(Self::EmbeddedPuzzleId(puzzle_id.clone())),
+            _ => Err("Exactly one of a definition file or `--puzzle` must be specified.".into()),
+        }
     }
 }
 
 // TODO: can we afford to make these fields into references?
+/// A `FilePath` can be JSON `KPatternData`, a ksolve scramble file (e.g. `.scr`), or a file with one alg per line. The format is detected automatically.
 #[derive(Debug, From)]
 pub enum PatternSource {
     DefaultFromDefinition,
     FilePath(PathBuf),
+    /// Algs read from standard in, one per line.
+    StdinAlgs,
     AlgAppliedToDefaultPattern(Alg),
+    /// A facelet string (e.g. Kociemba's `UUUUUUUUURRR…`), for a 2x2x2, 3x3x3, or 4x4x4 definition.
+    /// Pieces that look the same are represented by the same piece, so the target pattern of a search needs [`crate::_internal::facelets::merge_look_alike_pieces`].
+    #[from(ignore)]
+    Facelets(String),
+    /// Cycle notation relative to the default pattern, e.g. `CORNERS:(0 1 2)+ EDGES:(3 7)`.
+    #[from(ignore)]
+    Cycles(String),
+}
+
+/// A pattern to search for, named by its input (e.g. the alg, or the name of a block in a scramble file).
+#[derive(Debug)]
+pub struct NamedPattern {
+    pub name: String,
+    pub pattern: KPattern,
+}
+
+fn patterns_from_alg_lines(
+    kpuzzle: &KPuzzle,
+    input: &str,
+) -> Result<Vec<NamedPattern>, ArgumentError> {
+    let mut named_patterns = vec![];
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let pattern = line
+            .parse::<Alg>()
+            .map_err(|e| e.to_string())
+            .and_then(|alg| {
+                kpuzzle
+                    .default_pattern()
+                    .apply_alg(&alg)
+                    .map_err(|e| e.to_string())
+            })
+            .map_err(|e| ArgumentError {
+                description: format!("Line {}: Invalid scramble alg `{}`: {}", i + 1, line, e),
+            })?;
+        named_patterns.push(NamedPattern {
+            name: line.to_owned(),
+            pattern,
+        });
+    }
+    Ok(named_patterns)
 }
 
 impl PatternSource {
     pub fn pattern(self, kpuzzle: &KPuzzle) -> Result<KPattern, CommandError /* TODO */> {
-        Ok(match self {
-            PatternSource::DefaultFromDefinition => kpuzzle.default_pattern(),
+        let mut named_patterns = self.patterns(kpuzzle)?;
+        if named_patterns.len() != 1 {
+            return Err(CommandError::ArgumentError(ArgumentError {
+                description: format!(
+                    "Expected exactly one scramble, but found {}.",
+                    named_patterns.len()
+                ),
+            }));
+        }
+        Ok(named_patterns.remove(0).pattern)
+    }
+
+    /// Like [`PatternSource::pattern`], but allows any number of scrambles (from a scramble file or standard in).
+    pub fn patterns(self, kpuzzle: &KPuzzle) -> Result<Vec<NamedPattern>, CommandError> {
+        let (name, pattern) = match self {
+            PatternSource::DefaultFromDefinition => {
+                ("default pattern".to_owned(), kpuzzle.default_pattern())
+            }
             PatternSource::FilePath(path_buf) => {
-                let kpattern_data: KPatternData = read_to_json(&path_buf)?;
+                let input_str = read_input_file(&path_buf)?;
+                if !is_probably_json(&input_str) {
+                    if is_probably_ksolve_scrambles(&input_str) {
+                        return Ok(parse_ksolve_scrambles(kpuzzle, &input_str)?
+                            .into_iter()
+                            .map(|scramble| NamedPattern {
+                                name: scramble.name,
+                                pattern: scramble.pattern,
+                            })
+                            .collect());
+                    }
+                    return Ok(patterns_from_alg_lines(kpuzzle, &input_str)?);
+                }
+                let kpattern_data: KPatternData = parse_json(&input_str)?;
                 match KPattern::try_from_data(kpuzzle, &kpattern_data) {
-                    Ok(start_or_target_pattern) => start_or_target_pattern,
+                    Ok(start_or_target_pattern) => (
+                        path_buf.to_string_lossy().into_owned(),
+                        start_or_target_pattern,
+                    ),
                     Err(e) => {
                         return Err(CommandError::ArgumentError(ArgumentError {
                             description: e.to_string(),
@@ -66,9 +177,34 @@ impl PatternSource {
                     }
                 }
             }
+            PatternSource::StdinAlgs => {
+                let mut input_str = String::new();
+                if let Err(e) = stdin().read_to_string(&mut input_str) {
+                    return Err(CommandError::ArgumentError(ArgumentError {
+                        description: format!("Could not read scrambles from standard in: {}", e),
+                    }));
+                }
+                return Ok(patterns_from_alg_lines(kpuzzle, &input_str)?);
+            }
+            PatternSource::Facelets(facelets) => {
+                let Some(facelet_mapping) = embedded_facelet_mapping(kpuzzle) else {
+                    return Err(CommandError::ArgumentError(ArgumentError {
+                        description: format!(
+                            "Facelet strings are only supported for the built-in 2x2x2, 3x3x3, and 4x4x4 definitions (see `--puzzle`), not for: {}",
+                            kpuzzle.definition().name
+                        ),
+                    }));
+                };
+                let pattern = pattern_from_facelets(kpuzzle, facelet_mapping, &facelets)?;
+                (facelets, pattern)
+            }
+            PatternSource::Cycles(cycles) => {
+                let pattern = parse_pattern_cycle_notation(kpuzzle, &cycles)?;
+                (cycles, pattern)
+            }
             PatternSource::AlgAppliedToDefaultPattern(alg) => {
                 match kpuzzle.default_pattern().apply_alg(&alg) {
-                    Ok(pattern) => pattern,
+                    Ok(pattern) => (alg.to_string(), pattern),
                     Err(err) => {
                         return Err(CommandError::ArgumentError(ArgumentError {
                             description: err.to_string(), // TODO
@@ -76,7 +212,8 @@ impl PatternSource {
                     }
                 }
             }
-        })
+        };
+        Ok(vec![NamedPattern { name, pattern }])
     }
 }
 
@@ -86,28 +223,94 @@ impl PatternSource {
         scramble_and_target_pattern_optional_args: &ScrambleAndTargetPatternOptionalArgs,
     ) -> Result<Self, CommandError> {
         match (
+            scramble_and_target_pattern_optional_args.stdin_scrambles,
             &scramble_and_target_pattern_optional_args.scramble_alg,
-            &scramble_and_target_pattern_optional_args.scramble_file,
+            scramble_and_target_pattern_optional_args.scramble_file_path(),
+            &scramble_and_target_pattern_optional_args.scramble_facelets,
+            &scramble_and_target_pattern_optional_args.scramble_cycles,
         ) {
-            (None, None) => {
-                println!("No scramble specified, exiting.");
-                exit(0);
+            (true, None, None, None, None) => Ok(Self::StdinAlgs),
+            (false, None, None, None, None) => Err(ArgumentError::from(
+                "No scramble specified. Pass a scramble file, `--scramble-alg`, `--scramble-facelets`, `--scramble-cycles`, or `--stdin-scrambles`.",
+            )
+            .into()),
+            (false, None, Some(scramble_file), None, None) => {
+                Ok(Self::FilePath(scramble_file.clone()))
             }
-            (None, Some(scramble_file)) => Ok(Self::FilePath(scramble_file.clone())),
-            (Some(scramble_alg), None) => {
+            (false, None, None, Some(scramble_facelets), None) => {
+                Ok(Self::Facelets(scramble_facelets.clone()))
+            }
+            (false, None, None, None, Some(scramble_cycles)) => {
+                Ok(Self::Cycles(scramble_cycles.clone()))
+            }
+            (false, Some(scramble_alg), None, None, None) => {
                 let alg = match scramble_alg.parse::<Alg>() {
                     Ok(alg) => alg,
                     Err(e) => {
-                        eprintln!("Could not parse alg: {:?}", e);
-                        exit(1)
+                        return Err(ArgumentError {
+                            description: format!(
+                                "Could not parse the scramble alg `{}`: {}",
+                                scramble_alg, e
+                            ),
+                        }
+                        .into())
                     }
                 };
                 Ok(Self::AlgAppliedToDefaultPattern(alg))
             }
-            (Some(_), Some(_)) => {
-                eprintln!("Error: specified both a scramble alg and a scramble file, exiting.");
-                exit(1);
-            }
+            _ => Err(ArgumentError::from(
+                "More than one scramble was specified. Pass only one of a scramble file, `--scramble-alg`, `--scramble-facelets`, `--scramble-cycles`, and `--stdin-scrambles`.",
+            )
+            .into()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        _internal::{
+            cli::args::{GeneratorArgs, ScrambleAndTargetPatternOptionalArgs},
+            errors::CommandError,
+        },
+        experimental_lib_api::PatternSource,
+    };
+
+    #[test]
+    fn clap_args_errors_test() {
+        let argument_error = |args: ScrambleAndTargetPatternOptionalArgs| {
+            match PatternSource::search_pattern_from_clap_args(&args) {
+                Err(CommandError::ArgumentError(e)) => e.to_string(),
+                _ => panic!("Expected an argument error."),
+            }
+        };
+        assert!(argument_error(Default::default()).starts_with("No scramble specified."));
+        assert!(argument_error(ScrambleAndTargetPatternOptionalArgs {
+            scramble_alg: Some("R U".to_owned()),
+            scramble_cycles: Some("EDGES:(0 1)".to_owned()),
+            ..Default::default()
+        })
+        .starts_with("More than one scramble was specified."));
+        assert!(argument_error(ScrambleAndTargetPatternOptionalArgs {
+            scramble_alg: Some("R U".to_owned()),
+            stdin_scrambles: true,
+            ..Default::default()
+        })
+        .starts_with("More than one scramble was specified."));
+        assert!(argument_error(ScrambleAndTargetPatternOptionalArgs {
+            scramble_alg: Some("R U (".to_owned()),
+            ..Default::default()
+        })
+        .starts_with("Could not parse the scramble alg `R U (`"));
+
+        let generator_args = GeneratorArgs {
+            generator_moves_string: Some("R, U".to_owned()),
+            ..Default::default()
+        };
+        assert!(generator_args
+            .parse()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid entry ` U` for `--generator-moves`"));
+    }
+}
@@ -0,0 +1,169 @@
+use cubing::kpuzzle::KPuzzle;
+
+use crate::_internal::{
+    cli::args::{CosetOptionalArgs, GeneratorArgs},
+    errors::{ArgumentError, CommandError},
+    gods_algorithm::{
+        coset::{CosetSearch, CosetSearchConstructionOptions, CosetSubgroup, CosetTable},
+        progress::{GodsAlgorithmProgressObserver, SilentGodsAlgorithmProgressObserver},
+    },
+};
+
+use super::common::PatternSource;
+
+/// Note: the `coset_optional_args` argument is not yet ergonomic, and will be refactored.
+///
+/// This does not print anything. Use [`coset_with_observer`] to receive progress updates.
+pub fn coset(
+    kpuzzle: &KPuzzle,
+    coset_optional_args: CosetOptionalArgs,
+) -> Result<CosetTable, CommandError> {
+    coset_with_observer(
+        kpuzzle,
+        coset_optional_args,
+        &mut SilentGodsAlgorithmProgressObserver,
+    )
+}
+
+pub fn coset_with_observer(
+    kpuzzle: &KPuzzle,
+    coset_optional_args: CosetOptionalArgs,
+    observer: &mut dyn GodsAlgorithmProgressObserver,
+) -> Result<CosetTable, CommandError> {
+    let subgroup = match (
+        coset_optional_args.subgroup_moves,
+        coset_optional_args.subgroup_mask,
+    ) {
+        (Some(subgroup_moves), None) => CosetSubgroup::Generators(
+            GeneratorArgs {
+                generator_moves_string: Some(subgroup_moves),
+                ..Default::default()
+            }
+            .parse()?,
+        ),
+        (None, Some(subgroup_mask)) => {
+            CosetSubgroup::Mask(PatternSource::FilePath(subgroup_mask).pattern(kpuzzle)?)
+        }
+        _ => {
+            return Err(ArgumentError {
+                description: "Exactly one of a subgroup mask or subgroup moves must be specified."
+                    .to_owned(),
+            }
+            .into())
+        }
+    };
+    let coset_pattern = match coset_optional_args.coset_pattern {
+        Some(path_buf) => Some(PatternSource::FilePath(path_buf).pattern(kpuzzle)?),
+        None => None,
+    };
+    let mut coset_search = CosetSearch::try_new(
+        kpuzzle,
+        subgroup,
+        &coset_optional_args.generator_args.parse()?,
+        &coset_optional_args.metric_args.metric,
+        CosetSearchConstructionOptions {
+            coset_pattern,
+            max_depth: coset_optional_args.max_depth,
+            memory_mebibytes: coset_optional_args.memory_args.memory_mebibytes,
+        },
+    )?;
+    coset_search.fill(observer)
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::{
+        alg::parse_alg,
+        puzzles::{cube2x2x2_kpuzzle, cube3x3x3_kpuzzle},
+    };
+
+    use crate::{
+        _internal::{
+            cli::args::{CosetOptionalArgs, GeneratorArgs, MemoryArgs, MetricEnum},
+            gods_algorithm::coset::{CosetSearch, CosetSearchConstructionOptions, CosetSubgroup},
+            gods_algorithm::progress::SilentGodsAlgorithmProgressObserver,
+        },
+        experimental_lib_api::coset,
+    };
+
+    #[test]
+    fn coset_api_test() {
+        let kpuzzle = cube2x2x2_kpuzzle();
+        let generator_args = || GeneratorArgs {
+            generator_moves_string: Some("R,U".to_owned()),
+            ..Default::default()
+        };
+
+        // On the 2x2x2, the subgroup `<R2, U2>` has 6 elements.
+        let table = coset(
+            kpuzzle,
+            CosetOptionalArgs {
+                subgroup_moves: Some("R2,U2".to_owned()),
+                generator_args: generator_args(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(table.completed());
+        assert_eq!(table.num_subgroup_elements(), Some(6));
+        assert_eq!(table.depth_counts().iter().sum::<usize>(), 6);
+        assert_eq!(table.depth_counts()[0], 1);
+
+        let coset_pattern = kpuzzle
+            .default_pattern()
+            .apply_alg(parse_alg!("R U"))
+            .unwrap();
+        let mut coset_search = CosetSearch::try_new(
+            kpuzzle,
+            CosetSubgroup::Generators(
+                GeneratorArgs {
+                    generator_moves_string: Some("R2,U2".to_owned()),
+                    ..Default::default()
+                }
+                .parse()
+                .unwrap(),
+            ),
+            &generator_args().parse().unwrap(),
+            &MetricEnum::Hand,
+            CosetSearchConstructionOptions {
+                coset_pattern: Some(coset_pattern),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let table = coset_search
+            .fill(&mut SilentGodsAlgorithmProgressObserver)
+            .unwrap();
+        assert!(table.completed());
+        assert_eq!(table.depth_counts()[0], 0);
+        assert_eq!(table.depth_counts().iter().sum::<usize>(), 6);
+
+        // `R` and `U` never move the DFL corner, so `F2` cannot be reached.
+        let table = coset(
+            kpuzzle,
+            CosetOptionalArgs {
+                subgroup_moves: Some("F2".to_owned()),
+                generator_args: generator_args(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(!table.completed());
+        assert_eq!(table.num_subgroup_elements(), Some(2));
+        assert_eq!(table.depth_counts(), [1]);
+
+        // On the 3x3x3, the bitmap for `<R, U>` does not fit in 1 MiB.
+        assert!(coset(
+            cube3x3x3_kpuzzle(),
+            CosetOptionalArgs {
+                subgroup_moves: Some("R,U".to_owned()),
+                generator_args: generator_args(),
+                memory_args: MemoryArgs {
+                    memory_mebibytes: Some(1),
+                },
+                ..Default::default()
+            },
+        )
+        .is_err());
+    }
+}
@@ -3,13 +3,21 @@ use cubing::kpuzzle::KPuzzle;
 use crate::_internal::{
     cli::args::GodsAlgorithmOptionalArgs,
     errors::CommandError,
-    gods_algorithm::gods_algorithm_table::{GodsAlgorithmSearch, GodsAlgorithmTable},
+    gods_algorithm::{
+        disk_backed_bfs::DiskBackedOptions,
+        gods_algorithm_table::{
+            GodsAlgorithmSearch, GodsAlgorithmSearchConstructionOptions, GodsAlgorithmTable,
+        },
+        progress::{GodsAlgorithmProgressObserver, SilentGodsAlgorithmProgressObserver},
+    },
 };
 
 use super::common::PatternSource;
 
 /// Note: the `gods_algorithm_optional_args` argument is not yet ergonomic, and will be refactored.
 ///
+/// This does not print anything. Use [`gods_algorithm_with_observer`] to receive progress updates.
+///
 /// Usage example:
 ///
 /// ```
@@ -44,6 +52,18 @@ use super::common::PatternSource;
 pub fn gods_algorithm(
     kpuzzle: &KPuzzle,
     gods_algorithm_optional_args: GodsAlgorithmOptionalArgs,
+) -> Result<GodsAlgorithmTable, CommandError> {
+    gods_algorithm_with_observer(
+        kpuzzle,
+        gods_algorithm_optional_args,
+        &mut SilentGodsAlgorithmProgressObserver,
+    )
+}
+
+pub fn gods_algorithm_with_observer(
+    kpuzzle: &KPuzzle,
+    gods_algorithm_optional_args: GodsAlgorithmOptionalArgs,
+    observer: &mut dyn GodsAlgorithmProgressObserver,
 ) -> Result<GodsAlgorithmTable, CommandError> {
     let start_pattern = match gods_algorithm_optional_args
         .start_pattern_args
@@ -55,20 +75,52 @@ pub fn gods_algorithm(
     let mut gods_algorithm_search = GodsAlgorithmSearch::try_new(
         kpuzzle.clone(),
         start_pattern,
-        &gods_algorithm_optional_args.generator_args.parse(),
+        &gods_algorithm_optional_args.generator_args.parse()?,
         &gods_algorithm_optional_args.metric_args.metric,
+        GodsAlgorithmSearchConstructionOptions {
+            checkpoint_dir: gods_algorithm_optional_args.checkpoint_dir,
+            resume: gods_algorithm_optional_args.resume,
+            disk_backed: gods_algorithm_optional_args.disk_backed.then(|| {
+                let defaults = DiskBackedOptions::default();
+                DiskBackedOptions {
+                    temp_dir: gods_algorithm_optional_args
+                        .temp_dir
+                        .unwrap_or(defaults.temp_dir),
+                    memory_mebibytes: gods_algorithm_optional_args
+                        .performance_args
+                        .memory_args
+                        .memory_mebibytes
+                        .unwrap_or(defaults.memory_mebibytes),
+                }
+            }),
+            symmetry_reduced: gods_algorithm_optional_args.symmetry_reduced,
+        },
     )?;
-    gods_algorithm_search.fill();
+    gods_algorithm_search.fill_with_observer(observer)?;
     Ok(gods_algorithm_search.table)
 }
 
 #[cfg(test)]
 mod tests {
-    use cubing::puzzles::cube3x3x3_kpuzzle;
+    use std::{
+        ops::Deref,
+        path::{Path, PathBuf},
+    };
+
+    use cubing::{
+        alg::Alg,
+        puzzles::{cube2x2x2_kpuzzle, cube3x3x3_kpuzzle},
+    };
 
     use crate::{
-        _internal::cli::args::{GeneratorArgs, GodsAlgorithmOptionalArgs},
-        experimental_lib_api::gods_algorithm,
+        _internal::{
+            cli::args::{GeneratorArgs, GodsAlgorithmOptionalArgs},
+            gods_algorithm::{
+                gods_algorithm_table::GodsAlgorithmTable, packed_pattern::num_pattern_bytes,
+                progress::GodsAlgorithmProgressObserver,
+            },
+        },
+        experimental_lib_api::{gods_algorithm, gods_algorithm_with_observer},
     };
 
     #[test]
@@ -85,5 +137,222 @@ mod tests {
         )
         .unwrap();
         assert_eq!(table.pattern_to_depth.len(), 12);
+        assert_eq!(table.depth_counts(), &[1, 2, 2, 2, 2, 2, 1]);
+        assert_eq!(table.max_depth(), Some(6));
+    }
+
+    /// A unique path in the temp dir that is removed when dropped, including
+    /// when an assertion fails. Tests can run in parallel (including across
+    /// processes), so each one gets its own path.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!(
+                "{}-{}-{}",
+                name,
+                std::process::id(),
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|duration| duration.as_nanos())
+                    .unwrap_or_default()
+            )))
+        }
+    }
+
+    impl Deref for TempPath {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl AsRef<Path> for TempPath {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            if self.0.is_dir() {
+                let _ = std::fs::remove_dir_all(&self.0);
+            } else {
+                let _ = std::fs::remove_file(&self.0);
+            }
+        }
+    }
+
+    #[test]
+    fn gods_algorithm_table_save_load_solve_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let table = gods_algorithm(
+            kpuzzle,
+            GodsAlgorithmOptionalArgs {
+                generator_args: GeneratorArgs {
+                    generator_moves_string: Some("R2,U2".to_owned()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let path = TempPath::new("twsearch-gods-algorithm-table-test.bin");
+        table.save(&path).unwrap();
+        let loaded_table = GodsAlgorithmTable::load(kpuzzle, &path).unwrap();
+        // Tables can only be loaded for the same puzzle definition.
+        assert!(GodsAlgorithmTable::load(cube2x2x2_kpuzzle(), &path).is_err());
+        // Corrupt the first piece of the last entry.
+        let mut bytes = std::fs::read(&path).unwrap();
+        let num_entry_bytes = num_pattern_bytes(kpuzzle) + 1;
+        let corrupted_byte_index = bytes.len() - num_entry_bytes;
+        bytes[corrupted_byte_index] = 0xFF;
+        std::fs::write(&path, bytes).unwrap();
+        assert!(GodsAlgorithmTable::load(kpuzzle, &path).is_err());
+        assert!(loaded_table.completed());
+        assert_eq!(loaded_table.pattern_to_depth, table.pattern_to_depth);
+        assert_eq!(loaded_table.depth_counts(), table.depth_counts());
+
+        let pattern = kpuzzle
+            .default_pattern()
+            .apply_alg(&"R2 U2 R2 U2 R2".parse::<Alg>().unwrap())
+            .unwrap();
+        let solution = loaded_table.solve(&pattern).unwrap();
+        assert_eq!(solution.nodes.len(), 5);
+        assert_eq!(
+            pattern.apply_alg(&solution).unwrap(),
+            kpuzzle.default_pattern()
+        );
+
+        // Disk-backed tables only keep the start pattern in memory.
+        let disk_backed_table = gods_algorithm(
+            kpuzzle,
+            GodsAlgorithmOptionalArgs {
+                generator_args: GeneratorArgs {
+                    generator_moves_string: Some("R2,U2".to_owned()),
+                    ..Default::default()
+                },
+                disk_backed: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(disk_backed_table.disk_backed());
+        assert!(disk_backed_table.save(&path).is_err());
+        assert!(disk_backed_table
+            .solve(&pattern)
+            .unwrap_err()
+            .description
+            .starts_with("Tables from disk-backed enumeration"));
+    }
+
+    #[test]
+    fn gods_algorithm_observer_test() {
+        #[derive(Default)]
+        struct DepthRecorder {
+            completed_depths: Vec<(usize, usize)>,
+        }
+
+        impl GodsAlgorithmProgressObserver for DepthRecorder {
+            fn depth_completed(
+                &mut self,
+                depth: usize,
+                num_patterns_at_depth: usize,
+                _num_patterns_total: usize,
+            ) {
+                self.completed_depths.push((depth, num_patterns_at_depth));
+            }
+        }
+
+        let mut depth_recorder = DepthRecorder::default();
+        gods_algorithm_with_observer(
+            cube3x3x3_kpuzzle(),
+            GodsAlgorithmOptionalArgs {
+                generator_args: GeneratorArgs {
+                    generator_moves_string: Some("R2,U2".to_owned()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            &mut depth_recorder,
+        )
+        .unwrap();
+        assert_eq!(
+            depth_recorder.completed_depths,
+            vec![(1, 2), (2, 2), (3, 2), (4, 2), (5, 2), (6, 1), (7, 0)]
+        );
+    }
+
+    #[test]
+    fn gods_algorithm_symmetry_reduced_test() {
+        let table = gods_algorithm(
+            cube3x3x3_kpuzzle(),
+            GodsAlgorithmOptionalArgs {
+                generator_args: GeneratorArgs {
+                    generator_moves_string: Some("R2,U2".to_owned()),
+                    ..Default::default()
+                },
+                symmetry_reduced: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(table.depth_counts(), &[1, 2, 2, 2, 2, 2, 1]);
+        let symmetry_reduced_counts = table.symmetry_reduced_counts().unwrap();
+        // The rotation that swaps `R` and `U`, and the reflections that fix or swap them.
+        assert_eq!(symmetry_reduced_counts.num_symmetries, 4);
+        assert_eq!(symmetry_reduced_counts.depth_counts, vec![1; 7]);
+        assert!(table.solve(&cube3x3x3_kpuzzle().default_pattern()).is_err());
+        assert!(table
+            .save(&TempPath::new(
+                "twsearch-gods-algorithm-symmetry-reduced-test.bin"
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn gods_algorithm_resume_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let checkpoint_dir = TempPath::new("twsearch-gods-algorithm-resume-test");
+        let args = || GodsAlgorithmOptionalArgs {
+            generator_args: GeneratorArgs {
+                generator_moves_string: Some("R2,U2".to_owned()),
+                ..Default::default()
+            },
+            checkpoint_dir: Some(checkpoint_dir.to_path_buf()),
+            resume: true,
+            ..Default::default()
+        };
+        let table = gods_algorithm(kpuzzle, args()).unwrap();
+
+        // Simulate an interruption right after depth 2 was completed.
+        let metadata_path = checkpoint_dir.join("checkpoint.json");
+        let mut metadata: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&metadata_path).unwrap()).unwrap();
+        metadata["lastCompletedDepth"] = 2.into();
+        metadata["numPatternsTotal"] = table
+            .pattern_to_depth
+            .values()
+            .filter(|depth| **depth <= 2)
+            .count()
+            .into();
+        std::fs::write(&metadata_path, metadata.to_string()).unwrap();
+
+        let resumed_table = gods_algorithm(kpuzzle, args()).unwrap();
+        assert!(resumed_table.completed());
+        assert_eq!(resumed_table.pattern_to_depth, table.pattern_to_depth);
+
+        // A checkpoint cannot be resumed from a different start pattern.
+        let start_pattern_path = checkpoint_dir.join("start-pattern.txt");
+        std::fs::write(&start_pattern_path, "R2\n").unwrap();
+        let mut different_start_args = args();
+        different_start_args.start_pattern_args.start_pattern = Some(start_pattern_path);
+        assert!(gods_algorithm(kpuzzle, different_start_args)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("start pattern"));
     }
 }
@@ -4,13 +4,27 @@
 
 mod common;
 pub use common::KPuzzleSource; // TODO
-pub use common::PatternSource; // TODO
+pub use common::{NamedPattern, PatternSource}; // TODO
 
 mod search_api;
-pub use search_api::search;
+pub use search_api::{search, search_batch, SearchSession};
+mod search_options;
+pub use search_options::{
+    CanonicalFSMOptions, CheckBeforeSolve, PatternSearchOptions, SearchMetric, SearchOptions,
+    SearchVerbosity,
+};
 
 mod gods_algorithm_api;
-pub use gods_algorithm_api::gods_algorithm;
+pub use gods_algorithm_api::{gods_algorithm, gods_algorithm_with_observer};
+
+mod coset_api;
+pub use coset_api::{coset, coset_with_observer};
+
+mod schreier_sims_api;
+pub use schreier_sims_api::schreier_sims;
+
+mod random_pattern_sampler;
+pub use random_pattern_sampler::RandomPatternSampler;
 
 mod multi_phase_search;
 pub use multi_phase_search::{
@@ -173,18 +173,18 @@ impl<TPuzzle: SemiGroupActionPuzzle> MultiPhaseSearch<TPuzzle> {
                 .write_info(&format!("Starting phase: {}", phase.phase_name()));
 
             // TODO: can we avoid clones?
-            let Some(phase_search_pattern) = apply_flat_alg(
+            let phase_search_pattern = apply_flat_alg(
                 &self.tpuzzle,
                 search_pattern.clone(),
                 &current_solution.clone().unwrap_or_default(),
-            ) else {
-                return Err(SearchError {
-                    description: format!(
-                        "Could not apply alg to search pattern for phase: {}",
-                        phase.phase_name()
-                    ),
-                });
-            };
+            )
+            .map_err(|e| SearchError {
+                description: format!(
+                    "Could not apply alg to search pattern for phase {}: {}",
+                    phase.phase_name(),
+                    e.description
+                ),
+            })?;
 
             // dbg!(&phase_search_pattern);
             self.search_logger
@@ -213,7 +213,8 @@ impl<TPuzzle: SemiGroupActionPuzzle> MultiPhaseSearch<TPuzzle> {
                 }),
             };
         }
-        Ok(current_solution.expect("No phase solutions?"))
+        // With no phases, the pattern is left as-is.
+        Ok(current_solution.unwrap_or_default())
     }
 }
 
@@ -221,19 +222,36 @@ fn apply_flat_alg<TPuzzle: SemiGroupActionPuzzle>(
     tpuzzle: &TPuzzle,
     pattern: TPuzzle::Pattern,
     alg: &Alg,
-) -> Option<TPuzzle::Pattern> {
+) -> Result<TPuzzle::Pattern, SearchError> {
     let mut pattern = pattern;
-    for r#move in alg.nodes.iter() {
-        match r#move {
+    for node in alg.nodes.iter() {
+        match node {
             AlgNode::MoveNode(r#move) => {
-                let transformation = tpuzzle.puzzle_transformation_from_move(r#move).ok()?;
-                pattern = tpuzzle.pattern_apply_transformation(&pattern, &transformation)?;
+                let transformation =
+                    tpuzzle
+                        .puzzle_transformation_from_move(r#move)
+                        .map_err(|_| SearchError {
+                            description: format!("Move `{}` is not valid for the puzzle.", r#move),
+                        })?;
+                let Some(next_pattern) =
+                    tpuzzle.pattern_apply_transformation(&pattern, &transformation)
+                else {
+                    return Err(SearchError {
+                        description: format!("Move `{}` could not be applied.", r#move),
+                    });
+                };
+                pattern = next_pattern;
             }
             AlgNode::PauseNode(_) => {}
-            _ => todo!(
-                "Phase algs with nodes other than `Move`s and `Pause`s are not currently supported"
-            ),
+            _ => {
+                return Err(SearchError {
+                    description: format!(
+                        "Phase solutions must only contain moves and pauses, but found: {}",
+                        node
+                    ),
+                })
+            }
         }
     }
-    Some(pattern)
+    Ok(pattern)
 }
@@ -0,0 +1,87 @@
+use cubing::kpuzzle::{KPattern, KTransformation};
+use rand::thread_rng;
+
+use crate::_internal::schreier_sims::stabilizer_chain::StabilizerChain;
+
+/// Samples uniformly random patterns that are reachable from a start pattern using the given generators.
+///
+/// This builds a stabilizer chain once, so that no puzzle-specific
+/// randomization (parity or orientation constraints) is needed. Sampling a
+/// uniformly random group element and applying it to the start pattern results
+/// in a uniformly random reachable pattern, even if the start pattern has
+/// identical pieces or orientation wildcards.
+///
+/// ```
+/// use cubing::puzzles::cube2x2x2_kpuzzle;
+/// use twsearch::experimental_lib_api::RandomPatternSampler;
+///
+/// let kpuzzle = cube2x2x2_kpuzzle();
+/// let generators = ["R", "U"].map(|r#move| {
+///     kpuzzle
+///         .transformation_from_move(&r#move.parse().unwrap())
+///         .unwrap()
+/// });
+/// let random_pattern_sampler = RandomPatternSampler::new(&kpuzzle.default_pattern(), &generators);
+/// dbg!(random_pattern_sampler.random_pattern());
+/// ```
+pub struct RandomPatternSampler {
+    start_pattern: KPattern,
+    stabilizer_chain: StabilizerChain,
+}
+
+impl RandomPatternSampler {
+    pub fn new(start_pattern: &KPattern, generators: &[KTransformation]) -> Self {
+        Self {
+            start_pattern: start_pattern.clone(),
+            stabilizer_chain: StabilizerChain::new(start_pattern.kpuzzle(), generators),
+        }
+    }
+
+    /// A uniformly random element of the group generated by the generators.
+    pub fn random_transformation(&self) -> KTransformation {
+        self.stabilizer_chain
+            .random_transformation(&mut thread_rng())
+    }
+
+    pub fn random_pattern(&self) -> KPattern {
+        self.start_pattern
+            .apply_transformation(&self.random_transformation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use cubing::{kpuzzle::KTransformation, puzzles::cube2x2x2_kpuzzle};
+
+    use crate::experimental_lib_api::RandomPatternSampler;
+
+    #[test]
+    fn random_pattern_sampler_test() {
+        let kpuzzle = cube2x2x2_kpuzzle();
+        let generators: Vec<KTransformation> = ["R2", "U2"]
+            .iter()
+            .map(|r#move| {
+                kpuzzle
+                    .transformation_from_move(&r#move.parse().unwrap())
+                    .unwrap()
+            })
+            .collect();
+        let random_pattern_sampler =
+            RandomPatternSampler::new(&kpuzzle.default_pattern(), &generators);
+
+        // `<R2, U2>` has 6 patterns on the 2x2x2. The chance of missing one of them in 300 samples is negligible.
+        let mut seen = HashSet::new();
+        for _ in 0..300 {
+            seen.insert(random_pattern_sampler.random_pattern());
+        }
+        assert_eq!(seen.len(), 6);
+        assert!(seen.contains(&kpuzzle.default_pattern()));
+        assert!(seen.contains(
+            &kpuzzle
+                .default_pattern()
+                .apply_transformation(&generators[0])
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+use cubing::kpuzzle::{KPuzzle, KTransformation};
+
+use crate::_internal::schreier_sims::group_order::GroupOrder;
+
+/// Calculates the order of the group generated by the moves of the definition, using the Schreier–Sims algorithm.
+///
+/// Identical pieces and orientation wildcards in the default pattern are
+/// accounted for in the number of reachable patterns (`num_patterns`).
+pub fn schreier_sims(kpuzzle: &KPuzzle) -> GroupOrder {
+    let generators: Vec<KTransformation> = kpuzzle
+        .definition()
+        .moves
+        .keys()
+        .map(|r#move| {
+            kpuzzle
+                .transformation_from_move(r#move)
+                .expect("Internal error: could not look up a move from the definition.")
+        })
+        .collect();
+    GroupOrder::new(kpuzzle, &generators, &kpuzzle.default_pattern())
+}
+
+#[cfg(test)]
+mod tests {
+    use cubing::{
+        kpuzzle::{KPuzzle, KTransformation, OrientationWithMod},
+        puzzles::{cube2x2x2_kpuzzle, cube3x3x3_kpuzzle},
+    };
+
+    use crate::{
+        _internal::{ksolve::parse_ksolve_definition, schreier_sims::group_order::GroupOrder},
+        experimental_lib_api::schreier_sims,
+    };
+
+    #[test]
+    fn schreier_sims_test() {
+        // The 2x2x2 definition includes whole-puzzle rotations, so all 8! × 3⁷ corner patterns are reachable.
+        let group_order = schreier_sims(cube2x2x2_kpuzzle());
+        assert_eq!(group_order.total.to_decimal_string(), "88179840");
+        assert_eq!(group_order.total.to_string(), "2⁷ × 3⁹ × 5¹ × 7¹");
+        assert_eq!(group_order.num_patterns, group_order.total);
+
+        let group_order = schreier_sims(cube3x3x3_kpuzzle());
+        let edges = group_order
+            .orbits
+            .iter()
+            .find(|orbit_group_order| orbit_group_order.orbit_name == "EDGES")
+            .unwrap();
+        // 12! × 2¹¹
+        assert_eq!(edges.order.to_decimal_string(), "980995276800");
+    }
+
+    #[test]
+    fn schreier_sims_identical_pieces_test() {
+        let kpuzzle = cube2x2x2_kpuzzle();
+        let generators: Vec<KTransformation> = ["U", "x", "y"]
+            .iter()
+            .map(|r#move| {
+                kpuzzle
+                    .transformation_from_move(&r#move.parse().unwrap())
+                    .unwrap()
+            })
+            .collect();
+        let orbit_info = kpuzzle.orbit_info_iter().next().unwrap();
+
+        // Only distinguish the top and bottom layers: 8! × 3⁷ / (4! × 4!)
+        let mut two_colors = kpuzzle.default_pattern();
+        for i in 0..8 {
+            two_colors.set_piece(orbit_info, i, i / 4);
+        }
+        let group_order = GroupOrder::new(kpuzzle, &generators, &two_colors);
+        assert_eq!(group_order.total.to_decimal_string(), "88179840");
+        assert_eq!(group_order.num_patterns.to_decimal_string(), "153090");
+
+        // Ignore orientation: 8!
+        let mut ignore_orientation = kpuzzle.default_pattern();
+        for i in 0..8 {
+            ignore_orientation.set_orientation_with_mod(
+                orbit_info,
+                i,
+                &OrientationWithMod {
+                    orientation: 0,
+                    orientation_mod: 1,
+                },
+            );
+        }
+        let group_order = GroupOrder::new(kpuzzle, &generators, &ignore_orientation);
+        assert_eq!(group_order.num_patterns.to_decimal_string(), "40320");
+    }
+
+    #[test]
+    fn schreier_sims_ksolve_test() {
+        let def = parse_ksolve_definition(include_str!("../../../samples/main/2x2x2.tws")).unwrap();
+        let kpuzzle = KPuzzle::try_from(def).unwrap();
+        assert_eq!(
+            schreier_sims(&kpuzzle).total.to_decimal_string(),
+            "88179840"
+        );
+    }
+
+    #[test]
+    fn schreier_sims_5x5x5_test() {
+        // The center orbits interleave identical pieces, e.g. `1 1 2 3 1 2 3 4 …`.
+        let def = parse_ksolve_definition(include_str!("../../../samples/main/5x5x5.tws")).unwrap();
+        let kpuzzle = KPuzzle::try_from(def).unwrap();
+        let group_order = schreier_sims(&kpuzzle);
+        assert_eq!(
+            group_order.num_patterns.to_decimal_string(),
+            "282870942277741856536180333107150328293127731985672134721536000000000000000"
+        );
+        let center2 = group_order
+            .orbits
+            .iter()
+            .find(|orbit_group_order| orbit_group_order.orbit_name == "CENTER2")
+            .unwrap();
+        // 24! / 4!⁶
+        assert_eq!(center2.num_patterns.to_decimal_string(), "3246670537110000");
+    }
+}
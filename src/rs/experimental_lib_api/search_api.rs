@@ -1,35 +1,46 @@
-use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::channel,
+        Arc,
+    },
+    thread::{available_parallelism, scope},
+};
 
 use crate::_internal::{
-    cli::args::{SearchCommandOptionalArgs, VerbosityLevel},
-    errors::CommandError,
+    errors::{CommandError, SearchError},
+    puzzle_traits::puzzle_traits::GroupActionPuzzle,
+    reachability::ReachabilityChecker,
     search::{
+        hash_prune_table::PRUNE_TABLE_ENTRY_NUM_BYTES,
         iterative_deepening::iterative_deepening_search::{
-            IndividualSearchOptions, IterativeDeepeningSearch,
-            IterativeDeepeningSearchConstructionOptions, SearchSolutions,
+            IterativeDeepeningSearch, IterativeDeepeningSearchConstructionOptions, SearchSolutions,
         },
         search_logger::SearchLogger,
     },
 };
 use cubing::kpuzzle::{KPattern, KPuzzle};
 
-use super::common::PatternSource;
+use super::{PatternSearchOptions, SearchOptions};
 
-/// Note: the `search_command_optional_args` argument is not yet ergonomic, and will be refactored.
-///
 /// Usage example:
 ///
 /// ```
-/// use cubing::{alg::parse_alg, puzzles::cube3x3x3_kpuzzle};
-/// use twsearch::experimental_lib_api::{search};
+/// use cubing::{alg::{parse_alg, parse_move}, puzzles::cube3x3x3_kpuzzle};
+/// use twsearch::experimental_lib_api::{search, SearchOptions};
 ///
 /// let kpuzzle = cube3x3x3_kpuzzle();
 /// let search_pattern = kpuzzle
 ///     .default_pattern()
 ///     .apply_alg(parse_alg!("R U R'"))
 ///     .expect("Invalid alg for puzzle.");
-/// let solutions =
-///     search(kpuzzle, &search_pattern, Default::default()).expect("Search failed.");
+/// let solutions = search(
+///     kpuzzle,
+///     &search_pattern,
+///     SearchOptions::new().generator_moves(vec![parse_move!("R").clone(), parse_move!("U").clone()]),
+/// )
+/// .expect("Search failed.");
 /// for solution in solutions {
 ///     println!("{}", solution);
 /// }
@@ -37,60 +48,203 @@ use super::common::PatternSource;
 pub fn search(
     kpuzzle: &KPuzzle,
     search_pattern: &KPattern,
-    search_command_optional_args: SearchCommandOptionalArgs,
+    search_options: SearchOptions,
 ) -> Result<SearchSolutions, CommandError> {
-    if search_command_optional_args.search_args.all_optimal {
-        eprintln!("⚠️ --all-optimal was specified, but is not currently implemented. Ignoring.");
-    }
+    let pattern_search_options = search_options.pattern_search_options();
+    let search_session = SearchSession::try_new(kpuzzle, search_options)?;
+    Ok(search_session.search(search_pattern, pattern_search_options)?)
+}
 
-    let target_pattern = match search_command_optional_args
-        .scramble_and_target_pattern_optional_args
-        .experimental_target_pattern
-    {
-        Some(path_buf) => PatternSource::FilePath(path_buf).pattern(kpuzzle)?,
-        None => kpuzzle.default_pattern(),
-    };
+/// Searches for multiple patterns, sharing a single search (and prune table) between them.
+///
+/// The patterns are searched in parallel (see [`SearchOptions::num_threads`]), but
+/// `on_solutions` is called with the index and solutions (or the error from
+/// checking the pattern) of each pattern in the original order.
+pub fn search_batch(
+    kpuzzle: &KPuzzle,
+    search_patterns: &[KPattern],
+    search_options: SearchOptions,
+    mut on_solutions: impl FnMut(usize, Result<SearchSolutions, SearchError>),
+) -> Result<(), CommandError> {
+    let pattern_search_options = search_options.pattern_search_options();
+    let num_threads = search_options
+        .num_threads
+        .unwrap_or_else(|| available_parallelism().map_or(1, |n| n.get()))
+        .clamp(1, search_patterns.len().max(1));
+    let search_session = SearchSession::try_new(kpuzzle, search_options)?;
 
-    let mut iterative_deepening_search = <IterativeDeepeningSearch<KPuzzle>>::try_new(
-        kpuzzle.clone(),
-        search_command_optional_args
-            .generator_args
-            .parse()
-            .enumerate_moves_for_kpuzzle(kpuzzle),
-        vec![target_pattern], // TODO: support multiple target patterns in API
-        IterativeDeepeningSearchConstructionOptions {
-            search_logger: Arc::new(SearchLogger {
-                verbosity: search_command_optional_args
-                    .verbosity_args
-                    .verbosity
-                    .unwrap_or(VerbosityLevel::Error),
-            }),
-            metric: search_command_optional_args.metric_args.metric,
-            random_start: search_command_optional_args.search_args.random_start,
-            ..Default::default()
-        },
-    )?;
-
-    let solutions = iterative_deepening_search.search(
-        search_pattern,
-        IndividualSearchOptions {
-            min_num_solutions: search_command_optional_args.min_num_solutions,
-            min_depth: search_command_optional_args.search_args.min_depth,
-            max_depth: search_command_optional_args.search_args.max_depth,
-            ..Default::default()
-        },
-    );
+    let next_index = AtomicUsize::new(0);
+    let (sender, receiver) = channel::<(usize, Result<SearchSolutions, SearchError>)>();
+    scope(|scope| {
+        for _ in 0..num_threads {
+            let sender = sender.clone();
+            let search_session = &search_session;
+            let pattern_search_options = &pattern_search_options;
+            let next_index = &next_index;
+            scope.spawn(move || loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(search_pattern) = search_patterns.get(index) else {
+                    break;
+                };
+                let solutions =
+                    search_session.search(search_pattern, pattern_search_options.clone());
+                if sender.send((index, solutions)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
 
-    Ok(solutions)
+        // Results can arrive out of order, so we hold on to them until all earlier ones have been reported.
+        let mut pending = BTreeMap::<usize, Result<SearchSolutions, SearchError>>::new();
+        let mut next_index_to_report = 0;
+        for (index, solutions) in receiver {
+            pending.insert(index, solutions);
+            while let Some(solutions) = pending.remove(&next_index_to_report) {
+                on_solutions(next_index_to_report, solutions);
+                next_index_to_report += 1;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Builds the search data (including the prune table) for a puzzle once, and
+/// then accepts any number of searches.
+///
+/// The prune table is shared between searches and is only extended when a
+/// search needs a deeper table. A session can be shared between threads (e.g.
+/// using an `Arc`) to run searches concurrently.
+///
+/// ```
+/// use cubing::{alg::parse_alg, puzzles::cube3x3x3_kpuzzle};
+/// use twsearch::experimental_lib_api::SearchSession;
+///
+/// let kpuzzle = cube3x3x3_kpuzzle();
+/// let search_session =
+///     SearchSession::try_new(kpuzzle, Default::default()).expect("Invalid search options.");
+/// for alg in [parse_alg!("R U R'"), parse_alg!("F2 L")] {
+///     let search_pattern = kpuzzle
+///         .default_pattern()
+///         .apply_alg(alg)
+///         .expect("Invalid alg for puzzle.");
+///     let mut solutions = search_session
+///         .search(&search_pattern, Default::default())
+///         .expect("Unsolvable pattern.");
+///     println!("{}", solutions.next().expect("No solution found."));
+/// }
+/// ```
+pub struct SearchSession {
+    iterative_deepening_search: IterativeDeepeningSearch<KPuzzle>,
+    target_patterns: Vec<KPattern>,
+    reachability_checker: Option<ReachabilityChecker>,
+}
+
+impl SearchSession {
+    pub fn try_new(kpuzzle: &KPuzzle, search_options: SearchOptions) -> Result<Self, CommandError> {
+        let generator_moves = search_options
+            .generator_moves
+            .unwrap_or_else(|| kpuzzle.puzzle_definition_all_moves());
+        let target_patterns = if search_options.target_patterns.is_empty() {
+            vec![kpuzzle.default_pattern()]
+        } else {
+            search_options.target_patterns
+        };
+        let reachability_checker = ReachabilityChecker::try_from_check_before_solve(
+            kpuzzle,
+            &generator_moves,
+            Some(&search_options.check_before_solve.into()),
+        )?;
+
+        let iterative_deepening_search = <IterativeDeepeningSearch<KPuzzle>>::try_new(
+            kpuzzle.clone(),
+            generator_moves,
+            target_patterns.clone(),
+            IterativeDeepeningSearchConstructionOptions {
+                search_logger: Arc::new(SearchLogger {
+                    verbosity: search_options.verbosity.into(),
+                }),
+                metric: search_options.metric.into(),
+                random_start: search_options.random_start,
+                min_prune_table_size: search_options.memory_mebibytes.map(|memory_mebibytes| {
+                    largest_power_of_two_at_most(
+                        (memory_mebibytes << 20) / PRUNE_TABLE_ENTRY_NUM_BYTES,
+                    )
+                }),
+                canonical_fsm_construction_options: search_options.canonical_fsm_options.into(),
+            },
+        )?;
+        Ok(Self {
+            iterative_deepening_search,
+            target_patterns,
+            reachability_checker,
+        })
+    }
+
+    /// The search runs on the calling thread, and all solutions are found before this returns.
+    ///
+    /// Unless [`SearchOptions::check_before_solve`] is `never`, this returns an error (instead
+    /// of searching indefinitely) if the pattern is known to be unsolvable.
+    pub fn search(
+        &self,
+        search_pattern: &KPattern,
+        pattern_search_options: PatternSearchOptions,
+    ) -> Result<SearchSolutions, SearchError> {
+        if let Some(reachability_checker) = &self.reachability_checker {
+            // The pattern only needs to be able to reach one of the target patterns.
+            let mut first_error = None;
+            for target_pattern in &self.target_patterns {
+                match reachability_checker.check(search_pattern, target_pattern) {
+                    Ok(()) => {
+                        first_error = None;
+                        break;
+                    }
+                    Err(e) => {
+                        first_error.get_or_insert(e);
+                    }
+                }
+            }
+            if let Some(e) = first_error {
+                return Err(e);
+            }
+        }
+        Ok(self
+            .iterative_deepening_search
+            .search(search_pattern, pattern_search_options.into()))
+    }
+}
+
+fn largest_power_of_two_at_most(n: usize) -> usize {
+    match n {
+        0 => 0,
+        n => 1 << (usize::BITS - 1 - n.leading_zeros()),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use cubing::{alg::parse_alg, puzzles::cube3x3x3_kpuzzle};
+    use cubing::{
+        alg::{parse_alg, parse_move},
+        puzzles::cube3x3x3_kpuzzle,
+    };
 
     use crate::{
-        _internal::cli::args::{GeneratorArgs, SearchCommandOptionalArgs},
-        experimental_lib_api::search,
+        _internal::{
+            cli::args::{
+                CommonSearchArgs, EnableAutoAlwaysNeverValueEnum, MemoryArgs, PerformanceArgs,
+                ScrambleAndTargetPatternOptionalArgs, SearchCommandOptionalArgs,
+            },
+            cycle_notation::parse_pattern_cycle_notation,
+            errors::CommandError,
+            facelets::facelets_from_pattern,
+            puzzle_traits::puzzle_traits::GroupActionPuzzle,
+            reachability::ReachabilityChecker,
+        },
+        experimental_lib_api::{
+            search, search_batch, CanonicalFSMOptions, PatternSearchOptions, PatternSource,
+            SearchOptions, SearchSession,
+        },
+        scramble::{embedded_facelet_mapping, embedded_kpuzzle},
     };
 
     #[test]
@@ -107,15 +261,162 @@ mod tests {
         let mut solutions = search(
             kpuzzle,
             &search_pattern,
-            SearchCommandOptionalArgs {
-                generator_args: GeneratorArgs {
-                    generator_moves_string: Some("R,U".to_owned()), // TODO: make this semantic
+            SearchOptions::new()
+                .generator_moves(vec![parse_move!("R").clone(), parse_move!("U").clone()]),
+        )
+        .unwrap();
+        assert_eq!(solutions.next().unwrap().nodes.len(), 3);
+
+        // `R L' x'` has the same effect as `M`, so it is pruned. The optimal solution must still be found.
+        let search_pattern = kpuzzle
+            .default_pattern()
+            .apply_alg(parse_alg!("R U M' x"))
+            .expect("Invalid alg for puzzle.");
+        let mut solutions = search(
+            kpuzzle,
+            &search_pattern,
+            SearchOptions::new()
+                .generator_moves(vec![
+                    parse_move!("R").clone(),
+                    parse_move!("L").clone(),
+                    parse_move!("U").clone(),
+                    parse_move!("M").clone(),
+                    parse_move!("x").clone(),
+                ])
+                .canonical_fsm_options(CanonicalFSMOptions {
+                    max_relation_length: Some(3),
                     ..Default::default()
-                },
-                ..Default::default()
+                }),
+        )
+        .unwrap();
+        assert_eq!(solutions.next().unwrap().nodes.len(), 4);
+    }
+
+    #[test]
+    fn search_batch_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let search_patterns: Vec<_> = [parse_alg!("R U R'"), parse_alg!("F"), parse_alg!("R U")]
+            .iter()
+            .map(|alg| {
+                kpuzzle
+                    .default_pattern()
+                    .apply_alg(alg)
+                    .expect("Invalid alg for puzzle.")
+            })
+            .collect();
+        let mut solution_lengths = vec![];
+        search_batch(
+            kpuzzle,
+            &search_patterns,
+            SearchOptions::new().num_threads(2),
+            |index, solutions| {
+                solution_lengths.push((index, solutions.unwrap().next().unwrap().nodes.len()))
             },
         )
         .unwrap();
-        assert_eq!(solutions.next().unwrap().nodes.len(), 3);
+        assert_eq!(solution_lengths, vec![(0, 3), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn search_session_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let search_session = SearchSession::try_new(kpuzzle, Default::default()).unwrap();
+        std::thread::scope(|scope| {
+            for (alg, expected_length) in [(parse_alg!("R U R' F2"), 4), (parse_alg!("L"), 1)] {
+                let search_session = &search_session;
+                scope.spawn(move || {
+                    let search_pattern = kpuzzle.default_pattern().apply_alg(alg).unwrap();
+                    let mut solutions = search_session
+                        .search(&search_pattern, Default::default())
+                        .unwrap();
+                    assert_eq!(solutions.next().unwrap().nodes.len(), expected_length);
+                });
+            }
+        });
+
+        // The maximum depth is exclusive.
+        let search_pattern = kpuzzle
+            .default_pattern()
+            .apply_alg(parse_alg!("R U"))
+            .unwrap();
+        let mut solutions = search_session
+            .search(&search_pattern, PatternSearchOptions::new().max_depth(2))
+            .unwrap();
+        assert!(solutions.next().is_none());
+    }
+
+    #[test]
+    fn check_before_solve_test() {
+        let kpuzzle = cube3x3x3_kpuzzle();
+        let search_error = |cycles: &str| {
+            let search_pattern = parse_pattern_cycle_notation(kpuzzle, cycles).unwrap();
+            match search(kpuzzle, &search_pattern, Default::default()) {
+                Err(CommandError::SearchError(e)) => e.description,
+                _ => panic!("Expected a search error."),
+            }
+        };
+        assert_eq!(
+            search_error("CORNERS:(0+)"),
+            "The orientation sum of orbit CORNERS is 1 mod 3 (expected 0 mod 3)."
+        );
+        assert_eq!(
+            search_error("EDGES:(0 1)"),
+            // Slice moves and rotations also permute the centers.
+            "The combined permutation parity of orbits EDGES, CORNERS and CENTERS is odd (relative to the target pattern), but every move changes it by an even amount."
+        );
+        // Swapping two corners and two edges is solvable (but takes a while to search for).
+        let search_pattern =
+            parse_pattern_cycle_notation(kpuzzle, "CORNERS:(0 1) EDGES:(0 1)").unwrap();
+        let reachability_checker = ReachabilityChecker::try_from_check_before_solve(
+            kpuzzle,
+            &kpuzzle.puzzle_definition_all_moves(),
+            Some(&EnableAutoAlwaysNeverValueEnum::Always),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(reachability_checker
+            .check(&search_pattern, &kpuzzle.default_pattern())
+            .is_ok());
+    }
+
+    #[test]
+    fn facelets_search_test() {
+        // The centers of a 4x4x4 with the same color cannot be told apart, so the
+        // search must treat them as identical (otherwise, they could be assigned
+        // with the wrong permutation parity).
+        let kpuzzle = embedded_kpuzzle("4x4x4").unwrap();
+        let mapping = embedded_facelet_mapping(kpuzzle).unwrap();
+        for (alg, expected_length) in [(parse_alg!("Rw"), 1), (parse_alg!("Rw U"), 2)] {
+            let facelets = facelets_from_pattern(
+                kpuzzle,
+                mapping,
+                &kpuzzle.default_pattern().apply_alg(alg).unwrap(),
+            )
+            .unwrap();
+            let search_command_optional_args = SearchCommandOptionalArgs {
+                search_args: CommonSearchArgs {
+                    performance_args: PerformanceArgs {
+                        memory_args: MemoryArgs {
+                            memory_mebibytes: Some(64),
+                        },
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                scramble_and_target_pattern_optional_args: ScrambleAndTargetPatternOptionalArgs {
+                    scramble_facelets: Some(facelets.clone()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let search_pattern = PatternSource::Facelets(facelets).pattern(kpuzzle).unwrap();
+            let mut solutions = search(
+                kpuzzle,
+                &search_pattern,
+                SearchOptions::try_from_clap_args(kpuzzle, &search_command_optional_args).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(solutions.next().unwrap().nodes.len(), expected_length);
+        }
     }
 }
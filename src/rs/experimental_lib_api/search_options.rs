@@ -0,0 +1,398 @@
+use std::collections::HashSet;
+
+use cubing::{
+    alg::{Move, QuantumMove},
+    kpuzzle::{KPattern, KPuzzle},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::_internal::{
+    canonical_fsm::canonical_fsm::CanonicalFSMConstructionOptions,
+    cli::args::{
+        EnableAutoAlwaysNeverValueEnum, MetricEnum, ScrambleAndTargetPatternOptionalArgs,
+        SearchCommandOptionalArgs, VerbosityLevel,
+    },
+    errors::CommandError,
+    facelets::merge_look_alike_pieces,
+    search::{
+        iterative_deepening::iterative_deepening_search::IndividualSearchOptions,
+        prune_table_trait::Depth, search_logger::SearchLogger,
+    },
+};
+
+use crate::scramble::embedded_facelet_mapping;
+
+use super::common::PatternSource;
+
+fn target_patterns_from_clap_args(
+    kpuzzle: &KPuzzle,
+    scramble_and_target_pattern_optional_args: &ScrambleAndTargetPatternOptionalArgs,
+) -> Result<Vec<KPattern>, CommandError> {
+    let target_pattern =
+        match &scramble_and_target_pattern_optional_args.experimental_target_pattern {
+            Some(path_buf) => Some(PatternSource::FilePath(path_buf.clone()).pattern(kpuzzle)?),
+            None => None,
+        };
+    // A facelet string cannot tell look-alike pieces apart, so the target pattern must not either.
+    if scramble_and_target_pattern_optional_args
+        .scramble_facelets
+        .is_some()
+    {
+        if let Some(facelet_mapping) = embedded_facelet_mapping(kpuzzle) {
+            let target_pattern = target_pattern.unwrap_or_else(|| kpuzzle.default_pattern());
+            return Ok(vec![merge_look_alike_pieces(
+                kpuzzle,
+                facelet_mapping,
+                &target_pattern,
+            )?]);
+        }
+    }
+    Ok(target_pattern.into_iter().collect())
+}
+
+/// The metric to use for counting moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMetric {
+    /// Each move (including multiples) counts as 1.
+    #[default]
+    Hand,
+    /// Each quantum turn counts as 1.
+    Quantum,
+}
+
+impl From<SearchMetric> for MetricEnum {
+    fn from(metric: SearchMetric) -> Self {
+        match metric {
+            SearchMetric::Hand => MetricEnum::Hand,
+            SearchMetric::Quantum => MetricEnum::Quantum,
+        }
+    }
+}
+
+impl From<&MetricEnum> for SearchMetric {
+    fn from(metric: &MetricEnum) -> Self {
+        match metric {
+            MetricEnum::Hand => SearchMetric::Hand,
+            MetricEnum::Quantum => SearchMetric::Quantum,
+        }
+    }
+}
+
+/// Whether to check that a pattern can be solved before searching it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CheckBeforeSolve {
+    /// Check only when this is cheap and exact for the puzzle.
+    #[default]
+    Auto,
+    Never,
+    Always,
+}
+
+impl From<CheckBeforeSolve> for EnableAutoAlwaysNeverValueEnum {
+    fn from(check_before_solve: CheckBeforeSolve) -> Self {
+        match check_before_solve {
+            CheckBeforeSolve::Auto => EnableAutoAlwaysNeverValueEnum::Auto,
+            CheckBeforeSolve::Never => EnableAutoAlwaysNeverValueEnum::Never,
+            CheckBeforeSolve::Always => EnableAutoAlwaysNeverValueEnum::Always,
+        }
+    }
+}
+
+impl From<&EnableAutoAlwaysNeverValueEnum> for CheckBeforeSolve {
+    fn from(check_before_solve: &EnableAutoAlwaysNeverValueEnum) -> Self {
+        match check_before_solve {
+            EnableAutoAlwaysNeverValueEnum::Auto => CheckBeforeSolve::Auto,
+            EnableAutoAlwaysNeverValueEnum::Never => CheckBeforeSolve::Never,
+            EnableAutoAlwaysNeverValueEnum::Always => CheckBeforeSolve::Always,
+        }
+    }
+}
+
+/// Which messages a search writes to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchVerbosity {
+    Silent,
+    Error,
+    Warning,
+    Info,
+}
+
+impl From<SearchVerbosity> for VerbosityLevel {
+    fn from(verbosity: SearchVerbosity) -> Self {
+        match verbosity {
+            SearchVerbosity::Silent => VerbosityLevel::Silent,
+            SearchVerbosity::Error => VerbosityLevel::Error,
+            SearchVerbosity::Warning => VerbosityLevel::Warning,
+            SearchVerbosity::Info => VerbosityLevel::Info,
+        }
+    }
+}
+
+impl From<VerbosityLevel> for SearchVerbosity {
+    fn from(verbosity: VerbosityLevel) -> Self {
+        match verbosity {
+            VerbosityLevel::Silent => SearchVerbosity::Silent,
+            VerbosityLevel::Error => SearchVerbosity::Error,
+            VerbosityLevel::Warning => SearchVerbosity::Warning,
+            VerbosityLevel::Info => SearchVerbosity::Info,
+        }
+    }
+}
+
+/// Restrictions on the move sequences that are searched (in addition to the canonical ordering of commuting moves).
+#[derive(Debug, Clone, Default)]
+pub struct CanonicalFSMOptions {
+    /// Each entry `(first, second)` forbids moves with these quantums from being adjacent (in either order).
+    pub forbid_adjacent_moves: HashSet<(QuantumMove, QuantumMove)>,
+    /// Each entry `(first, second)` forbids a move with quantum `first` from being followed by a move with quantum `second`.
+    pub forbid_transitions: HashSet<(QuantumMove, QuantumMove)>,
+    /// If set, also prune move sequences up to this length that have the same
+    /// effect as a shorter (or otherwise earlier) move sequence.
+    pub max_relation_length: Option<usize>,
+}
+
+impl From<CanonicalFSMOptions> for CanonicalFSMConstructionOptions {
+    fn from(options: CanonicalFSMOptions) -> Self {
+        Self {
+            forbid_transitions_by_quantums_either_direction: options.forbid_adjacent_moves,
+            forbid_transitions_by_quantums_in_order: options.forbid_transitions,
+            max_relation_length: options.max_relation_length,
+        }
+    }
+}
+
+impl From<CanonicalFSMConstructionOptions> for CanonicalFSMOptions {
+    fn from(options: CanonicalFSMConstructionOptions) -> Self {
+        Self {
+            forbid_adjacent_moves: options.forbid_transitions_by_quantums_either_direction,
+            forbid_transitions: options.forbid_transitions_by_quantums_in_order,
+            max_relation_length: options.max_relation_length,
+        }
+    }
+}
+
+/// Options for a single [`SearchSession::search`](super::SearchSession::search).
+///
+/// These default to the values of the corresponding fields of [`SearchOptions`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternSearchOptions {
+    pub(crate) min_depth: Option<usize>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) min_num_solutions: Option<usize>,
+}
+
+impl PatternSearchOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Inclusive.
+    pub fn min_depth(mut self, min_depth: usize) -> Self {
+        self.min_depth = Some(min_depth);
+        self
+    }
+
+    /// Exclusive.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Defaults to 1.
+    pub fn min_num_solutions(mut self, min_num_solutions: usize) -> Self {
+        self.min_num_solutions = Some(min_num_solutions);
+        self
+    }
+
+    pub fn get_min_num_solutions(&self) -> Option<usize> {
+        self.min_num_solutions
+    }
+}
+
+impl From<PatternSearchOptions> for IndividualSearchOptions {
+    fn from(options: PatternSearchOptions) -> Self {
+        Self {
+            min_num_solutions: options.min_num_solutions,
+            min_depth: options.min_depth.map(Depth),
+            max_depth: options.max_depth.map(Depth),
+            ..Default::default()
+        }
+    }
+}
+
+/// Options for [`search`](super::search), [`search_batch`](super::search_batch), and [`SearchSession`](super::SearchSession).
+///
+/// ```
+/// use cubing::alg::parse_move;
+/// use twsearch::experimental_lib_api::SearchOptions;
+///
+/// let search_options = SearchOptions::new()
+///     .generator_moves(vec![parse_move!("R").clone(), parse_move!("U").clone()])
+///     .max_depth(12)
+///     .min_num_solutions(2);
+/// ```
+#[derive(Debug)]
+pub struct SearchOptions {
+    pub(crate) generator_moves: Option<Vec<Move>>,
+    pub(crate) target_patterns: Vec<KPattern>,
+    pub(crate) metric: SearchMetric,
+    pub(crate) min_depth: Option<Depth>,
+    pub(crate) max_depth: Option<Depth>,
+    pub(crate) min_num_solutions: Option<usize>,
+    pub(crate) random_start: bool,
+    pub(crate) canonical_fsm_options: CanonicalFSMOptions,
+    pub(crate) check_before_solve: CheckBeforeSolve,
+    pub(crate) num_threads: Option<usize>,
+    pub(crate) memory_mebibytes: Option<usize>,
+    pub(crate) verbosity: SearchVerbosity,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            generator_moves: None,
+            target_patterns: vec![],
+            metric: SearchMetric::Hand,
+            min_depth: None,
+            max_depth: None,
+            min_num_solutions: None,
+            random_start: false,
+            canonical_fsm_options: Default::default(),
+            check_before_solve: CheckBeforeSolve::Auto,
+            num_threads: None,
+            memory_mebibytes: None,
+            verbosity: SearchVerbosity::Error,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The moves to search with (all multiples of each move are used). Defaults to all moves of the definition.
+    pub fn generator_moves(mut self, generator_moves: Vec<Move>) -> Self {
+        self.generator_moves = Some(generator_moves);
+        self
+    }
+
+    /// Adds a pattern to solve to. If no target patterns are added, the default pattern of the definition is used.
+    pub fn target_pattern(mut self, target_pattern: KPattern) -> Self {
+        self.target_patterns.push(target_pattern);
+        self
+    }
+
+    /// Defaults to [`SearchMetric::Hand`].
+    pub fn metric(mut self, metric: SearchMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Inclusive.
+    pub fn min_depth(mut self, min_depth: usize) -> Self {
+        self.min_depth = Some(Depth(min_depth));
+        self
+    }
+
+    /// Exclusive.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(Depth(max_depth));
+        self
+    }
+
+    /// Defaults to 1.
+    pub fn min_num_solutions(mut self, min_num_solutions: usize) -> Self {
+        self.min_num_solutions = Some(min_num_solutions);
+        self
+    }
+
+    pub fn random_start(mut self, random_start: bool) -> Self {
+        self.random_start = random_start;
+        self
+    }
+
+    pub fn canonical_fsm_options(mut self, canonical_fsm_options: CanonicalFSMOptions) -> Self {
+        self.canonical_fsm_options = canonical_fsm_options;
+        self
+    }
+
+    /// Defaults to [`CheckBeforeSolve::Auto`].
+    pub fn check_before_solve(mut self, check_before_solve: CheckBeforeSolve) -> Self {
+        self.check_before_solve = check_before_solve;
+        self
+    }
+
+    /// Only used by [`search_batch`](super::search_batch). Defaults to the number of logical CPU cores available.
+    pub fn num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = Some(num_threads);
+        self
+    }
+
+    /// The memory to allocate for the prune table up front. The prune table can still grow beyond this if a search needs a deeper table.
+    pub fn memory_mebibytes(mut self, memory_mebibytes: usize) -> Self {
+        self.memory_mebibytes = Some(memory_mebibytes);
+        self
+    }
+
+    /// Defaults to [`SearchVerbosity::Error`].
+    pub fn verbosity(mut self, verbosity: SearchVerbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// The options that apply to each individual search.
+    pub fn pattern_search_options(&self) -> PatternSearchOptions {
+        PatternSearchOptions {
+            min_depth: self.min_depth.map(|depth| depth.0),
+            max_depth: self.max_depth.map(|depth| depth.0),
+            min_num_solutions: self.min_num_solutions,
+        }
+    }
+
+    pub fn try_from_clap_args(
+        kpuzzle: &KPuzzle,
+        search_command_optional_args: &SearchCommandOptionalArgs,
+    ) -> Result<Self, CommandError> {
+        let search_args = &search_command_optional_args.search_args;
+        let verbosity = search_command_optional_args
+            .verbosity_args
+            .verbosity
+            .unwrap_or(VerbosityLevel::Error);
+        if search_args.all_optimal {
+            SearchLogger { verbosity }.write_warning(
+                "⚠️ --all-optimal was specified, but is not currently implemented. Ignoring.",
+            );
+        }
+        Ok(Self {
+            generator_moves: Some(
+                search_command_optional_args
+                    .generator_args
+                    .parse()?
+                    .enumerate_moves_for_kpuzzle(kpuzzle),
+            ),
+            target_patterns: target_patterns_from_clap_args(
+                kpuzzle,
+                &search_command_optional_args.scramble_and_target_pattern_optional_args,
+            )?,
+            metric: (&search_command_optional_args.metric_args.metric).into(),
+            min_depth: search_args.min_depth,
+            max_depth: search_args.max_depth,
+            min_num_solutions: search_command_optional_args.min_num_solutions,
+            random_start: search_args.random_start,
+            canonical_fsm_options: search_args
+                .canonical_fsm_args
+                .construction_options()?
+                .into(),
+            check_before_solve: search_args
+                .check_before_solve
+                .as_ref()
+                .map(CheckBeforeSolve::from)
+                .unwrap_or_default(),
+            num_threads: search_args.performance_args.num_threads,
+            memory_mebibytes: search_args.performance_args.memory_args.memory_mebibytes,
+            verbosity: verbosity.into(),
+        })
+    }
+}
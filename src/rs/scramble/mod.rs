@@ -6,6 +6,10 @@ mod scramble_search;
 mod puzzle;
 pub use puzzle::{Puzzle, PuzzleError};
 
+pub use puzzles::catalogue::{
+    embedded_facelet_mapping, embedded_kpuzzle, embedded_puzzles, EmbeddedPuzzle,
+};
+
 mod event;
 pub use event::{Event, EventError};
 
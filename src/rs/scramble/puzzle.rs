@@ -26,7 +26,7 @@ pub enum Puzzle {
 }
 
 impl Puzzle {
-    pub fn id(&self) -> &str {
+    pub const fn id(&self) -> &'static str {
         match self {
             Self::Cube3x3x3 => "3x3x3",
             Self::Cube2x2x2 => "2x2x2",
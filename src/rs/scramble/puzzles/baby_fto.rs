@@ -42,7 +42,7 @@ pub fn scramble_baby_fto() -> Alg {
     );
 
     let generator_moves = move_list_from_vec(vec!["U", "L", "F", "R", "BR"]);
-    let mut search = <FilteredSearch>::new(
+    let search = <FilteredSearch>::new(
         IterativeDeepeningSearch::try_new(
             kpuzzle.clone(),
             generator_moves,
@@ -53,6 +53,7 @@ pub fn scramble_baby_fto() -> Alg {
                         QuantumMove::new("L", None),
                         QuantumMove::new("BR", None),
                     )]),
+                    ..Default::default()
                 },
                 ..Default::default()
             },
@@ -0,0 +1,233 @@
+use cubing::{
+    kpuzzle::KPuzzle,
+    puzzles::{cube2x2x2_kpuzzle, cube3x3x3_kpuzzle},
+};
+
+use crate::{
+    _internal::{facelets::FaceletMapping, gods_algorithm::packed_pattern::DefinitionIdentity},
+    scramble::{Puzzle, PuzzleError},
+};
+
+use super::definitions::{
+    baby_fto_kpuzzle, clock_kpuzzle, cube2x2x2_facelet_mapping, cube3x3x3_centerless_kpuzzle,
+    cube3x3x3_facelet_mapping, cube4x4x4_facelet_mapping, cube4x4x4_kpuzzle, cube5x5x5_kpuzzle,
+    cube6x6x6_kpuzzle, cube7x7x7_kpuzzle, fto_kpuzzle, kilominx_kpuzzle, master_tetraminx_kpuzzle,
+    megaminx_kpuzzle, pyraminx_kpuzzle, redi_cube_kpuzzle,
+    skewb_fixed_corner_with_co_tweaks_kpuzzle, skewb_kpuzzle, square0_equatorless_kpuzzle,
+    square1_unbandaged_kpuzzle, tetraminx_kpuzzle,
+};
+
+/// A puzzle definition that is built into the binary.
+pub struct EmbeddedPuzzle {
+    /// Either a [`Puzzle::id()`] or the ID of a variant.
+    pub id: &'static str,
+    pub description: &'static str,
+    kpuzzle_fn: fn() -> &'static KPuzzle,
+}
+
+impl EmbeddedPuzzle {
+    pub fn kpuzzle(&self) -> &'static KPuzzle {
+        (self.kpuzzle_fn)()
+    }
+}
+
+const EMBEDDED_PUZZLES: &[EmbeddedPuzzle] = &[
+    EmbeddedPuzzle {
+        id: Puzzle::Cube2x2x2.id(),
+        description: "2x2x2 cube",
+        kpuzzle_fn: cube2x2x2_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Cube3x3x3.id(),
+        description: "3x3x3 cube",
+        kpuzzle_fn: cube3x3x3_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Cube4x4x4.id(),
+        description: "4x4x4 cube",
+        kpuzzle_fn: cube4x4x4_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Cube5x5x5.id(),
+        description: "5x5x5 cube",
+        kpuzzle_fn: cube5x5x5_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Cube6x6x6.id(),
+        description: "6x6x6 cube",
+        kpuzzle_fn: cube6x6x6_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Cube7x7x7.id(),
+        description: "7x7x7 cube",
+        kpuzzle_fn: cube7x7x7_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Clock.id(),
+        description: "Clock (pin moves like `UR_PLUS_`, and `y2`)",
+        kpuzzle_fn: clock_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Megaminx.id(),
+        description: "Megaminx (face turns)",
+        kpuzzle_fn: megaminx_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Pyraminx.id(),
+        description: "Pyraminx",
+        kpuzzle_fn: pyraminx_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Skewb.id(),
+        description: "Skewb (corner turns, including `R`, `L`, `U`, and `B`)",
+        kpuzzle_fn: skewb_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Square1.id(),
+        description: "Square-1 (bandaging is not enforced, same as `square1-unbandaged`)",
+        kpuzzle_fn: square1_unbandaged_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::FTO.id(),
+        description: "FTO (face turns)",
+        kpuzzle_fn: fto_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::MasterTetraminx.id(),
+        description: "Master Tetraminx",
+        kpuzzle_fn: master_tetraminx_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::Kilominx.id(),
+        description: "Kilominx (face turns)",
+        kpuzzle_fn: kilominx_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::RediCube.id(),
+        description: "Redi Cube (corner turns)",
+        kpuzzle_fn: redi_cube_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: Puzzle::BabyFTO.id(),
+        description: "Baby FTO",
+        kpuzzle_fn: baby_fto_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: "tetraminx",
+        description: "Pyraminx without tips",
+        kpuzzle_fn: tetraminx_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: "3x3x3-centerless",
+        description: "3x3x3 cube without centers (face turns only)",
+        kpuzzle_fn: cube3x3x3_centerless_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: "skewb-fixed-corner-with-co-tweaks",
+        description:
+            "Skewb with a fixed corner (with corner orientation adjusted for scramble finding)",
+        kpuzzle_fn: skewb_fixed_corner_with_co_tweaks_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: "square1-unbandaged",
+        description: "Square-1 without bandaging (any turn of the top or bottom layer is allowed)",
+        kpuzzle_fn: square1_unbandaged_kpuzzle,
+    },
+    EmbeddedPuzzle {
+        id: "square0-equatorless",
+        description: "Square-1 without bandaging or an equator layer",
+        kpuzzle_fn: square0_equatorless_kpuzzle,
+    },
+];
+
+/// All puzzle definitions that are built into the binary, in display order.
+pub fn embedded_puzzles() -> &'static [EmbeddedPuzzle] {
+    EMBEDDED_PUZZLES
+}
+
+pub fn embedded_kpuzzle(puzzle_id: &str) -> Result<&'static KPuzzle, PuzzleError> {
+    match EMBEDDED_PUZZLES
+        .iter()
+        .find(|embedded_puzzle| embedded_puzzle.id == puzzle_id)
+    {
+        Some(embedded_puzzle) => Ok(embedded_puzzle.kpuzzle()),
+        None => Err(PuzzleError {
+            description: format!(
+                "Unknown puzzle ID: {} (run `twsearch list-puzzles` to see the built-in puzzles)",
+                puzzle_id
+            ),
+        }),
+    }
+}
+
+/// The built-in facelet mapping for one of the built-in `2x2x2`, `3x3x3`, and
+/// `4x4x4` definitions. The mappings depend on the piece numbering, so other
+/// definitions are not supported (even if they have the same name).
+pub fn embedded_facelet_mapping(kpuzzle: &KPuzzle) -> Option<&'static FaceletMapping> {
+    let (embedded_kpuzzle, facelet_mapping) = match kpuzzle.definition().name.as_str() {
+        "2x2x2" => (cube2x2x2_kpuzzle(), cube2x2x2_facelet_mapping()),
+        "3x3x3" => (cube3x3x3_kpuzzle(), cube3x3x3_facelet_mapping()),
+        "4x4x4" => (cube4x4x4_kpuzzle(), cube4x4x4_facelet_mapping()),
+        _ => return None,
+    };
+    if DefinitionIdentity::new(kpuzzle) != DefinitionIdentity::new(embedded_kpuzzle) {
+        return None;
+    }
+    Some(facelet_mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scramble::{random_scramble_for_event, Event, Puzzle};
+
+    use super::{embedded_kpuzzle, embedded_puzzles};
+
+    #[test]
+    fn catalogue_test() {
+        for embedded_puzzle in embedded_puzzles() {
+            let kpuzzle = embedded_kpuzzle(embedded_puzzle.id).unwrap();
+            // Make sure the definition is valid.
+            kpuzzle.default_pattern();
+        }
+        // Every puzzle has a built-in definition.
+        for puzzle_id in [
+            "3x3x3",
+            "2x2x2",
+            "4x4x4",
+            "5x5x5",
+            "6x6x6",
+            "7x7x7",
+            "clock",
+            "megaminx",
+            "pyraminx",
+            "skewb",
+            "square1",
+            "fto",
+            "master_tetraminx",
+            "kilominx",
+            "redi_cube",
+            "baby_fto",
+        ] {
+            let puzzle = Puzzle::try_from_id(puzzle_id).unwrap();
+            assert!(embedded_kpuzzle(puzzle.id()).is_ok());
+        }
+        assert!(embedded_kpuzzle("4x4x4x4").is_err());
+    }
+
+    #[test]
+    fn scramble_notation_test() {
+        // Scrambles for these events use the notation of the built-in definition.
+        for event in [
+            Event::ClockSpeedsolving,
+            Event::SkewbSpeedsolving,
+            Event::Square1Speedsolving,
+        ] {
+            let scramble = random_scramble_for_event(event).unwrap();
+            embedded_kpuzzle(event.puzzle().id())
+                .unwrap()
+                .default_pattern()
+                .apply_alg(&scramble)
+                .unwrap();
+        }
+    }
+}
@@ -64,6 +64,7 @@ impl Default for Cube2x2x2ScrambleFinder {
                             QuantumMove::new("L", None),
                             QuantumMove::new("R", None),
                         )]),
+                        ..Default::default()
                     },
                     ..Default::default()
                 },
@@ -3,11 +3,26 @@ use cubing::{
     puzzles::cube3x3x3_kpuzzle,
 };
 
+use crate::_internal::facelets::FaceletMapping;
+
+macro_rules! facelet_mapping_from_json_file {
+    ($visibility:vis, $fn_name: ident, $file:expr) => {
+        $visibility fn $fn_name() -> &'static FaceletMapping {
+            static CELL: std::sync::OnceLock<FaceletMapping> = std::sync::OnceLock::new();
+            CELL.get_or_init(|| serde_json::from_str(include_str!($file)).unwrap())
+        }
+    };
+}
+
 kpuzzle_from_json_file!(pub(crate), cube3x3x3_centerless, "3x3x3-centerless.kpuzzle.json");
 kpattern_from_json_file!(pub(crate), cube3x3x3_centerless_g1_target, "3x3x3-G1-centerless.target-pattern.json", cube3x3x3_centerless_kpuzzle());
 
 kpattern_from_json_file!(pub(crate), cube3x3x3_g1_target, "3x3x3-G1.target-pattern.json", cube3x3x3_kpuzzle());
 
+facelet_mapping_from_json_file!(pub(crate), cube2x2x2_facelet_mapping, "2x2x2.facelets.json");
+facelet_mapping_from_json_file!(pub(crate), cube3x3x3_facelet_mapping, "3x3x3.facelets.json");
+facelet_mapping_from_json_file!(pub(crate), cube4x4x4_facelet_mapping, "4x4x4.facelets.json");
+
 // TODO: Handle a default pattern with indistinguishable pieces on `apply_mask(…)`.
 kpuzzle_from_json_file!(pub(crate), cube4x4x4, "4x4x4.kpuzzle.json");
 kpattern_from_json_file!(pub(crate), cube4x4x4_phase1_target, "4x4x4-Phase1.target.json", cube4x4x4_kpuzzle());
@@ -18,7 +33,7 @@ kpuzzle_from_json_file!(pub(crate), cube6x6x6, "6x6x6.kpuzzle.json");
 kpuzzle_from_json_file!(pub(crate), cube7x7x7, "7x7x7.kpuzzle.json");
 kpuzzle_from_json_file!(pub(crate), skewb_fixed_corner_with_co_tweaks, "skewb-fixed-corner-with-co-tweaks.kpuzzle.json");
 
-// kpuzzle_from_json_file!(pub(crate), tetraminx, "tetraminx.kpuzzle.json");
+kpuzzle_from_json_file!(pub(crate), tetraminx, "tetraminx.kpuzzle.json");
 kpuzzle_from_json_file!(pub(crate), pyraminx, "pyraminx.kpuzzle.json");
 
 kpuzzle_from_json_file!(pub(crate), square1_unbandaged, "square1-unbandaged.kpuzzle.json");
@@ -29,3 +44,13 @@ kpattern_from_json_file!(pub(crate), square1_corners, "square1-corners.mask-patt
 kpuzzle_from_json_file!(pub(crate), square0_equatorless, "square0-equatorless.kpuzzle.json");
 
 kpuzzle_from_json_file!(pub(crate), baby_fto, "baby_fto.kpuzzle.json");
+
+// Converted from the `samples/main/*.tws` definitions (master Tetraminx without the tips of the master Pyraminx).
+kpuzzle_from_json_file!(pub(crate), megaminx, "megaminx.kpuzzle.json");
+kpuzzle_from_json_file!(pub(crate), kilominx, "kilominx.kpuzzle.json");
+kpuzzle_from_json_file!(pub(crate), fto, "fto.kpuzzle.json");
+kpuzzle_from_json_file!(pub(crate), skewb, "skewb.kpuzzle.json");
+kpuzzle_from_json_file!(pub(crate), master_tetraminx, "master_tetraminx.kpuzzle.json");
+
+kpuzzle_from_json_file!(pub(crate), clock, "clock.kpuzzle.json");
+kpuzzle_from_json_file!(pub(crate), redi_cube, "redi_cube.kpuzzle.json");
@@ -1,5 +1,6 @@
 pub mod baby_fto;
 pub mod big_cubes;
+pub mod catalogue;
 pub mod clock;
 pub mod cube2x2x2_scramble_finder;
 pub mod cube4x4x4;